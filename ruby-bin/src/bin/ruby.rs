@@ -1,4 +1,4 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use std::path::Path;
 
 use clap::{App, Arg};
@@ -26,26 +26,34 @@ fn argspec() -> App<'static> {
                 .conflicts_with("PROGRAM_FILENAME")
                 .about("a line of ruby code to be executed, can be specified multiple times"),
         )
+        .arg(
+            Arg::new("x")
+                .short('x')
+                .about("strip off text before #!ruby line"),
+        )
         .arg(Arg::new("PROGRAM_FILENAME").about("path to a ruby file to be executed"))
 }
 
 fn main() -> Result<(), Error> {
     let opts = argspec().get_matches();
     //println!("{:?}", opts);
-    if let Some(path) = opts.value_of("PROGRAM_FILENAME") {
-        let path = Path::new(path);
-        let contents = std::fs::read_to_string(path)?;
-        let input = ruby_parser::lexer::Input::new(&contents);
-        let program = ruby_parser::parse(input);
-
-        // Check for a successful parse
-        if let Ok(program) = program {
-            println!(
-                "AST:\n{:?}\n\nRemaining source:\n----\n{}",
-                program.1, program.0
-            );
+    if let Some(filename) = opts.value_of("PROGRAM_FILENAME") {
+        let path = Path::new(filename);
+        let contents = std::fs::read(path)?;
+        let source = if opts.is_present("x") {
+            ruby_parser::lexer::Source::from_bytes_skipping_leading_garbage(&contents)
         } else {
-            println!("parsing error {:?}", program);
+            ruby_parser::lexer::Source::from_bytes(&contents)
+        }
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut input = source.input();
+        input.metadata.file = Some(filename);
+        match ruby_parser::parse(input) {
+            Ok(program) => println!("AST:\n{:?}", program),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
         }
     }
     Ok(())
@@ -66,5 +74,9 @@ mod test {
         let res = argspec().try_get_matches_from(vec!["ruby", "foo.rb"]);
         assert!(res.is_ok());
         assert_eq!(res.unwrap().value_of("PROGRAM_FILENAME"), Some("foo.rb"));
+
+        // Leading garbage
+        let res = argspec().try_get_matches_from(vec!["ruby", "-x", "foo.rb"]);
+        assert!(res.unwrap().is_present("x"));
     }
 }
@@ -0,0 +1,107 @@
+use super::*;
+
+/// Where a comment appears relative to the node it is attached to
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommentPlacement {
+    /// The comment precedes the node (usually on the lines above it)
+    Leading,
+    /// The comment follows the node (usually at the end of the node's last line)
+    Trailing,
+    /// The comment is within a node that has no children to attach it to (such as an empty body),
+    /// or is on its own line after the node's last child
+    Dangling,
+}
+
+/// A comment along with the node it was attached to
+#[derive(Debug, Copy, Clone)]
+pub struct AttachedComment<'a> {
+    pub comment: &'a Node,
+    pub node: &'a Node,
+    pub placement: CommentPlacement,
+}
+
+/// Associates a program's comments with the nodes nearest to them
+#[derive(Debug, Clone, Default)]
+pub struct CommentMap<'a> {
+    /// Every comment of the program, in order of appearance
+    pub attached: Vec<AttachedComment<'a>>,
+}
+
+impl<'a> CommentMap<'a> {
+    /// Returns the comments that precede the given node
+    pub fn leading(&self, node: &Node) -> Vec<&'a Node> {
+        self.find(node, CommentPlacement::Leading)
+    }
+    /// Returns the comments that follow the given node
+    pub fn trailing(&self, node: &Node) -> Vec<&'a Node> {
+        self.find(node, CommentPlacement::Trailing)
+    }
+    /// Returns the comments within the given node that precede or follow none of its children
+    pub fn dangling(&self, node: &Node) -> Vec<&'a Node> {
+        self.find(node, CommentPlacement::Dangling)
+    }
+    /// Nodes are compared by identity, since distinct nodes may share a span
+    fn find(&self, node: &Node, placement: CommentPlacement) -> Vec<&'a Node> {
+        self.attached
+            .iter()
+            .filter(|attached| std::ptr::eq(attached.node, node) && attached.placement == placement)
+            .map(|attached| attached.comment)
+            .collect()
+    }
+}
+
+impl Program {
+    /// Attaches each of the program's comments to the node nearest to it
+    ///
+    /// Each comment is placed among the children of the innermost node that encloses it. A
+    /// comment trails a preceding child that ends on the line the comment starts on, and otherwise
+    /// leads the child that follows it. A comment that is followed by no child (and doesn't share a
+    /// line with the preceding one) dangles within the enclosing node.
+    pub fn attach_comments(&self) -> CommentMap<'_> {
+        let mut map = CommentMap::default();
+        for comment in &self.comments {
+            let span = comment.span;
+            let mut enclosing = &self.program;
+            let mut children: Vec<&Node> = self
+                .begin_blocks
+                .iter()
+                .chain(self.program.spanned_children())
+                .collect();
+            loop {
+                children.sort_by_key(|child| child.span.start.offset);
+                let inner = children.iter().find(|child| {
+                    child.span.start.offset <= span.start.offset
+                        && span.end.offset <= child.span.end.offset
+                });
+                match inner {
+                    Some(inner) => {
+                        enclosing = inner;
+                        children = inner.spanned_children();
+                    }
+                    None => break,
+                }
+            }
+            let preceding = children
+                .iter()
+                .rev()
+                .find(|child| child.span.end.offset <= span.start.offset);
+            let following = children
+                .iter()
+                .find(|child| child.span.start.offset >= span.end.offset);
+            let (node, placement) = match (preceding, following) {
+                (Some(node), _) if node.span.end.line == span.start.line => {
+                    (*node, CommentPlacement::Trailing)
+                }
+                (_, Some(node)) => (*node, CommentPlacement::Leading),
+                (_, None) => (enclosing, CommentPlacement::Dangling),
+            };
+            map.attached.push(AttachedComment {
+                comment,
+                node,
+                placement,
+            });
+        }
+        map
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Conditional {
     pub kind: ConditionalKind,
     pub cond: Box<Node>,
@@ -8,7 +9,8 @@ pub struct Conditional {
     pub otherwise: Box<Node>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConditionalKind {
     If,
     Unless,
@@ -18,14 +20,16 @@ pub enum ConditionalKind {
     ModifyingUnless,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Case {
     pub expr: Box<Node>,
     pub when: Vec<WhenClause>,
     pub otherwise: Box<Node>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhenClause {
     pub when: Vec<Node>,
     pub then: Box<Node>,
@@ -1,6 +1,7 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
     pub name: String,
     pub kind: IdentifierKind,
@@ -20,14 +21,21 @@ impl From<Identifier> for String {
 
 impl From<Identifier> for Node {
     fn from(v: Identifier) -> Self {
-        Self::Identifier(v)
+        Self::new(NodeKind::Identifier(v))
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IdentifierKind {
     LocalVariable,
     GlobalVariable,
+    /// A numbered reference to a group of the last match (`$1`, `$2`, ...)
+    NthReference,
+    /// A reference to part of the last match (`$&`, `` $` ``, `$'` and `$+`)
+    BackReference,
+    /// A global with special meaning to the interpreter (`$~`, `$!`, `$0`, `$-w`, ...)
+    SpecialVariable,
     ClassVariable,
     InstanceVariable,
     Constant,
@@ -1,8 +1,19 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Interpolated {
     Command(Vec<Node>),
     String(Vec<Node>),
     Symbol(Vec<Node>),
 }
+
+/// Juxtaposed string literals (`"a" 'b'`), which are merged into a single string
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Concatenated {
+    /// The merged `Literal::String` or `Interpolated::String`
+    pub value: Box<Node>,
+    /// The original string literals, in source order
+    pub parts: Vec<Node>,
+}
@@ -1,11 +1,23 @@
-// TODO: add complex, irrational, bigint
-#[derive(Debug, PartialEq)]
+// TODO: add complex, irrational
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Literal {
     Nil,
     Boolean(bool),
-    Integer(isize),
-    Float(f64),
+    Integer(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::integer"))] isize),
+    /// An integer literal beyond the range of an `isize`, as its decimal digits (preceded by `-`
+    /// when negative)
+    BigInteger(String),
+    Float(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::float"))] f64),
     String(String),
+    /// A string literal whose content is not valid UTF-8, such as `"\xff\xfe"`
+    Bytes(Vec<u8>),
+    /// A character literal, such as `?a` or `?\n`
+    Character(String),
+    /// A character literal whose content is not valid UTF-8, such as `?\xff`
+    CharacterBytes(Vec<u8>),
     Symbol(String),
     Command(String),
+    /// A command literal whose content is not valid UTF-8, such as `` `\xff` ``
+    CommandBytes(Vec<u8>),
 }
@@ -1,6 +1,7 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Loop {
     pub kind: LoopKind,
     pub cond: Box<Node>,
@@ -8,11 +9,16 @@ pub struct Loop {
     pub bindings: Option<Vec<Node>>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopKind {
     For,
     While,
     Until,
     ModifyingWhile,
     ModifyingUntil,
+    /// `begin ... end while cond`, which runs its body once before checking the condition
+    PostConditionWhile,
+    /// `begin ... end until cond`, which runs its body once before checking the condition
+    PostConditionUntil,
 }
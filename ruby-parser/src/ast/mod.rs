@@ -1,6 +1,7 @@
 //! Provides the abstract syntax tree
 
 mod binary_op;
+mod comments;
 mod conditional;
 mod expr;
 mod identifier;
@@ -12,10 +13,12 @@ mod method;
 mod node;
 mod object;
 mod program;
+mod span;
 mod statement;
 mod unary_op;
 
 pub use binary_op::*;
+pub use comments::*;
 pub use conditional::*;
 pub use expr::*;
 pub use identifier::*;
@@ -27,5 +30,6 @@ pub use method::*;
 pub use node::*;
 pub use object::*;
 pub use program::*;
+pub use span::*;
 pub use statement::*;
 pub use unary_op::*;
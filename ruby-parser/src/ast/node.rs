@@ -1,8 +1,25 @@
 use super::*;
 use crate::lexer::*;
 
-#[derive(Debug, PartialEq)]
-pub enum Node {
+/// A node of the syntax tree along with the region of source it was parsed from
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub kind: NodeKind,
+    /// Nodes synthesized by the parser (such as a missing `else` branch) have an unset span
+    pub span: Span,
+}
+
+/// Spans are ignored when comparing nodes, so that equivalent programs compare equal
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeKind {
     None,
     Conditional(Conditional),
     LogicalAnd(LogicalAnd),
@@ -11,136 +28,160 @@ pub enum Node {
     Literal(Literal),
     Identifier(Identifier),
     Interpolated(Interpolated),
+    Concatenated(Concatenated),
     BinaryOp(BinaryOp),
     UnaryOp(UnaryOp),
-    Block(Vec<Self>),
-    BlockArg(Box<Self>),
+    Block(Vec<Node>),
+    BlockArg(Box<Node>),
     Segment(Segment),
     Comment(String),
     Ranged(Ranged),
-    Defined(Box<Self>),
-    Splat(Box<Self>),
-    Array(Vec<Self>),
-    Hash(Vec<Self>),
+    Defined(Box<Node>),
+    Splat(Box<Node>),
+    Array(Vec<Node>),
+    Hash(Vec<Association>),
     Alias(Alias),
     Undef(Undef),
     Loop(Loop),
     Rescue(Rescue),
     Case(Case),
+    /// `begin ... end`
+    Begin(Box<Node>),
     Nil,
     Self_,
+    /// `__FILE__`, resolved to the name of the file being parsed (when known)
+    File(Option<String>),
+    /// `__LINE__`, resolved to the line number it appears on
+    Line(usize),
+    /// `__ENCODING__`, resolved to the name of the source encoding
+    Encoding(String),
     Redo,
     Retry,
-    Return(Vec<Self>),
-    Break(Vec<Self>),
-    Next(Vec<Self>),
+    Return(Vec<Node>),
+    Break(Vec<Node>),
+    Next(Vec<Node>),
+    /// `END { }`
+    EndBlock(Box<Node>),
     EndOfProgram(String),
+    /// Source that could not be parsed (only produced by `parse_with_recovery`)
+    Error(String),
     Placeholder,
 }
 
 #[allow(dead_code)]
 impl Node {
+    /// Creates a node that has not (yet) been given a span
+    pub fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            span: Span::default(),
+        }
+    }
+    /// Creates a node that was parsed from the given span
+    pub fn with_span(kind: NodeKind, span: Span) -> Self {
+        Self { kind, span }
+    }
     /// Creates a token that represents an empty block
     pub(crate) fn empty() -> Self {
-        Self::Block(vec![])
+        Self::new(NodeKind::Block(vec![]))
     }
     /// Creates a token that represents a boolean value
     pub(crate) fn boolean(val: bool) -> Self {
-        Self::Literal(Literal::Boolean(val))
+        Self::new(NodeKind::Literal(Literal::Boolean(val)))
     }
     /// Creates a token that represents an integer value
     pub(crate) fn int(val: isize) -> Self {
-        Self::Literal(Literal::Integer(val))
+        Self::new(NodeKind::Literal(Literal::Integer(val)))
     }
     /// Creates a token that represents a float value
     pub(crate) fn float(val: f64) -> Self {
-        Self::Literal(Literal::Float(val))
+        Self::new(NodeKind::Literal(Literal::Float(val)))
     }
     /// Creates a token that represents a literal string
     pub(crate) fn literal_string(val: &str) -> Self {
-        Self::Literal(Literal::String(val.to_owned()))
+        Self::new(NodeKind::Literal(Literal::String(val.to_owned())))
     }
     /// Creates a token that represents a float value
     pub(crate) fn literal_symbol(val: &str) -> Self {
-        Self::Literal(Literal::Symbol(val.to_owned()))
+        Self::new(NodeKind::Literal(Literal::Symbol(val.to_owned())))
     }
     /// Creates a token that represents an identifier
     pub(crate) fn ident(name: &str, kind: IdentifierKind) -> Self {
-        Self::Identifier(Identifier {
+        Self::new(NodeKind::Identifier(Identifier {
             name: name.to_owned(),
             kind,
-        })
+        }))
     }
     /// Creates a token that represents a unary operation
     pub(crate) fn unary_op(op: UnaryOpKind, rhs: Self) -> Self {
-        Self::UnaryOp(UnaryOp {
+        Self::new(NodeKind::UnaryOp(UnaryOp {
             op,
             rhs: Box::new(rhs),
-        })
+        }))
     }
     /// Creates a token that represents a binary operation
     pub(crate) fn binary_op(lhs: Self, op: BinaryOpKind, rhs: Self) -> Self {
-        Self::BinaryOp(BinaryOp {
+        Self::new(NodeKind::BinaryOp(BinaryOp {
             op,
             lhs: Box::new(lhs),
             rhs: Box::new(rhs),
-        })
+        }))
     }
     /// Creates a token that represents a logical AND
     pub(crate) fn logical_and(first: Self, second: Self) -> Self {
-        Self::LogicalAnd(LogicalAnd {
+        Self::new(NodeKind::LogicalAnd(LogicalAnd {
             first: Box::new(first),
             second: Box::new(second),
-        })
+        }))
     }
     /// Creates a token that represents a logical OR
     pub(crate) fn logical_or(first: Self, second: Self) -> Self {
-        Self::LogicalOr(LogicalOr {
+        Self::new(NodeKind::LogicalOr(LogicalOr {
             first: Box::new(first),
             second: Box::new(second),
-        })
+        }))
     }
     /// Creates a token that represents a logical NOT
     pub(crate) fn logical_not(expr: Self) -> Self {
-        Self::LogicalNot(LogicalNot {
+        Self::new(NodeKind::LogicalNot(LogicalNot {
             expr: Box::new(expr),
-        })
+        }))
     }
     /// Creates a token that reprents a defined? statement
     pub(crate) fn defined(node: Self) -> Self {
-        Self::Defined(Box::new(node))
+        Self::new(NodeKind::Defined(Box::new(node)))
     }
     /// Creates a token that reprents a splat argument
     pub(crate) fn splat(node: Self) -> Self {
-        Self::Splat(Box::new(node))
+        Self::new(NodeKind::Splat(Box::new(node)))
     }
     /// Creates a token that reprents a splat argument
     pub(crate) fn block_arg(node: Self) -> Self {
-        Self::BlockArg(Box::new(node))
+        Self::new(NodeKind::BlockArg(Box::new(node)))
     }
     /// Creates a token that reprents an array constructor
     pub(crate) fn array(node: Vec<Self>) -> Self {
-        Self::Array(node)
+        Self::new(NodeKind::Array(node))
     }
     /// Creates a token that reprents a hash constructor
-    pub(crate) fn hash(node: Vec<Self>) -> Self {
-        Self::Hash(node)
+    pub(crate) fn hash(node: Vec<Association>) -> Self {
+        Self::new(NodeKind::Hash(node))
     }
     /// Creates a token that reprents a range
     pub(crate) fn range(from: Self, to: Self, exclusive: bool) -> Self {
-        Self::Ranged(Ranged {
+        Self::new(NodeKind::Ranged(Ranged {
             from: Box::new(from),
             to: Box::new(to),
             exclusive,
-        })
+        }))
     }
     /// Creates a token that represents a case statement
     pub(crate) fn case(expr: Self, when: Vec<WhenClause>, otherwise: Self) -> Self {
-        Self::Case(Case {
+        Self::new(NodeKind::Case(Case {
             expr: Box::new(expr),
             when: when,
             otherwise: Box::new(otherwise),
-        })
+        }))
     }
     /// Creates a token that represents a conditional statement
     pub(crate) fn conditional(
@@ -149,16 +190,16 @@ impl Node {
         then: Self,
         otherwise: Self,
     ) -> Self {
-        Self::Conditional(Conditional {
+        Self::new(NodeKind::Conditional(Conditional {
             kind,
             cond: Box::new(cond),
             then: Box::new(then),
             otherwise: Box::new(otherwise),
-        })
+        }))
     }
     /// Creates a token that represents a loop expression
     pub(crate) fn loop_(kind: LoopKind, cond: Self, body: Self, bindings: Vec<Self>) -> Self {
-        Self::Loop(Loop {
+        Self::new(NodeKind::Loop(Loop {
             kind,
             cond: Box::new(cond),
             body: Box::new(body),
@@ -167,27 +208,76 @@ impl Node {
             } else {
                 None
             },
-        })
+        }))
     }
     /// Creates a token that represents a rescued statement
     pub(crate) fn rescued_statement(body: Self, rescued: Self) -> Self {
-        Self::Rescue(Rescue {
+        Self::new(NodeKind::Rescue(Rescue {
             body: Box::new(body),
             rescue: vec![RescueClause {
                 exceptions: vec![],
-                assigned_to: Box::new(Self::None),
+                assigned_to: Box::new(Self::new(NodeKind::None)),
                 then: Box::new(rescued),
             }],
-            otherwise: Box::new(Self::None),
-        })
+            otherwise: Box::new(Self::new(NodeKind::None)),
+        }))
+    }
+    /// Returns the contents of a string literal that may be juxtaposed with other string literals
+    pub(crate) fn string_segments(&self) -> Option<Vec<Segment>> {
+        match &self.kind {
+            NodeKind::Literal(Literal::String(s)) | NodeKind::Literal(Literal::Character(s)) => {
+                Some(vec![Segment::String(s.clone())])
+            }
+            NodeKind::Literal(Literal::Bytes(b))
+            | NodeKind::Literal(Literal::CharacterBytes(b)) => {
+                Some(vec![Segment::Bytes(b.clone())])
+            }
+            NodeKind::Interpolated(Interpolated::String(nodes)) => Some(
+                nodes
+                    .iter()
+                    .map(|node| match &node.kind {
+                        NodeKind::Segment(Segment::String(s)) => Segment::String(s.clone()),
+                        NodeKind::Segment(Segment::Bytes(b)) => Segment::Bytes(b.clone()),
+                        _ => Segment::expr(node.clone()),
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+    /// Creates a token that represents adjacent string literals, given each literal's contents
+    pub(crate) fn concatenated(mut parts: Vec<(Self, Vec<Segment>)>) -> Self {
+        if parts.len() == 1 {
+            return parts.remove(0).0;
+        }
+        let span = parts[0].0.span.to(&parts[parts.len() - 1].0.span);
+        let (parts, segments): (Vec<Self>, Vec<Vec<Segment>>) = parts.into_iter().unzip();
+        // The string segments of the combined literal span the source between its interpolations,
+        // including the delimiters of the literals they were combined from
+        let value = match Interpolatable::from(segments.concat()).with_segment_spans(span) {
+            Interpolatable::String(s) => NodeKind::Literal(Literal::String(s)),
+            Interpolatable::Bytes(b) => NodeKind::Literal(Literal::Bytes(b)),
+            Interpolatable::Interpolated(v) => NodeKind::Interpolated(Interpolated::String(v)),
+        };
+        Self::with_span(
+            NodeKind::Concatenated(Concatenated {
+                value: Box::new(Self::with_span(value, span)),
+                parts,
+            }),
+            span,
+        )
     }
-    /// Creates a token that represents an alias
-    pub(crate) fn alias(to: String, from: String) -> Self {
-        Self::Alias(Alias { to, from })
+    /// Creates a token that represents a method alias
+    pub(crate) fn alias(to: MethodName, from: MethodName) -> Self {
+        Self::new(NodeKind::Alias(Alias::Method { to, from }))
+    }
+    /// Creates a token that represents a global variable alias
+    pub(crate) fn global_alias(to: Identifier, from: Identifier) -> Self {
+        Self::new(NodeKind::Alias(Alias::GlobalVariable { to, from }))
     }
     /// Creates a token that represents an undefinition
-    pub(crate) fn undef(list: Vec<String>) -> Self {
-        Self::Undef(Undef { list })
+    pub(crate) fn undef(list: Vec<MethodName>) -> Self {
+        Self::new(NodeKind::Undef(Undef { list }))
     }
     /// Allows placeholding nodes to be updated when working around left-recursion via LL(k)
     /// The first item in the tuple represents the intial term
@@ -196,10 +286,16 @@ impl Node {
         if let Some(mut parent_node) = nodes.1 {
             use std::borrow::BorrowMut;
             {
+                let start = nodes.0.span.start;
                 let mut n = &mut parent_node;
                 loop {
-                    match n {
-                        Self::Conditional(sub) => {
+                    // Each node on the way down begins where the initial term does
+                    n.span.start = start;
+                    if !n.is_decursable() {
+                        break;
+                    }
+                    match &mut n.kind {
+                        NodeKind::Conditional(sub) => {
                             n = match sub.kind {
                                 ConditionalKind::ModifyingIf | ConditionalKind::ModifyingUnless => {
                                     sub.then.borrow_mut()
@@ -207,20 +303,37 @@ impl Node {
                                 _ => sub.cond.borrow_mut(),
                             }
                         }
-                        Self::Loop(sub) => {
+                        NodeKind::Loop(sub) => {
                             n = match sub.kind {
-                                LoopKind::ModifyingWhile | LoopKind::ModifyingUntil => {
+                                LoopKind::ModifyingWhile
+                                | LoopKind::ModifyingUntil
+                                | LoopKind::PostConditionWhile
+                                | LoopKind::PostConditionUntil => {
+                                    // A modifier applied directly to `begin ... end` is a post-condition loop
+                                    if let (NodeKind::Placeholder, NodeKind::Begin(_)) =
+                                        (&sub.body.kind, &nodes.0.kind)
+                                    {
+                                        sub.kind = match sub.kind {
+                                            LoopKind::ModifyingWhile => {
+                                                LoopKind::PostConditionWhile
+                                            }
+                                            LoopKind::ModifyingUntil => {
+                                                LoopKind::PostConditionUntil
+                                            }
+                                            _ => sub.kind.clone(),
+                                        };
+                                    }
                                     sub.body.borrow_mut()
                                 }
                                 _ => sub.cond.borrow_mut(),
                             }
                         }
-                        Self::BinaryOp(sub) => n = sub.lhs.borrow_mut(),
-                        Self::LogicalOr(sub) => n = sub.first.borrow_mut(),
-                        Self::LogicalAnd(sub) => n = sub.first.borrow_mut(),
-                        Self::LogicalNot(sub) => n = sub.expr.borrow_mut(),
-                        Self::Rescue(sub) => n = sub.body.borrow_mut(),
-                        _ => break,
+                        NodeKind::BinaryOp(sub) => n = sub.lhs.borrow_mut(),
+                        NodeKind::LogicalOr(sub) => n = sub.first.borrow_mut(),
+                        NodeKind::LogicalAnd(sub) => n = sub.first.borrow_mut(),
+                        NodeKind::LogicalNot(sub) => n = sub.expr.borrow_mut(),
+                        NodeKind::Rescue(sub) => n = sub.body.borrow_mut(),
+                        _ => unreachable!(),
                     }
                 }
                 *n = nodes.0;
@@ -230,4 +343,192 @@ impl Node {
             nodes.0
         }
     }
+    /// Returns the nodes directly contained by this node, in order of appearance
+    pub fn children(&self) -> impl Iterator<Item = &Self> {
+        let children: Vec<&Self> =
+            match &self.kind {
+                NodeKind::Conditional(v) => vec![&v.cond, &v.then, &v.otherwise],
+                NodeKind::LogicalAnd(v) => vec![&v.first, &v.second],
+                NodeKind::LogicalOr(v) => vec![&v.first, &v.second],
+                NodeKind::LogicalNot(v) => vec![&v.expr],
+                NodeKind::Interpolated(Interpolated::Command(v))
+                | NodeKind::Interpolated(Interpolated::String(v))
+                | NodeKind::Interpolated(Interpolated::Symbol(v))
+                | NodeKind::Block(v)
+                | NodeKind::Array(v)
+                | NodeKind::Return(v)
+                | NodeKind::Break(v)
+                | NodeKind::Next(v) => v.iter().collect(),
+                NodeKind::Concatenated(v) => std::iter::once(&*v.value).chain(&v.parts).collect(),
+                NodeKind::BinaryOp(v) => vec![&v.lhs, &v.rhs],
+                NodeKind::UnaryOp(v) => vec![&v.rhs],
+                NodeKind::BlockArg(v)
+                | NodeKind::Defined(v)
+                | NodeKind::Splat(v)
+                | NodeKind::Begin(v)
+                | NodeKind::EndBlock(v)
+                | NodeKind::Segment(Segment::Expr(v)) => vec![v],
+                NodeKind::Ranged(v) => vec![&v.from, &v.to],
+                NodeKind::Hash(v) => v
+                    .iter()
+                    .flat_map(|association| match association {
+                        Association::Pair(pair) => vec![&*pair.key, &*pair.value],
+                        Association::DoubleSplat(node) => vec![&**node],
+                    })
+                    .collect(),
+                NodeKind::Alias(Alias::Method { to, from }) => {
+                    to.children().into_iter().chain(from.children()).collect()
+                }
+                NodeKind::Undef(v) => v.list.iter().flat_map(MethodName::children).collect(),
+                NodeKind::Loop(v) => std::iter::once(&*v.cond)
+                    .chain(v.bindings.iter().flatten())
+                    .chain(std::iter::once(&*v.body))
+                    .collect(),
+                NodeKind::Rescue(v) => std::iter::once(&*v.body)
+                    .chain(v.rescue.iter().flat_map(|clause| {
+                        clause
+                            .exceptions
+                            .iter()
+                            .chain(std::iter::once(&*clause.assigned_to))
+                            .chain(std::iter::once(&*clause.then))
+                    }))
+                    .chain(std::iter::once(&*v.otherwise))
+                    .collect(),
+                NodeKind::Case(v) => std::iter::once(&*v.expr)
+                    .chain(v.when.iter().flat_map(|clause| {
+                        clause.when.iter().chain(std::iter::once(&*clause.then))
+                    }))
+                    .chain(std::iter::once(&*v.otherwise))
+                    .collect(),
+                // Listed explicitly, so that a new variant must declare its children here
+                NodeKind::None
+                | NodeKind::Literal(_)
+                | NodeKind::Identifier(_)
+                | NodeKind::Segment(_)
+                | NodeKind::Comment(_)
+                | NodeKind::Alias(Alias::GlobalVariable { .. })
+                | NodeKind::Nil
+                | NodeKind::Self_
+                | NodeKind::File(_)
+                | NodeKind::Line(_)
+                | NodeKind::Encoding(_)
+                | NodeKind::Redo
+                | NodeKind::Retry
+                | NodeKind::EndOfProgram(_)
+                | NodeKind::Error(_)
+                | NodeKind::Placeholder => vec![],
+            };
+        children.into_iter()
+    }
+    /// Returns mutable references to the nodes directly contained by this node, in order of appearance
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Self> {
+        let children: Vec<&mut Self> = match &mut self.kind {
+            NodeKind::Conditional(v) => vec![&mut v.cond, &mut v.then, &mut v.otherwise],
+            NodeKind::LogicalAnd(v) => vec![&mut v.first, &mut v.second],
+            NodeKind::LogicalOr(v) => vec![&mut v.first, &mut v.second],
+            NodeKind::LogicalNot(v) => vec![&mut v.expr],
+            NodeKind::Interpolated(Interpolated::Command(v))
+            | NodeKind::Interpolated(Interpolated::String(v))
+            | NodeKind::Interpolated(Interpolated::Symbol(v))
+            | NodeKind::Block(v)
+            | NodeKind::Array(v)
+            | NodeKind::Return(v)
+            | NodeKind::Break(v)
+            | NodeKind::Next(v) => v.iter_mut().collect(),
+            NodeKind::Concatenated(v) => {
+                std::iter::once(&mut *v.value).chain(&mut v.parts).collect()
+            }
+            NodeKind::BinaryOp(v) => vec![&mut v.lhs, &mut v.rhs],
+            NodeKind::UnaryOp(v) => vec![&mut v.rhs],
+            NodeKind::BlockArg(v)
+            | NodeKind::Defined(v)
+            | NodeKind::Splat(v)
+            | NodeKind::Begin(v)
+            | NodeKind::EndBlock(v)
+            | NodeKind::Segment(Segment::Expr(v)) => vec![v],
+            NodeKind::Ranged(v) => vec![&mut v.from, &mut v.to],
+            NodeKind::Hash(v) => v
+                .iter_mut()
+                .flat_map(|association| match association {
+                    Association::Pair(pair) => vec![&mut *pair.key, &mut *pair.value],
+                    Association::DoubleSplat(node) => vec![&mut **node],
+                })
+                .collect(),
+            NodeKind::Alias(Alias::Method { to, from }) => to
+                .children_mut()
+                .into_iter()
+                .chain(from.children_mut())
+                .collect(),
+            NodeKind::Undef(v) => v
+                .list
+                .iter_mut()
+                .flat_map(MethodName::children_mut)
+                .collect(),
+            NodeKind::Loop(v) => std::iter::once(&mut *v.cond)
+                .chain(v.bindings.iter_mut().flatten())
+                .chain(std::iter::once(&mut *v.body))
+                .collect(),
+            NodeKind::Rescue(v) => std::iter::once(&mut *v.body)
+                .chain(v.rescue.iter_mut().flat_map(|clause| {
+                    clause
+                        .exceptions
+                        .iter_mut()
+                        .chain(std::iter::once(&mut *clause.assigned_to))
+                        .chain(std::iter::once(&mut *clause.then))
+                }))
+                .chain(std::iter::once(&mut *v.otherwise))
+                .collect(),
+            NodeKind::Case(v) => std::iter::once(&mut *v.expr)
+                .chain(v.when.iter_mut().flat_map(|clause| {
+                    clause
+                        .when
+                        .iter_mut()
+                        .chain(std::iter::once(&mut *clause.then))
+                }))
+                .chain(std::iter::once(&mut *v.otherwise))
+                .collect(),
+            NodeKind::None
+            | NodeKind::Literal(_)
+            | NodeKind::Identifier(_)
+            | NodeKind::Segment(_)
+            | NodeKind::Comment(_)
+            | NodeKind::Alias(Alias::GlobalVariable { .. })
+            | NodeKind::Nil
+            | NodeKind::Self_
+            | NodeKind::File(_)
+            | NodeKind::Line(_)
+            | NodeKind::Encoding(_)
+            | NodeKind::Redo
+            | NodeKind::Retry
+            | NodeKind::EndOfProgram(_)
+            | NodeKind::Error(_)
+            | NodeKind::Placeholder => vec![],
+        };
+        children.into_iter()
+    }
+    /// Returns the children of the node that cover some source, in place of any that don't
+    pub(crate) fn spanned_children(&self) -> Vec<&Self> {
+        self.children()
+            .flat_map(|child| {
+                if child.span.is_empty() {
+                    child.spanned_children()
+                } else {
+                    vec![child]
+                }
+            })
+            .collect()
+    }
+    /// Returns true when the node may contain the placeholder of a left-recursive term
+    fn is_decursable(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeKind::Conditional(_)
+                | NodeKind::Loop(_)
+                | NodeKind::BinaryOp(_)
+                | NodeKind::LogicalOr(_)
+                | NodeKind::LogicalAnd(_)
+                | NodeKind::LogicalNot(_)
+                | NodeKind::Rescue(_)
+        )
+    }
 }
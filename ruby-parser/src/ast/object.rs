@@ -1,8 +1,57 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ranged {
     pub from: Box<Node>,
     pub to: Box<Node>,
     pub exclusive: bool,
 }
+
+/// Represents an entry within a hash constructor or a trailing hash argument
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Association {
+    /// `key => value`, `key: value`, `"key": value` or `key:`
+    Pair(Pair),
+    /// `**value`
+    DoubleSplat(Box<Node>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pair {
+    pub key: Box<Node>,
+    pub value: Box<Node>,
+    pub syntax: PairSyntax,
+}
+
+/// Records how a pair's key was written in the source
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PairSyntax {
+    /// `key => value`
+    Rocket,
+    /// `key: value`
+    Label,
+    /// `"key": value` | `'key': value`
+    QuotedLabel,
+    /// `key:` (the value refers to the variable or method of the same name)
+    Shorthand,
+}
+
+#[allow(dead_code)]
+impl Association {
+    /// Creates an association that represents a key / value pair
+    pub(crate) fn pair(key: Node, value: Node, syntax: PairSyntax) -> Self {
+        Self::Pair(Pair {
+            key: Box::new(key),
+            value: Box::new(value),
+            syntax,
+        })
+    }
+    /// Creates an association that represents a double splat
+    pub(crate) fn double_splat(node: Node) -> Self {
+        Self::DoubleSplat(Box::new(node))
+    }
+}
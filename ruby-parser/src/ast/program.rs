@@ -1,7 +1,52 @@
 use super::*;
+use crate::lexer::Encoding;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
     pub program: Node,
+    /// The bodies of any top-level `BEGIN { }` blocks, in order of appearance
+    pub begin_blocks: Vec<Node>,
+    /// The program's `#!` line (when present on the program's first line)
+    pub shebang: Option<Shebang>,
+    /// The magic comments that were recognized before the program's first token
+    pub magic_comments: MagicComments,
+    /// Every comment in the program (including `=begin`/`=end` blocks), in order of appearance
+    pub comments: Vec<Node>,
+    /// The encoding of the program's source
+    pub encoding: Encoding,
     pub data: Option<String>,
 }
+
+/// Describes a program's `#!` line
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shebang {
+    /// The contents of the line that follow the `#!`
+    pub line: String,
+    /// The command line switches that follow the `ruby` interpreter
+    pub flags: Vec<String>,
+}
+
+/// Describes the directives set by a program's magic comments
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MagicComments {
+    /// `# encoding: ...` | `# coding: ...` (honored on the first line, or the second line after a shebang)
+    pub encoding: Option<String>,
+    /// `# frozen_string_literal: true`
+    pub frozen_string_literal: Option<bool>,
+    /// `# warn_indent: true`
+    pub warn_indent: Option<bool>,
+    /// `# shareable_constant_value: literal`
+    pub shareable_constant_value: Option<ShareableConstantValue>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShareableConstantValue {
+    None,
+    Literal,
+    ExperimentalEverything,
+    ExperimentalCopy,
+}
@@ -0,0 +1,49 @@
+/// A location within a program's source
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// The byte offset from the start of the program
+    pub offset: usize,
+    /// The line number (starts at 1)
+    pub line: usize,
+    /// The character number within the line (starts at 1, UTF8-aware)
+    pub char: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, char: usize) -> Self {
+        Self { offset, line, char }
+    }
+}
+
+/// The region of a program's source that a node was parsed from
+///
+/// The end position is exclusive. A span may cover the bodies of any heredocs that start within it.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+    /// Returns the span from the start of this span to the end of another
+    pub fn to(&self, other: &Self) -> Self {
+        Self::new(self.start, other.end)
+    }
+    /// Returns true when the span has not been set
+    pub fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+    /// Returns the number of bytes covered by the span
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+    /// Returns true when the span covers no source
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
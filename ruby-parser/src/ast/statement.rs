@@ -1,24 +1,59 @@
 use super::*;
 
-#[derive(Debug, PartialEq)]
-pub struct Alias {
-    pub to: String,
-    pub from: String,
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alias {
+    /// Aliases a method (`alias new old`)
+    Method { to: MethodName, from: MethodName },
+    /// Aliases a global variable (`alias $new $old`)
+    GlobalVariable { to: Identifier, from: Identifier },
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Undef {
-    pub list: Vec<String>,
+    pub list: Vec<MethodName>,
 }
 
-#[derive(Debug, PartialEq)]
+/// A method name given to `alias` or `undef`, recorded as it was written
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MethodName {
+    /// A bare method name (`foo`)
+    Bare(String),
+    /// A symbol (`:foo`, `:"foo"`, or `%s(foo)`)
+    Symbol(String),
+    /// An interpolated dynamic symbol (`:"foo#{x}"`)
+    DynamicSymbol(Vec<Node>),
+}
+
+impl MethodName {
+    /// Returns the nodes contained by the name (only present in dynamic symbols)
+    pub(crate) fn children(&self) -> Vec<&Node> {
+        match self {
+            Self::DynamicSymbol(v) => v.iter().collect(),
+            _ => vec![],
+        }
+    }
+    /// Returns mutable references to the nodes contained by the name
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut Node> {
+        match self {
+            Self::DynamicSymbol(v) => v.iter_mut().collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rescue {
     pub body: Box<Node>,
     pub rescue: Vec<RescueClause>,
     pub otherwise: Box<Node>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RescueClause {
     pub exceptions: Vec<Node>,
     pub assigned_to: Box<Node>,
@@ -0,0 +1,306 @@
+//! Provides a lossless concrete syntax tree
+
+use crate::ast::{Node, Program, Span};
+use crate::lexer::Token;
+use std::iter::Peekable;
+
+/// A program's concrete syntax tree, along with its abstract syntax tree
+///
+/// The tree holds every token that the parser consumed, in order of appearance, so concatenating
+/// them reproduces the source byte-for-byte (see `parse_syntax_tree`).
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxTree<'a> {
+    program: Program,
+    tokens: Vec<Token<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    pub(crate) fn new(program: Program, tokens: Vec<Token<'a>>) -> Self {
+        Self { program, tokens }
+    }
+    /// Returns the program's abstract syntax tree
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+    /// Returns the program's abstract syntax tree, discarding its tokens
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+    /// Returns every token of the source, in order of appearance
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+    /// Arranges the tokens into syntax nodes, following the abstract syntax tree
+    ///
+    /// The root's node is the program's top-level block. Nodes that lie within a single token
+    /// (such as code interpolated into a string) are part of that token.
+    pub fn root(&self) -> SyntaxNode<'_> {
+        let program = &self.program;
+        let mut tokens = self.tokens.iter().cloned().peekable();
+        let children = program
+            .begin_blocks
+            .iter()
+            .chain(program.program.spanned_children())
+            .collect();
+        SyntaxNode::build(&program.program, children, &mut tokens, usize::MAX)
+    }
+}
+
+/// Renders the source that the program was parsed from
+impl std::fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for token in &self.tokens {
+            f.write_str(token.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// A node of the concrete syntax tree
+///
+/// Each syntax node pairs a node of the abstract syntax tree with the tokens it was parsed from,
+/// including the trivia (whitespace, comments, parentheses, keywords, etc.) that the abstract
+/// syntax tree omits.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxNode<'a> {
+    /// The node of the abstract syntax tree (the program's top-level block for the root)
+    pub node: &'a Node,
+    /// The node's own tokens and the syntax nodes of its children, in order of appearance
+    pub children: Vec<SyntaxElement<'a>>,
+}
+
+/// An element of the concrete syntax tree
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(Token<'a>),
+}
+
+impl<'a> SyntaxNode<'a> {
+    /// Builds a syntax node from the tokens that start before the given offset
+    fn build<I: Iterator<Item = Token<'a>>>(
+        node: &'a Node,
+        mut children: Vec<&'a Node>,
+        tokens: &mut Peekable<I>,
+        end: usize,
+    ) -> Self {
+        children.sort_by_key(|child| child.span.start.offset);
+        let mut elements = vec![];
+        for child in children {
+            let span = child.span;
+            take_tokens(&mut elements, tokens, span.start.offset);
+            match tokens.peek() {
+                Some(token) if token.span.start.offset < span.end.offset => {
+                    let grandchildren = child.spanned_children();
+                    let syntax = Self::build(child, grandchildren, tokens, span.end.offset);
+                    elements.push(SyntaxElement::Node(syntax));
+                }
+                _ => {}
+            }
+        }
+        take_tokens(&mut elements, tokens, end);
+        Self {
+            node,
+            children: elements,
+        }
+    }
+    /// Returns the syntax nodes of the node's children
+    pub fn child_nodes(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter().filter_map(|element| match element {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+    /// Returns every token of the node (including those of its descendants), in order
+    pub fn tokens(&self) -> Vec<&Token<'a>> {
+        self.children
+            .iter()
+            .flat_map(|element| match element {
+                SyntaxElement::Node(node) => node.tokens(),
+                SyntaxElement::Token(token) => vec![token],
+            })
+            .collect()
+    }
+    /// Returns the region of source covered by the node's tokens
+    pub fn span(&self) -> Span {
+        let tokens = self.tokens();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => first.span.to(&last.span),
+            _ => Span::default(),
+        }
+    }
+}
+
+/// Renders the source that the node was parsed from
+impl std::fmt::Display for SyntaxNode<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for token in self.tokens() {
+            f.write_str(token.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the tokens that start before the given offset
+fn take_tokens<'a, I: Iterator<Item = Token<'a>>>(
+    elements: &mut Vec<SyntaxElement<'a>>,
+    tokens: &mut Peekable<I>,
+    end: usize,
+) {
+    while let Some(token) = tokens.next_if(|token| token.span.start.offset < end) {
+        elements.push(SyntaxElement::Token(token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{IdentifierKind, NodeKind};
+    use crate::lexer::{Input, LiteralKind, TokenKind};
+
+    #[test]
+    fn test_syntax_tree() {
+        let source = "# c\n[1,  2]  # t\nx ? 1 : \"a#{b}\"\n";
+        let tree = crate::parse_syntax_tree(Input::new(source)).unwrap();
+        let root = tree.root();
+        assert_eq!(tree.to_string(), source);
+        assert_eq!(root.to_string(), source);
+        assert_eq!(root.span().end.offset, source.len());
+        // The abstract syntax tree is the one that `parse` produces
+        assert_eq!(tree.program(), &crate::parse(Input::new(source)).unwrap());
+        let nodes: Vec<&SyntaxNode> = root.child_nodes().collect();
+        assert_eq!(nodes.len(), 2);
+        // The typed node is available alongside its trivia
+        assert_eq!(
+            nodes[0].node,
+            &Node::array(vec![Node::int(1), Node::int(2)])
+        );
+        let texts: Vec<Option<&str>> = nodes[0]
+            .children
+            .iter()
+            .map(|element| match element {
+                SyntaxElement::Node(_) => None,
+                SyntaxElement::Token(token) => Some(token.text),
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![Some("["), None, Some(","), Some("  "), None, Some("]")]
+        );
+        let comments: Vec<&str> = root
+            .children
+            .iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Token(token) if token.kind == TokenKind::Comment => Some(token.text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(comments, vec!["# c", "# t"]);
+        // Code interpolated into a string is part of the string's token
+        let string = nodes[1].child_nodes().last().unwrap();
+        assert_eq!(string.child_nodes().count(), 0);
+        assert_eq!(string.to_string(), "\"a#{b}\"");
+        assert_eq!(
+            nodes[1].child_nodes().next().unwrap().node,
+            &Node::ident("x", IdentifierKind::LocalVariable)
+        );
+        assert!(matches!(nodes[1].node.kind, NodeKind::Conditional(_)));
+        // Heredoc bodies (including those of heredocs started within interpolated code) are kept
+        let source = "[<<A, \"#{<<B}\"]\na\nA\nb\nB\n__LINE__";
+        let tree = crate::parse_syntax_tree(Input::new(source)).unwrap();
+        let root = tree.root();
+        assert_eq!(root.to_string(), source);
+        let nodes: Vec<&SyntaxNode> = root.child_nodes().collect();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1].to_string(), "__LINE__");
+    }
+
+    #[test]
+    fn test_recorded_tokens() {
+        use IdentifierKind::*;
+        use TokenKind::*;
+        fn tokens(source: &str) -> Vec<(TokenKind, &str)> {
+            let tree = crate::parse_syntax_tree(Input::new(source)).unwrap();
+            assert_eq!(tree.to_string(), source);
+            assert_eq!(tree.root().to_string(), source);
+            assert_eq!(tree.program(), &crate::parse(Input::new(source)).unwrap());
+            // The tokens are contiguous and were all consumed by the parser
+            let mut offset = 0;
+            for token in tree.tokens() {
+                assert_eq!(token.span.start.offset, offset, "{:?}", source);
+                assert_ne!(token.kind, Error, "{:?}", source);
+                offset = token.span.end.offset;
+            }
+            assert_eq!(offset, source.len());
+            tree.tokens()
+                .iter()
+                .filter(|token| token.kind != Whitespace)
+                .map(|token| (token.kind.clone(), token.text))
+                .collect()
+        }
+        // Tokens are classified by the parser rather than by their context
+        assert_eq!(
+            tokens("def foo(a)\n  bar(a) # c\nend"),
+            vec![
+                (Keyword, "def"),
+                (Identifier(Method), "foo"),
+                (Punctuation, "("),
+                (Identifier(LocalVariable), "a"),
+                (Punctuation, ")"),
+                (Newline, "\n"),
+                (Identifier(Method), "bar"),
+                (Punctuation, "("),
+                (Identifier(LocalVariable), "a"),
+                (Punctuation, ")"),
+                (Comment, "# c"),
+                (Newline, "\n"),
+                (Keyword, "end"),
+            ]
+        );
+        assert_eq!(
+            tokens("x ||= -1.5 ** 'a' \"b\""),
+            vec![
+                (Identifier(LocalVariable), "x"),
+                (Operator, "||="),
+                (Literal(LiteralKind::Float), "-1.5"),
+                (Operator, "**"),
+                (Literal(LiteralKind::String), "'a'"),
+                (Literal(LiteralKind::String), "\"b\""),
+            ]
+        );
+        // Whitespace consumed piecemeal is a single token, unlike an escaped line terminator
+        let tree = crate::parse_syntax_tree(Input::new("1  + \\\n 2")).unwrap();
+        let texts: Vec<&str> = tree.tokens().iter().map(|token| token.text).collect();
+        assert_eq!(texts, vec!["1", "  ", "+", " ", "\\\n", " ", "2"]);
+        // Heredoc bodies fill the gaps that parsing jumped over, including the bodies of heredocs
+        // whose starting line continues after them
+        for source in [
+            "x = <<~EOS\n  hi #{y}\nEOS\nz",
+            "foo(<<A) if x(<<B)\na\nA\nb\nB\n",
+            "p <<-A, <<'B'\n  x\n  A\ny\nB\n",
+            "\"#{<<A}\" + 'x'\nbody #{1}\nA\n",
+            "res = <<~MARK \\\nfoobar\nMARK\n\"meh\"",
+            "<<A\nx\nA",
+        ]
+        .iter()
+        {
+            let bodies = tokens(source)
+                .into_iter()
+                .filter(|(kind, _)| *kind == HeredocBody)
+                .count();
+            assert_eq!(bodies, source.matches("<<").count(), "{:?}", source);
+        }
+        assert_eq!(
+            tokens("#!/usr/bin/env ruby\n=begin\nx\n=end\n1\n__END__\ndata"),
+            vec![
+                (Comment, "#!/usr/bin/env ruby"),
+                (Newline, "\n"),
+                (Comment, "=begin\nx\n=end\n"),
+                (Literal(LiteralKind::Integer), "1"),
+                (Newline, "\n"),
+                (EndOfProgram, "__END__\ndata"),
+            ]
+        );
+        tokens(include_str!("../../test.rb"));
+    }
+}
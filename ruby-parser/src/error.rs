@@ -0,0 +1,851 @@
+//! Provides the error type returned when a program cannot be parsed
+
+use crate::ast::Position;
+use crate::lexer::{Input, Token, TokenKind, Tokens};
+use codemap::CodeMap;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+
+/// Describes something that would have allowed parsing to continue
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum Expected {
+    /// A keyword or punctuation token
+    Token(String),
+    /// A token that closes a construct opened earlier in the program
+    Closing {
+        token: String,
+        opener: String,
+        position: Position,
+    },
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token(token) if token.chars().any(char::is_control) => {
+                write!(f, "`{}`", token.escape_debug())
+            }
+            Self::Token(token) => write!(f, "`{}`", token),
+            Self::Closing {
+                token,
+                opener,
+                position,
+            } => write!(
+                f,
+                "`{}` to close `{}` at {}:{}",
+                token, opener, position.line, position.char
+            ),
+        }
+    }
+}
+
+/// Describes why a program could not be parsed
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// The name of the file being parsed
+    pub file: Option<String>,
+    /// The furthest location the parser reached before failing
+    pub position: Position,
+    /// Everything that would have allowed parsing to continue at that location
+    pub expected: Vec<Expected>,
+    /// A one-line summary of the error
+    pub message: String,
+    /// The line of source that contains the error (without its line terminator)
+    pub source_line: String,
+    /// Set when partial input ended before the program did (see `Metadata::partial`)
+    pub incomplete: Option<Incomplete>,
+}
+
+/// Describes partial input that needs more input before it can be parsed
+#[derive(Debug, PartialEq, Clone)]
+pub struct Incomplete {
+    /// The constructs that are still open, outermost first
+    pub unclosed: Vec<Unclosed>,
+}
+
+impl Incomplete {
+    /// Returns the character an IRB-style prompt uses to show what is being continued
+    ///
+    /// This is the kind of quote for open string-like literals, and `*` otherwise.
+    pub fn prompt_marker(&self) -> char {
+        let opener = match self.unclosed.last() {
+            Some(unclosed) => unclosed.opener.as_str(),
+            None => return '*',
+        };
+        let heredoc = opener
+            .trim_start_matches("<<")
+            .trim_start_matches(&['-', '~'][..]);
+        match opener.chars().next() {
+            Some('"') | Some('\'') | Some('`') | Some('/') => opener.chars().next().unwrap(),
+            Some('<') if opener.starts_with("<<") => match heredoc.chars().next() {
+                Some('\'') => '\'',
+                Some('`') => '`',
+                _ => '"',
+            },
+            Some('%') => match opener[1..].chars().next() {
+                Some('q') => '\'',
+                Some('x') => '`',
+                Some('r') => '/',
+                Some('s') => ':',
+                Some('w') | Some('W') | Some('i') | Some('I') => ']',
+                _ => '"',
+            },
+            _ => '*',
+        }
+    }
+}
+
+/// Describes a construct that was still open when partial input ended
+#[derive(Debug, PartialEq, Clone)]
+pub struct Unclosed {
+    /// The source that opened the construct (such as `do`, `"` or `<<~EOS`)
+    pub opener: String,
+    /// The token that will close the construct
+    pub closer: String,
+    /// Where the construct was opened
+    pub position: Position,
+}
+
+impl ParseError {
+    /// Builds an error from the failures recorded while parsing `source`
+    pub(crate) fn new(
+        source: &str,
+        file: Option<&str>,
+        partial: bool,
+        failures: Failures,
+        fallback: Position,
+    ) -> Self {
+        // A rejection only explains the failure when parsing failed where it was made
+        let reason = failures
+            .rejected
+            .filter(|(position, _)| *position == fallback)
+            .map(|(_, reason)| reason);
+        let (position, expected, message) = match failures.furthest {
+            Some(position) if position > fallback || reason.is_none() => {
+                (position, failures.expected, None)
+            }
+            _ => (fallback, BTreeSet::new(), reason),
+        };
+        let mut codemap = CodeMap::new();
+        let file_map = codemap.add_file(file.unwrap_or("-").to_owned(), source.to_owned());
+        let offset = position.offset.min(source.len()) as u64;
+        let line = file_map.find_line(file_map.span.subspan(offset, offset).low());
+        let source_line = file_map
+            .source_line(line)
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_owned();
+        let expected: Vec<Expected> = expected.into_iter().collect();
+        // Partial input that failed at its very end may be completed by more input
+        let incomplete = if partial && position.offset == source.len() {
+            Some(Incomplete {
+                unclosed: failures
+                    .open_at_furthest
+                    .into_iter()
+                    .map(|opening| Unclosed {
+                        opener: opening.opener.into_owned(),
+                        closer: opening.closer.into_owned(),
+                        position: opening.position,
+                    })
+                    .collect(),
+            })
+        } else {
+            None
+        };
+        let message = message
+            .map(|m| m.to_owned())
+            .unwrap_or_else(|| Self::describe(&source[offset as usize..], &expected));
+        Self {
+            file: file.map(|f| f.to_owned()),
+            position,
+            expected,
+            message,
+            source_line,
+            incomplete,
+        }
+    }
+
+    /// Drops the closers that were expected for the constructs that turned out to be `closed`
+    pub(crate) fn closed<F>(&mut self, source: &str, closed: F)
+    where
+        F: Fn(&Position) -> bool,
+    {
+        let count = self.expected.len();
+        self.expected.retain(|e| match e {
+            Expected::Closing { position, .. } => !closed(position),
+            Expected::Token(_) => true,
+        });
+        if self.expected.len() < count {
+            let offset = self.position.offset.min(source.len());
+            self.message = Self::describe(&source[offset..], &self.expected);
+        }
+    }
+    /// Summarizes what was found and what was expected instead
+    fn describe(remaining: &str, expected: &[Expected]) -> String {
+        // The innermost unclosed construct explains the error best
+        let closing = expected
+            .iter()
+            .filter_map(|e| match e {
+                Expected::Closing { position, .. } => Some((position, e)),
+                _ => None,
+            })
+            .max_by_key(|(position, _)| *position);
+        if let Some((_, closing)) = closing {
+            return format!("expected {}", closing);
+        }
+        let word = remaining
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        let found = match remaining.chars().next() {
+            None => "end of input".to_owned(),
+            Some('\n') | Some('\r') => "end of line".to_owned(),
+            Some(_) if !word.is_empty() => format!("`{}`", word),
+            Some(c) => format!("`{}`", c.escape_debug()),
+        };
+        match expected.len() {
+            0 => format!("unexpected {}", found),
+            1 => format!("unexpected {}, expected {}", found, expected[0]),
+            _ => format!(
+                "unexpected {}, expected one of {}",
+                found,
+                expected
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.position.line.to_string();
+        let gutter = " ".repeat(line.len());
+        // Tabs are kept so that the caret lines up with the source line
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.position.char.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "error: {}", self.message)?;
+        match &self.file {
+            Some(file) => writeln!(
+                f,
+                "{}--> {}:{}:{}",
+                gutter, file, self.position.line, self.position.char
+            )?,
+            None => writeln!(
+                f,
+                "{}--> {}:{}",
+                gutter, self.position.line, self.position.char
+            )?,
+        }
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line, self.source_line)?;
+        write!(f, "{} | {}^", gutter, indent)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A construct whose closing token has not been parsed yet
+#[derive(Debug, Clone)]
+struct Opening {
+    opener: Cow<'static, str>,
+    closer: Cow<'static, str>,
+    position: Position,
+}
+
+/// Tracks the furthest point any parser failed at while parsing a program
+///
+/// Parsers backtrack freely, so the error that reaches the top of the parser is rarely the one
+/// that explains what went wrong. Instead, every failed token records what it expected (and
+/// the characters within tokens record how far parsing got).
+#[derive(Debug, Default)]
+pub(crate) struct Failures {
+    furthest: Option<Position>,
+    expected: BTreeSet<Expected>,
+    openings: Vec<Opening>,
+    /// The deepest set of constructs that were open at the furthest failure
+    open_at_furthest: Vec<Opening>,
+    /// The most recent failure that was not caused by a missing token, and where it happened
+    rejected: Option<(Position, &'static str)>,
+    /// The offsets of the statements being parsed, with the closers of the constructs they're in
+    statements: Vec<(usize, Option<Cow<'static, str>>)>,
+    /// The deepest set of statements that were being parsed at the furthest failure
+    statements_at_furthest: Vec<(usize, Option<Cow<'static, str>>)>,
+    /// Regions of source that are parsed as errors when recovering
+    skipped: Vec<Range<usize>>,
+}
+
+impl Failures {
+    /// Tracks failures while treating each of the given regions as an error
+    pub(crate) fn skipping(skipped: Vec<Range<usize>>) -> Self {
+        Self {
+            skipped,
+            ..Self::default()
+        }
+    }
+    /// Returns where the skipped region that begins at `offset` ends
+    pub(crate) fn skipped_from(&self, offset: usize) -> Option<usize> {
+        self.skipped
+            .iter()
+            .find(|region| region.start == offset)
+            .map(|region| region.end)
+    }
+    /// Records that `token` was expected at the given position
+    pub(crate) fn expected(&mut self, position: Position, token: &str) {
+        let expected = self.expected_token(token);
+        self.failed(position, Some(expected));
+    }
+    /// Records that a token's characters failed to match `token` at the given position
+    ///
+    /// This only advances the furthest failure, unless `token` closes the innermost construct.
+    pub(crate) fn probed(&mut self, position: Position, token: &str) {
+        let expected = match self.expected_token(token) {
+            closing @ Expected::Closing { .. } => Some(closing),
+            Expected::Token(_) => None,
+        };
+        self.failed(position, expected);
+    }
+    /// Describes an expected token, which may close the innermost construct
+    fn expected_token(&self, token: &str) -> Expected {
+        match self.openings.last() {
+            Some(opening) if opening.closer == token => Expected::Closing {
+                token: token.to_owned(),
+                opener: opening.opener.to_string(),
+                position: opening.position,
+            },
+            _ => Expected::Token(token.to_owned()),
+        }
+    }
+    /// Records a failure at the given position
+    fn failed(&mut self, position: Position, expected: Option<Expected>) {
+        if self
+            .skipped
+            .iter()
+            .any(|region| region.contains(&position.offset))
+        {
+            return;
+        }
+        match self.furthest {
+            Some(furthest) if furthest.offset > position.offset => return,
+            Some(furthest) if furthest.offset == position.offset => {
+                if self.openings.len() > self.open_at_furthest.len() {
+                    self.open_at_furthest = self.openings.clone();
+                }
+                if self.statements.len() > self.statements_at_furthest.len() {
+                    self.statements_at_furthest = self.statements.clone();
+                }
+            }
+            _ => {
+                self.furthest = Some(position);
+                self.expected.clear();
+                self.open_at_furthest = self.openings.clone();
+                self.statements_at_furthest = self.statements.clone();
+            }
+        }
+        self.expected.extend(expected);
+    }
+    /// Marks the start of a construct that must be closed by `closer`
+    pub(crate) fn open<S, T>(&mut self, opener: S, closer: T, position: Position)
+    where
+        S: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        self.openings.push(Opening {
+            opener: opener.into(),
+            closer: closer.into(),
+            position,
+        });
+    }
+    /// Marks the end of the innermost construct
+    pub(crate) fn close(&mut self) {
+        self.openings.pop();
+    }
+    /// Marks the start of a statement within the innermost construct
+    pub(crate) fn begin_statement(&mut self, offset: usize) {
+        let closer = self.openings.last().map(|opening| opening.closer.clone());
+        self.statements.push((offset, closer));
+    }
+    /// Marks the end of the innermost statement
+    pub(crate) fn end_statement(&mut self) {
+        self.statements.pop();
+    }
+    /// Returns the statements that were being parsed at the furthest failure, innermost first
+    ///
+    /// Each is given as its offset and the token that closes the construct it's in.
+    pub(crate) fn failed_statements(&self) -> Vec<(usize, Option<Cow<'static, str>>)> {
+        self.statements_at_furthest.iter().rev().cloned().collect()
+    }
+    /// Explains a failure at the given position that was not caused by a missing token
+    pub(crate) fn reject(&mut self, position: Position, reason: &'static str) {
+        self.rejected = Some((position, reason));
+    }
+}
+
+/// Finds where parsing failed, given the input that parsing began with
+pub(crate) fn failure_position(i: &Input, e: nom::Err<(Input, nom::error::ErrorKind)>) -> Position {
+    match e {
+        nom::Err::Error((i, _)) | nom::Err::Failure((i, _)) => i.position(),
+        // A streaming parser ran out of input before it could finish
+        nom::Err::Incomplete(_) => nom::InputTake::take_split(i, nom::InputLength::input_len(i))
+            .0
+            .position(),
+    }
+}
+
+/// Finds where parsing can resume after skipping the statement at `start`, which failed at `offset`
+///
+/// The statement is lexed into tokens (so that strings, comments and heredocs are skipped whole)
+/// up to the first separator after `offset`, or the `closer` of the construct the statement is
+/// in. Either must be outside of any construct that the statement opens. The bodies of heredocs
+/// that the statement starts are skipped along with it.
+///
+/// Returns `None` when the construct the statement is in is never closed, as the error is then
+/// better explained by an enclosing statement.
+pub(crate) fn resynchronize(
+    source: &str,
+    start: usize,
+    offset: usize,
+    closer: Option<&str>,
+) -> Option<usize> {
+    let trimmed = |end: usize| start + source[start..end].trim_end().len();
+    let mut tokens = Tokens::new(Input::new(&source[start..])).peekable();
+    let mut end = None;
+    // The closers of the constructs opened within the statement
+    let mut open: Vec<&str> = vec![];
+    let mut previous: Option<Token> = None;
+    // Whether a loop's condition is being lexed, where `do` doesn't begin a block
+    let mut condition = false;
+    while let Some(token) = tokens.next() {
+        let at = start + token.span.start.offset;
+        let beginning = match &previous {
+            None => true,
+            Some(token) => match (&token.kind, token.text) {
+                (TokenKind::Newline, _) | (TokenKind::Operator, _) => true,
+                (TokenKind::Punctuation, ")" | "]" | "}") => false,
+                (TokenKind::Punctuation, _) => true,
+                (
+                    TokenKind::Keyword,
+                    "end" | "self" | "nil" | "true" | "false" | "redo" | "retry" | "return"
+                    | "break" | "next" | "__FILE__" | "__LINE__" | "__ENCODING__",
+                ) => false,
+                (TokenKind::Keyword, _) => true,
+                _ => false,
+            },
+        };
+        match (&token.kind, token.text) {
+            (TokenKind::Newline, _) | (TokenKind::Punctuation, ";")
+                if open.is_empty() && at >= offset && end.is_none() =>
+            {
+                let mut after = at;
+                while let Some(body) = tokens.next_if(|token| {
+                    matches!(token.kind, TokenKind::Newline | TokenKind::HeredocBody)
+                }) {
+                    if body.kind == TokenKind::HeredocBody {
+                        after = start + body.span.end.offset;
+                    }
+                }
+                end = Some(trimmed(after));
+                if closer.is_none() {
+                    return end;
+                }
+            }
+            (TokenKind::Newline, _) | (TokenKind::Punctuation, ";") => condition = false,
+            (TokenKind::EndOfProgram, _) => break,
+            (TokenKind::Keyword, "do") if condition => condition = false,
+            (TokenKind::Keyword, "def" | "class" | "module" | "begin" | "case" | "do") => {
+                open.push("end")
+            }
+            (TokenKind::Keyword, "for") => {
+                condition = true;
+                open.push("end");
+            }
+            (TokenKind::Keyword, "while" | "until") if beginning => {
+                condition = true;
+                open.push("end");
+            }
+            (TokenKind::Keyword, "if" | "unless") if beginning => open.push("end"),
+            (TokenKind::Punctuation, "(") => open.push(")"),
+            (TokenKind::Punctuation, "[") => open.push("]"),
+            (TokenKind::Punctuation, "{") => open.push("}"),
+            (TokenKind::Keyword, "end") | (TokenKind::Punctuation, ")" | "]" | "}") => {
+                match open.iter().rposition(|closer| *closer == token.text) {
+                    Some(depth) => open.truncate(depth),
+                    None if closer == Some(token.text) && at > start => {
+                        return end.or_else(|| Some(trimmed(at)))
+                    }
+                    // A mismatched closer closes the innermost construct in its place
+                    None => {
+                        open.pop();
+                    }
+                }
+            }
+            _ => {}
+        }
+        if !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment) {
+            previous = Some(token);
+        }
+    }
+    match closer {
+        Some(_) => None,
+        None => end.or(Some(source.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{IdentifierKind::LocalVariable, Input, Node};
+    use crate::parse;
+
+    fn error(source: &str) -> ParseError {
+        parse(Input::new(source)).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_error() {
+        // Unclosed constructs
+        let e = error("def foo\n  1\n");
+        assert_eq!(e.message, "expected `end` to close `def` at 1:1");
+        assert_eq!(e.position, Position::new(12, 3, 1));
+        let e = error("x = (1 + 2");
+        assert_eq!(e.message, "expected `)` to close `(` at 1:5");
+        // Unexpected tokens
+        let e = error("1 +\n");
+        assert_eq!(e.position, Position::new(4, 2, 1));
+        assert!(e.message.starts_with("unexpected end of input"));
+        let e = error("1 + )");
+        assert_eq!(e.position, Position::new(4, 1, 5));
+        assert!(e.message.starts_with("unexpected `)`"));
+        let e = error("foo(1, 2]");
+        assert_eq!(e.position.offset, 8);
+        assert_eq!(e.message, "expected `)` to close `(` at 1:4");
+        assert!(e.expected.contains(&Expected::Closing {
+            token: ")".to_owned(),
+            opener: "(".to_owned(),
+            position: Position::new(3, 1, 4),
+        }));
+        // Only grammar-level tokens are expected, rather than the characters that make up tokens
+        let e = error("foo(");
+        let mut expected = [
+            "!",
+            "&",
+            "(",
+            "*",
+            "**",
+            "-",
+            "::",
+            ";",
+            "BEGIN",
+            "END",
+            "[",
+            "__ENCODING__",
+            "__FILE__",
+            "__LINE__",
+            "alias",
+            "begin",
+            "break",
+            "case",
+            "class",
+            "def",
+            "defined?",
+            "false",
+            "for",
+            "if",
+            "module",
+            "next",
+            "nil",
+            "not",
+            "redo",
+            "retry",
+            "return",
+            "self",
+            "super",
+            "true",
+            "undef",
+            "unless",
+            "until",
+            "while",
+            "yield",
+            "yield(",
+            "{",
+        ]
+        .iter()
+        .map(|token| Expected::Token(token.to_string()))
+        .collect::<Vec<Expected>>();
+        expected.push(Expected::Closing {
+            token: ")".to_owned(),
+            opener: "(".to_owned(),
+            position: Position::new(3, 1, 4),
+        });
+        assert_eq!(e.expected, expected);
+        // Failures that aren't caused by a missing token
+        let e = error("if true\n  BEGIN { 1 }\nend");
+        assert_eq!(e.message, "`BEGIN` is permitted only at the top level");
+        assert_eq!(e.position, Position::new(10, 2, 3));
+        // Rejections don't explain failures elsewhere
+        let mut failures = Failures::default();
+        failures.reject(
+            Position::new(10, 2, 3),
+            "`BEGIN` is permitted only at the top level",
+        );
+        let source = "if true\n  BEGIN { 1 }\nend )";
+        let e = ParseError::new(source, None, false, failures, Position::new(26, 3, 5));
+        assert_eq!(e.message, "unexpected `)`");
+    }
+
+    #[test]
+    fn test_display() {
+        let mut input = Input::new("class Foo\n\tdef bar(\nend\n");
+        input.metadata.file = Some("foo.rb");
+        let e = parse(input).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "error: expected `)` to close `(` at 2:9\n --> foo.rb:2:10\n  |\n2 | \tdef bar(\n  | \t        ^"
+        );
+        let e = error("1 + )");
+        assert_eq!(
+            e.to_string(),
+            format!("error: {}\n --> 1:5\n  |\n1 | 1 + )\n  |     ^", e.message)
+        );
+    }
+
+    #[test]
+    fn test_parse_with_recovery() {
+        use crate::ast::NodeKind;
+        use crate::parse_with_recovery;
+        let statements = |source: &str| {
+            let (program, errors) = parse_with_recovery(Input::new(source));
+            match program.program.kind {
+                NodeKind::Block(nodes) => (nodes, errors),
+                _ => unreachable!(),
+            }
+        };
+        // Valid programs are unaffected
+        let (nodes, errors) = statements("foo\nbar");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::ident("foo", LocalVariable),
+                Node::ident("bar", LocalVariable)
+            ]
+        );
+        assert!(errors.is_empty());
+        // Resynchronizes at separators
+        let (nodes, errors) = statements("foo(1, 2]\nbar; baz ]\nqux");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::new(NodeKind::Error("foo(1, 2]".to_owned())),
+                Node::ident("bar", LocalVariable),
+                Node::new(NodeKind::Error("baz ]".to_owned())),
+                Node::ident("qux", LocalVariable),
+            ]
+        );
+        assert_eq!(nodes[2].span.start, Position::new(15, 2, 6));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "expected `)` to close `(` at 1:4");
+        assert_eq!(errors[1].position, Position::new(19, 2, 10));
+        // Statements that span multiple lines are skipped entirely
+        let (nodes, errors) = statements("foo(1,\n 2]\nbar");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::new(NodeKind::Error("foo(1,\n 2]".to_owned())),
+                Node::ident("bar", LocalVariable),
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        // Stray closers
+        let (nodes, errors) = statements("end\nfoo");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::new(NodeKind::Error("end".to_owned())),
+                Node::ident("foo", LocalVariable),
+            ]
+        );
+        assert!(errors[0]
+            .message
+            .starts_with("unexpected `end`, expected one of"));
+        // Unclosed constructs
+        let (nodes, errors) = statements("def foo\n  1\n");
+        assert_eq!(
+            nodes,
+            vec![Node::new(NodeKind::Error("def foo\n  1\n".to_owned()))]
+        );
+        assert_eq!(errors[0].message, "expected `end` to close `def` at 1:1");
+        // The statements before an unrecoverable one are kept
+        let (nodes, _) = statements("x\ndef foo\n  1\n");
+        assert_eq!(
+            nodes,
+            vec![
+                Node::ident("x", LocalVariable),
+                Node::new(NodeKind::Error("def foo\n  1\n".to_owned())),
+            ]
+        );
+        // Parsing resumes after the skipped statement, keeping the rest of the program
+        let (program, errors) = parse_with_recovery(Input::new(
+            "BEGIN { 1 }\nfoo # a\nbar(]\n# b\nbaz\n__END__\ndata",
+        ));
+        assert_eq!(
+            program.program,
+            Node::new(NodeKind::Block(vec![
+                Node::ident("foo", LocalVariable),
+                Node::new(NodeKind::Error("bar(]".to_owned())),
+                Node::ident("baz", LocalVariable),
+            ]))
+        );
+        assert_eq!(program.program.span.end, Position::new(34, 6, 1));
+        assert_eq!(program.begin_blocks.len(), 1);
+        assert_eq!(program.comments.len(), 2);
+        assert_eq!(program.data.as_deref(), Some("data"));
+        assert_eq!(errors.len(), 1);
+        // Heredoc bodies that follow a skipped statement are still skipped over
+        let (nodes, _) = statements("foo <<A; bar ]\nbody\nA\nbaz");
+        assert_eq!(
+            nodes[1..],
+            [
+                Node::new(NodeKind::Error("bar ]".to_owned())),
+                Node::ident("baz", LocalVariable),
+            ]
+        );
+        // Errors within the bodies of constructs resynchronize at their closers
+        let (nodes, errors) = statements("def f\n  1 +\nend\n5");
+        assert_eq!(nodes[1..], [Node::int(5)]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .message
+            .starts_with("unexpected `end`, expected one of"));
+        let (nodes, errors) = statements("foo { 1 + ) }\n7");
+        assert_eq!(nodes[1..], [Node::int(7)]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, Position::new(10, 1, 11));
+        let while_loop = |body: Vec<Node>| {
+            Node::new(NodeKind::Loop(crate::ast::Loop {
+                kind: crate::ast::LoopKind::While,
+                cond: Box::new(Node::ident("x", LocalVariable)),
+                body: Box::new(Node::new(NodeKind::Block(body))),
+                bindings: None,
+            }))
+        };
+        let (nodes, errors) = statements("while x\n  )\nend\n8");
+        assert_eq!(
+            nodes,
+            vec![
+                while_loop(vec![Node::new(NodeKind::Error(")".to_owned()))]),
+                Node::int(8),
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .message
+            .starts_with("unexpected `)`, expected one of"));
+        // Strings, comments and heredocs are skipped whole
+        let (nodes, errors) = statements("while x\n  ) + \"end\" # }\n  y\nend\nz");
+        assert_eq!(
+            nodes,
+            vec![
+                while_loop(vec![
+                    Node::new(NodeKind::Error(") + \"end\" # }".to_owned())),
+                    Node::ident("y", LocalVariable),
+                ]),
+                Node::ident("z", LocalVariable),
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        let (nodes, _) = statements("while x\n  ) + <<A\nend\nA\n  y\nend\nz");
+        assert_eq!(
+            nodes,
+            vec![
+                while_loop(vec![
+                    Node::new(NodeKind::Error(") + <<A\nend\nA".to_owned())),
+                    Node::ident("y", LocalVariable),
+                ]),
+                Node::ident("z", LocalVariable),
+            ]
+        );
+        // Constructs opened within the skipped statement are skipped along with it
+        let (nodes, _) = statements("while x\n  ) + foo do\n    1\n  end\n  z\nend");
+        assert_eq!(
+            nodes,
+            vec![while_loop(vec![
+                Node::new(NodeKind::Error(") + foo do\n    1\n  end".to_owned())),
+                Node::ident("z", LocalVariable),
+            ])]
+        );
+        let (nodes, _) = statements("while x\n  ) if y\n  z\nend");
+        assert_eq!(
+            nodes,
+            vec![while_loop(vec![
+                Node::new(NodeKind::Error(") if y".to_owned())),
+                Node::ident("z", LocalVariable),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_failure_position() {
+        let input = Input::new("foo\nbar");
+        let e = nom::Err::Error((input.clone(), crate::ErrorKind::Tag));
+        assert_eq!(failure_position(&input, e), Position::new(0, 1, 1));
+        // Running out of input fails at its end
+        let e = nom::Err::Incomplete(nom::Needed::Unknown);
+        assert_eq!(failure_position(&input, e), Position::new(7, 2, 4));
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let incomplete = |source: &str| {
+            let mut input = Input::new(source);
+            input.metadata.partial = true;
+            parse(input).unwrap_err().incomplete
+        };
+        let unclosed = |opener: &str, closer: &str, position: Position| Unclosed {
+            opener: opener.to_owned(),
+            closer: closer.to_owned(),
+            position,
+        };
+        // Complete input that can't be parsed
+        assert_eq!(incomplete("1 + )"), None);
+        // Input that needs more to follow
+        assert_eq!(incomplete("1 +").unwrap().prompt_marker(), '*');
+        let open = incomplete("\"abc").unwrap();
+        assert_eq!(
+            open.unclosed,
+            vec![unclosed("\"", "\"", Position::new(0, 1, 1))]
+        );
+        assert_eq!(open.prompt_marker(), '"');
+        let open = incomplete("foo do\n").unwrap();
+        assert_eq!(
+            open.unclosed,
+            vec![unclosed("do", "end", Position::new(4, 1, 5))]
+        );
+        assert_eq!(open.prompt_marker(), '*');
+        let open = incomplete("foo(1, 'a").unwrap();
+        assert_eq!(
+            open.unclosed,
+            vec![
+                unclosed("(", ")", Position::new(3, 1, 4)),
+                unclosed("'", "'", Position::new(7, 1, 8))
+            ]
+        );
+        assert_eq!(open.prompt_marker(), '\'');
+        let open = incomplete("x = <<~EOS\nabc\n").unwrap();
+        assert_eq!(
+            open.unclosed,
+            vec![unclosed("<<~EOS", "EOS", Position::new(4, 1, 5))]
+        );
+        assert_eq!(open.prompt_marker(), '"');
+        assert_eq!(incomplete("%W[a b").unwrap().prompt_marker(), ']');
+        assert_eq!(incomplete("/ab").unwrap().prompt_marker(), '/');
+        // Complete input is never reported as incomplete
+        assert_eq!(parse(Input::new("\"abc")).unwrap_err().incomplete, None);
+    }
+}
@@ -0,0 +1,475 @@
+use super::{Input, Metadata};
+use crate::parsers::comment::leading_comments;
+use crate::parsers::program::leading_garbage;
+
+/// The UTF-8 byte order mark
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Maps the bytes 0x80 - 0x9F of Windows-1252 to their code points (`None` when undefined)
+const WINDOWS_1252: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
+
+/// Describes the encoding of a program's source
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    UsAscii,
+    /// Binary source, where each byte is decoded to the code point of the same value
+    Ascii8Bit,
+    /// Latin-1 source, where each byte is decoded to the code point of the same value
+    Iso8859_1,
+    Windows1252,
+    ShiftJis,
+    EucJp,
+}
+
+impl Encoding {
+    /// Looks up an encoding by any of the (case-insensitive) names Ruby accepts for it
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_uppercase();
+        // Emacs-style line ending suffixes are ignored
+        let name = ["-UNIX", "-DOS", "-MAC"]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(&name);
+        match name {
+            "UTF-8" | "CP65001" => Some(Self::Utf8),
+            "US-ASCII" | "ASCII" | "ANSI_X3.4-1968" | "646" => Some(Self::UsAscii),
+            "ASCII-8BIT" | "BINARY" => Some(Self::Ascii8Bit),
+            "ISO-8859-1" | "ISO8859-1" => Some(Self::Iso8859_1),
+            "WINDOWS-1252" | "CP1252" => Some(Self::Windows1252),
+            "SHIFT_JIS" => Some(Self::ShiftJis),
+            "EUC-JP" | "EUCJP" => Some(Self::EucJp),
+            _ => None,
+        }
+    }
+
+    /// Returns the encoding's canonical name (as reported by `__ENCODING__`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::UsAscii => "US-ASCII",
+            Self::Ascii8Bit => "ASCII-8BIT",
+            Self::Iso8859_1 => "ISO-8859-1",
+            Self::Windows1252 => "Windows-1252",
+            Self::ShiftJis => "Shift_JIS",
+            Self::EucJp => "EUC-JP",
+        }
+    }
+
+    /// Returns the number of bytes in the character that begins with the given byte
+    ///
+    /// Only meaningful for the lead bytes of valid source.
+    fn sequence_length(&self, lead: u8) -> usize {
+        match self {
+            Self::Utf8 => match lead {
+                0x00..=0x7F => 1,
+                0x80..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                _ => 4,
+            },
+            Self::UsAscii | Self::Ascii8Bit | Self::Iso8859_1 | Self::Windows1252 => 1,
+            Self::ShiftJis => match lead {
+                0x81..=0x9F | 0xE0..=0xFC => 2,
+                _ => 1,
+            },
+            Self::EucJp => match lead {
+                0x8F => 3,
+                0x8E | 0xA1..=0xFE => 2,
+                _ => 1,
+            },
+        }
+    }
+
+    /// Validates or transcodes source bytes of this encoding into UTF-8
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, EncodingError> {
+        let invalid = |offset| EncodingError::InvalidByteSequence {
+            encoding: *self,
+            offset,
+        };
+        match self {
+            Self::Utf8 => std::str::from_utf8(bytes)
+                .map(|s| s.to_owned())
+                .map_err(|e| invalid(e.valid_up_to())),
+            Self::UsAscii => match bytes.iter().position(|b| !b.is_ascii()) {
+                Some(offset) => Err(invalid(offset)),
+                None => Ok(bytes.iter().map(|&b| b as char).collect()),
+            },
+            Self::Ascii8Bit | Self::Iso8859_1 => Ok(bytes.iter().map(|&b| b as char).collect()),
+            Self::Windows1252 => bytes
+                .iter()
+                .enumerate()
+                .map(|(offset, &b)| match b {
+                    0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize].ok_or(invalid(offset)),
+                    _ => Ok(b as char),
+                })
+                .collect(),
+            Self::ShiftJis => Self::transcode(encoding_rs::SHIFT_JIS, bytes).map_err(invalid),
+            Self::EucJp => Self::transcode(encoding_rs::EUC_JP, bytes).map_err(invalid),
+        }
+    }
+
+    /// Transcodes multibyte source into UTF-8, returning the offset of the first invalid byte
+    fn transcode(encoding: &'static encoding_rs::Encoding, bytes: &[u8]) -> Result<String, usize> {
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut text = String::with_capacity(
+            decoder
+                .max_utf8_buffer_length_without_replacement(bytes.len())
+                .unwrap_or(bytes.len()),
+        );
+        match decoder.decode_to_string_without_replacement(bytes, &mut text, true) {
+            (encoding_rs::DecoderResult::Malformed(malformed, unread), read) => {
+                Err(read - malformed as usize - unread as usize)
+            }
+            _ => Ok(text),
+        }
+    }
+}
+
+/// Describes why a program's source could not be decoded
+#[derive(Debug, PartialEq, Clone)]
+pub enum EncodingError {
+    /// The magic comment names an encoding that is unknown or not supported (such as UTF-16)
+    UnsupportedEncoding(String),
+    /// The source contains bytes that are not valid in its encoding
+    InvalidByteSequence { encoding: Encoding, offset: usize },
+}
+
+impl std::fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedEncoding(name) => write!(f, "unknown encoding name - {}", name),
+            Self::InvalidByteSequence { encoding, offset } => write!(
+                f,
+                "invalid multibyte char ({}) at byte {}",
+                encoding.name(),
+                offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// A program's source that has been decoded from bytes
+///
+/// The parser only sees the decoded UTF-8 text, so the offsets of the resulting spans are
+/// offsets into `text()` rather than into the original bytes. Use `source_offset` to find where
+/// they lie in the original bytes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Source {
+    text: String,
+    encoding: Encoding,
+    /// Pairs of corresponding text and source offsets, recorded wherever the lengths of the
+    /// text and the source diverge
+    offsets: Vec<(usize, usize)>,
+    /// Whether everything before the first `#!` line that mentions `ruby` is discarded
+    skip_leading_garbage: bool,
+}
+
+impl Source {
+    /// Decodes a program's source, detecting its encoding from a UTF-8 BOM or a magic comment
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncodingError> {
+        Self::detect(bytes, false)
+    }
+
+    /// Decodes a program's source whose leading garbage is discarded (like `ruby -x`), detecting
+    /// its encoding from a UTF-8 BOM or a magic comment following the `#!` line
+    pub fn from_bytes_skipping_leading_garbage(bytes: &[u8]) -> Result<Self, EncodingError> {
+        Self::detect(bytes, true)
+    }
+
+    fn detect(bytes: &[u8], skip_leading_garbage: bool) -> Result<Self, EncodingError> {
+        if let Some(bytes) = bytes.strip_prefix(BOM) {
+            let mut source = Self::from_bytes_with_encoding(bytes, Encoding::Utf8)?;
+            source.offsets.insert(0, (0, BOM.len()));
+            source.skip_leading_garbage = skip_leading_garbage;
+            return Ok(source);
+        }
+        let encoding = match Self::magic_encoding(bytes, skip_leading_garbage) {
+            Some(name) => {
+                Encoding::from_name(&name).ok_or(EncodingError::UnsupportedEncoding(name))?
+            }
+            None => Encoding::default(),
+        };
+        let mut source = Self::from_bytes_with_encoding(bytes, encoding)?;
+        source.skip_leading_garbage = skip_leading_garbage;
+        Ok(source)
+    }
+
+    /// Decodes a program's source using a known encoding
+    pub fn from_bytes_with_encoding(
+        bytes: &[u8],
+        encoding: Encoding,
+    ) -> Result<Self, EncodingError> {
+        let text = encoding.decode(bytes)?;
+        let mut offsets = vec![];
+        let mut source_offset = 0;
+        for (offset, c) in text.char_indices() {
+            let length = encoding.sequence_length(bytes[source_offset]);
+            source_offset += length;
+            if length != c.len_utf8() {
+                offsets.push((offset + c.len_utf8(), source_offset));
+            }
+        }
+        Ok(Self {
+            text,
+            encoding,
+            offsets,
+            skip_leading_garbage: false,
+        })
+    }
+
+    /// Returns the decoded program text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the encoding the program was decoded from
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Converts an offset into the decoded text (such as that of a span) into the offset of the
+    /// same position within the original bytes
+    pub fn source_offset(&self, offset: usize) -> usize {
+        match self.offsets.partition_point(|&(text, _)| text <= offset) {
+            0 => offset,
+            n => {
+                let (text, source) = self.offsets[n - 1];
+                source + offset - text
+            }
+        }
+    }
+
+    /// Creates the parser's input for the decoded program
+    pub fn input(&self) -> Input<'_> {
+        Input::new_with_metadata(
+            &self.text,
+            Metadata {
+                encoding: self.encoding,
+                skip_leading_garbage: self.skip_leading_garbage,
+                ..Metadata::default()
+            },
+        )
+    }
+
+    /// Finds the encoding named by a magic comment on the first line (or the second after a `#!`)
+    ///
+    /// When leading garbage is discarded, the first line is the `#!` line that mentions `ruby`.
+    fn magic_encoding(bytes: &[u8], skip_leading_garbage: bool) -> Option<String> {
+        // Magic comments are ASCII, so the other bytes may be replaced without harm
+        let text = String::from_utf8_lossy(bytes);
+        let mut i = Input::new(&text);
+        if skip_leading_garbage {
+            i = leading_garbage(i).ok()?.0;
+        }
+        let head: String = i.split_inclusive('\n').take(2).collect();
+        leading_comments(Input::new(&head))
+            .ok()
+            .and_then(|(_, magic)| magic.encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_from_name() {
+        assert_eq!(Some(Encoding::Utf8), Encoding::from_name("utf-8"));
+        assert_eq!(Some(Encoding::Utf8), Encoding::from_name("UTF-8-unix"));
+        assert_eq!(Some(Encoding::UsAscii), Encoding::from_name("ascii"));
+        assert_eq!(Some(Encoding::Ascii8Bit), Encoding::from_name("binary"));
+        assert_eq!(Some(Encoding::Iso8859_1), Encoding::from_name("iso-8859-1"));
+        assert_eq!(Some(Encoding::Windows1252), Encoding::from_name("CP1252"));
+        assert_eq!(Some(Encoding::ShiftJis), Encoding::from_name("shift_jis"));
+        assert_eq!(Some(Encoding::EucJp), Encoding::from_name("eucJP"));
+        assert_eq!(None, Encoding::from_name("UTF-16"));
+        assert_eq!(None, Encoding::from_name("utf-9"));
+    }
+
+    #[test]
+    fn test_source_from_bytes() {
+        fn decode(bytes: &[u8]) -> Result<(String, Encoding), EncodingError> {
+            Source::from_bytes(bytes).map(|s| (s.text().to_owned(), s.encoding()))
+        }
+        // UTF-8 is the default
+        assert_eq!(Ok(("é".to_owned(), Encoding::Utf8)), decode("é".as_bytes()));
+        assert_eq!(
+            Err(EncodingError::InvalidByteSequence {
+                encoding: Encoding::Utf8,
+                offset: 2
+            }),
+            decode(b"a \xff")
+        );
+        // Byte order marks
+        assert_eq!(
+            Ok(("'é'".to_owned(), Encoding::Utf8)),
+            decode(b"\xEF\xBB\xBF'\xC3\xA9'")
+        );
+        // Magic comments
+        assert_eq!(
+            Ok((
+                "# encoding: iso-8859-1\n'é'".to_owned(),
+                Encoding::Iso8859_1
+            )),
+            decode(b"# encoding: iso-8859-1\n'\xE9'")
+        );
+        assert_eq!(
+            Ok((
+                "#!/usr/bin/ruby\n# -*- coding: cp1252 -*-\n'€'".to_owned(),
+                Encoding::Windows1252
+            )),
+            decode(b"#!/usr/bin/ruby\n# -*- coding: cp1252 -*-\n'\x80'")
+        );
+        assert_eq!(
+            Err(EncodingError::InvalidByteSequence {
+                encoding: Encoding::Utf8,
+                offset: 1
+            }),
+            decode(b"'\xff'\n# encoding: binary")
+        );
+        assert_eq!(
+            Err(EncodingError::InvalidByteSequence {
+                encoding: Encoding::UsAscii,
+                offset: 21
+            }),
+            decode(b"# encoding: us-ascii\n\xC3\xA9")
+        );
+        // Multibyte encodings
+        assert_eq!(
+            Ok((
+                "# encoding: Shift_JIS\n'日本'".to_owned(),
+                Encoding::ShiftJis
+            )),
+            decode(b"# encoding: Shift_JIS\n'\x93\xfa\x96\x7b'")
+        );
+        assert_eq!(
+            Ok(("# coding: euc-jp\n'日本'".to_owned(), Encoding::EucJp)),
+            decode(b"# coding: euc-jp\n'\xc6\xfc\xcb\xdc'")
+        );
+        assert_eq!(
+            Err(EncodingError::InvalidByteSequence {
+                encoding: Encoding::ShiftJis,
+                offset: 23
+            }),
+            decode(b"# encoding: Shift_JIS\n'\x93'")
+        );
+        assert_eq!(
+            Err(EncodingError::UnsupportedEncoding("UTF-16".to_owned())),
+            decode(b"# encoding: UTF-16\n")
+        );
+        // Leading garbage
+        let bytes = b"garbage\n#!/usr/bin/env ruby\n# encoding: iso-8859-1\n'\xE9'";
+        let source = Source::from_bytes_skipping_leading_garbage(bytes).unwrap();
+        assert_eq!(Encoding::Iso8859_1, source.encoding());
+        assert!(source.input().metadata.skip_leading_garbage);
+        assert!(crate::parse(source.input()).is_ok());
+        assert_eq!(
+            Err(EncodingError::InvalidByteSequence {
+                encoding: Encoding::Utf8,
+                offset: 52
+            }),
+            decode(bytes)
+        );
+        assert_eq!(
+            Ok(Encoding::Utf8),
+            Source::from_bytes_skipping_leading_garbage(b"# encoding: binary\n#!ruby\n")
+                .map(|s| s.encoding())
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        use crate::ast::{Literal, NodeKind};
+        fn literal(bytes: &[u8]) -> NodeKind {
+            let source = Source::from_bytes(bytes).unwrap();
+            match crate::parse(source.input()).unwrap().program.kind {
+                NodeKind::Block(mut nodes) => nodes.remove(0).kind,
+                _ => unreachable!(),
+            }
+        }
+        let string = |s: &str| NodeKind::Literal(Literal::String(s.to_owned()));
+        // Escaped bytes are in the source's encoding
+        assert_eq!(
+            string("日本"),
+            literal(b"# encoding: Shift_JIS\n\"\x93\xfa\\x96\\x7b\"")
+        );
+        assert_eq!(string("本"), literal(b"# encoding: euc-jp\n\"\\313\\334\""));
+        assert_eq!(string("é€"), literal(b"# encoding: cp1252\n\"\\xE9\\x80\""));
+        assert_eq!(
+            NodeKind::Literal(Literal::Bytes(vec![0x96])),
+            literal(b"# encoding: Shift_JIS\n\"\\x96\"")
+        );
+        // Escapes in UTF-8 source are unaffected
+        assert_eq!(
+            NodeKind::Literal(Literal::Bytes(vec![0xC3])),
+            literal(b"\"\\xC3\"")
+        );
+    }
+
+    #[test]
+    fn test_source_offset() {
+        let spans = |source: &Source| -> Vec<(usize, usize)> {
+            let program = crate::parse(source.input()).unwrap();
+            match program.program.kind {
+                crate::ast::NodeKind::Block(nodes) => nodes
+                    .iter()
+                    .map(|n| {
+                        (
+                            source.source_offset(n.span.start.offset),
+                            source.source_offset(n.span.end.offset),
+                        )
+                    })
+                    .collect(),
+                _ => unreachable!(),
+            }
+        };
+        // Spans are offsets into the UTF-8 text, which are mapped back to the original bytes
+        let bytes = b"# encoding: Shift_JIS\n'\x93\xfa\x96\x7b'; '\xb1'; 1";
+        let source = Source::from_bytes(bytes).unwrap();
+        assert_eq!("'日本'", &source.text()[22..30]);
+        assert_eq!(vec![(22, 28), (30, 33), (35, 36)], spans(&source));
+        assert_eq!(b"'\x93\xfa\x96\x7b'", &bytes[22..28]);
+        let bytes = b"'\xe9' + 1";
+        let source = Source::from_bytes_with_encoding(bytes, Encoding::Iso8859_1).unwrap();
+        assert_eq!(vec![(0, 7)], spans(&source));
+        let source = Source::from_bytes(b"\xEF\xBB\xBF'\xC3\xA9'; 1").unwrap();
+        assert_eq!(vec![(3, 7), (9, 10)], spans(&source));
+    }
+}
@@ -16,7 +16,7 @@ pub enum HeredocIndentation {
 }
 
 /// Tracks heredoc-specific metadata when parsing a heredoc
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct HeredocMetadata<'a> {
     /// Tracks the type of quotation used when parsing a heredoc
     pub(crate) quote_type: Option<HeredocQuoteType>,
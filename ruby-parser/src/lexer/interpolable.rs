@@ -1,26 +1,32 @@
 use super::{Node, Segment};
+use crate::ast::{NodeKind, Span};
 use std::cmp::min;
 
 /// Defines something that may be interpolated
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Interpolatable {
     String(String),
+    /// Content that is not valid UTF-8
+    Bytes(Vec<u8>),
     Interpolated(Vec<Node>),
 }
 
 impl From<Vec<Segment>> for Interpolatable {
     fn from(item: Vec<Segment>) -> Self {
         let mut tokens: Vec<Node> = vec![];
-        let mut string = String::new();
+        let mut bytes: Vec<u8> = vec![];
         let mut interpolated = false;
         for part in item {
             match part {
-                Segment::Char(c) => string.push(c),
-                Segment::String(s) => string.push_str(&s),
+                Segment::Char(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                Segment::String(s) => bytes.extend_from_slice(s.as_bytes()),
+                Segment::Bytes(b) => bytes.extend_from_slice(&b),
                 Segment::Expr(t) => {
-                    if !string.is_empty() {
-                        tokens.push(Node::Segment(Segment::String(string.clone())));
-                        string.clear();
+                    if !bytes.is_empty() {
+                        tokens.push(Node::new(crate::ast::NodeKind::Segment(Self::segment(
+                            bytes,
+                        ))));
+                        bytes = vec![];
                     }
                     tokens.push(*t);
                     interpolated = true;
@@ -28,96 +34,106 @@ impl From<Vec<Segment>> for Interpolatable {
             }
         }
         if interpolated {
-            if !string.is_empty() {
-                tokens.push(Node::Segment(Segment::String(string.clone())));
+            if !bytes.is_empty() {
+                tokens.push(Node::new(crate::ast::NodeKind::Segment(Self::segment(
+                    bytes,
+                ))));
             }
             Self::Interpolated(tokens)
         } else {
-            Self::String(string)
+            match String::from_utf8(bytes) {
+                Ok(s) => Self::String(s),
+                Err(e) => Self::Bytes(e.into_bytes()),
+            }
         }
     }
 }
 
 impl Interpolatable {
-    /// Strips leading indentation from the content according to the rules for squiggly heredocs
-    pub fn to_unindented(self) -> Self {
-        match self {
-            Self::Interpolated(tokens) => Self::Interpolated(Self::unindent_tokens(tokens)),
-            Self::String(string) => {
-                let mut tokens =
-                    Self::unindent_tokens(vec![Node::Segment(Segment::String(string))]);
-                if let Node::Segment(Segment::String(string)) = tokens.remove(0) {
-                    Self::String(string)
-                } else {
-                    unreachable!()
-                }
-            }
+    /// Converts a sequence of bytes into a string segment when they form valid UTF-8
+    fn segment(bytes: Vec<u8>) -> Segment {
+        match String::from_utf8(bytes) {
+            Ok(s) => Segment::String(s),
+            Err(e) => Segment::Bytes(e.into_bytes()),
         }
     }
-    fn unindent_tokens(mut tokens: Vec<Node>) -> Vec<Node> {
-        let mut after_newline = true;
-        let mut indentation = usize::MAX;
-        // Determine the indentation level
-        for t in &tokens {
-            if let Node::Segment(Segment::String(string)) = t {
-                for line in string.lines() {
-                    let mut whitespace = 0usize;
-                    if after_newline {
-                        for c in line.chars() {
-                            match c {
-                                ' ' | '\t' => whitespace += 1,
-                                _ => {
-                                    // Short-circuit if no adjustments are needed
-                                    if whitespace == 0 {
-                                        return tokens;
-                                    }
-                                    indentation = min(indentation, whitespace);
-                                    break;
-                                }
-                            }
-                        }
+
+    /// Gives the string segments of interpolated content the spans of source between its
+    /// interpolations, given the span of the entire content
+    pub(crate) fn with_segment_spans(mut self, span: Span) -> Self {
+        if let Self::Interpolated(nodes) = &mut self {
+            for index in 0..nodes.len() {
+                if let NodeKind::Segment(_) = nodes[index].kind {
+                    let start = match index {
+                        0 => span.start,
+                        _ => nodes[index - 1].span.end,
                     };
-                    after_newline = true;
+                    let end = match nodes.get(index + 1) {
+                        Some(next) => next.span.start,
+                        None => span.end,
+                    };
+                    nodes[index].span = Span::new(start, end);
                 }
-            } else {
-                after_newline = false;
             }
         }
-        // Return if no adjustments need to be made
-        if indentation == usize::MAX {
-            return tokens;
+        self
+    }
+
+    /// Builds the content of a squiggly heredoc, stripping leading indentation the same way MRI does
+    ///
+    /// Tabs advance to the next multiple of 8, lines containing only whitespace do not count
+    /// towards the indentation level, and text following an interpolation is never dedented.
+    /// Escaped whitespace is treated as content.
+    pub fn from_unindented(segments: Vec<Segment>) -> Self {
+        let width = Self::indentation(&segments);
+        if width == 0 {
+            return Self::from(segments);
         }
-        // Adjust the indentation of string segments accordingly
-        after_newline = true;
-        let mut whitespace = indentation;
-        for t in &mut tokens {
-            if let Node::Segment(Segment::String(ref mut string)) = t {
-                let mut new_string = String::new();
-                for c in string.chars() {
-                    match c {
-                        ' ' | '\t' => {
-                            if after_newline && whitespace > 0 {
-                                whitespace -= 1;
-                                continue;
-                            }
-                            new_string.push(c);
-                        }
-                        '\n' => {
-                            after_newline = true;
-                            whitespace = indentation;
-                            new_string.push(c);
-                        }
-                        _ => {
-                            after_newline = false;
-                            new_string.push(c);
-                        }
-                    };
+        let mut column = Some(0usize);
+        let segments = segments
+            .into_iter()
+            .filter(|segment| {
+                match (segment, column) {
+                    (Segment::Char(' '), Some(c)) if c < width => {
+                        column = Some(c + 1);
+                        return false;
+                    }
+                    (Segment::Char('\t'), Some(c)) if c < width && Self::tab_stop(c) <= width => {
+                        column = Some(Self::tab_stop(c));
+                        return false;
+                    }
+                    (Segment::Char('\n'), _) => column = Some(0),
+                    _ => column = None,
+                }
+                true
+            })
+            .collect::<Vec<Segment>>();
+        Self::from(segments)
+    }
+
+    /// Determines the indentation level of the least-indented line
+    fn indentation(segments: &[Segment]) -> usize {
+        let mut indentation = usize::MAX;
+        let mut column = Some(0usize);
+        for segment in segments {
+            match (segment, column) {
+                (Segment::Char(' '), Some(c)) => column = Some(c + 1),
+                (Segment::Char('\t'), Some(c)) => column = Some(Self::tab_stop(c)),
+                (Segment::Char('\r'), _) => {}
+                // Lines consisting solely of whitespace are ignored
+                (Segment::Char('\n'), _) => column = Some(0),
+                (_, Some(c)) => {
+                    indentation = min(indentation, c);
+                    column = None;
                 }
-                *string = new_string;
-            } else {
-                after_newline = false;
+                (_, None) => {}
             }
         }
-        tokens
+        indentation
+    }
+
+    /// Returns the column reached by a tab character
+    fn tab_stop(column: usize) -> usize {
+        (column / 8 + 1) * 8
     }
 }
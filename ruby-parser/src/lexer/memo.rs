@@ -0,0 +1,134 @@
+use super::{Input, NodeResult, Token};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Remembers what a parser produced at each position of the input
+///
+/// The parser backtracks freely, so the same expression is otherwise reparsed by every
+/// alternative that begins with it. Each level of nesting (such as an interpolation within a
+/// string) multiplies the work of the levels above it.
+#[derive(Default)]
+pub(crate) struct Memo<'a> {
+    results: HashMap<(&'static str, usize), Vec<Entry<'a>>>,
+}
+
+/// What a parser produced for an input, along with the tokens that it recorded
+type Entry<'a> = (Input<'a>, NodeResult<'a>, Vec<Token<'a>>);
+
+impl<'a> Memo<'a> {
+    /// Returns what the named parser produced when it was last given an equivalent input
+    ///
+    /// The tokens that the parser recorded (see `TokenLog`) are returned along with the result.
+    pub(crate) fn get(
+        &self,
+        parser: &'static str,
+        i: &Input<'a>,
+    ) -> Option<(NodeResult<'a>, &[Token<'a>])> {
+        self.results
+            .get(&(parser, i.offset()))?
+            .iter()
+            .find(|(input, _, _)| Self::equivalent(input, i))
+            .map(|(_, res, tokens)| (res.clone(), &tokens[..]))
+    }
+
+    /// Records what the named parser produced for the given input
+    pub(crate) fn insert(
+        &mut self,
+        parser: &'static str,
+        i: Input<'a>,
+        res: NodeResult<'a>,
+        tokens: Vec<Token<'a>>,
+    ) {
+        self.results
+            .entry((parser, i.offset()))
+            .or_default()
+            .push((i, res, tokens));
+    }
+
+    /// The number of times that a memoized parser has been run
+    #[cfg(test)]
+    pub(crate) fn runs(&self) -> usize {
+        self.results.values().map(Vec::len).sum()
+    }
+
+    /// Determines whether parsing either input would produce the same result
+    ///
+    /// Aside from the position, this depends on the pending heredoc jump, the lexer state and
+    /// the parser's settings.
+    fn equivalent(a: &Input<'a>, b: &Input<'a>) -> bool {
+        a == b
+            && a.metadata.file == b.metadata.file
+            && a.metadata.skip_leading_garbage == b.metadata.skip_leading_garbage
+            && a.metadata.partial == b.metadata.partial
+            && a.metadata.encoding == b.metadata.encoding
+            && a.metadata.quote_delimiter == b.metadata.quote_delimiter
+            && a.metadata.heredoc == b.metadata.heredoc
+            && match (&a.remaining_input, &b.remaining_input) {
+                (Some(a), Some(b)) => Self::equivalent(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl fmt::Debug for Memo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memo")
+            .field("results", &self.results.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::program::program;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_memoized_parse() {
+        // Memoized results must be the same as the results of parsing without a memo
+        for source in [
+            "1 + 2 * 3",
+            "foo.bar[1]::Baz",
+            "\"a#{b}c\" 'd'",
+            "x ? 1 : 2",
+            "<<A + \"#{<<B}\"\na\nA\nb\nB\n",
+            "%w[a b] if (1..)",
+        ]
+        .iter()
+        {
+            let memo = RefCell::new(Memo::default());
+            let mut i = Input::new(*source);
+            i.metadata.memo = Some(&memo);
+            let memoized = nom::combinator::all_consuming(program)(i).map(|(_, v)| v);
+            let direct = nom::combinator::all_consuming(program)(Input::new(*source));
+            assert_eq!(direct.map(|(_, v)| v), memoized, "{:?}", source);
+            assert!(memo.borrow().runs() > 0, "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let i = Input::new("foo");
+        assert!(Memo::equivalent(&i, &i.clone()));
+        let mut j = i.clone();
+        j.metadata.file = Some("foo.rb");
+        assert!(!Memo::equivalent(&i, &j));
+        let mut j = i.clone();
+        j.metadata.partial = true;
+        assert!(!Memo::equivalent(&i, &j));
+        let mut j = i.clone();
+        j.metadata.skip_leading_garbage = true;
+        assert!(!Memo::equivalent(&i, &j));
+        let mut j = i.clone();
+        j.metadata.encoding = crate::lexer::Encoding::Ascii8Bit;
+        assert!(!Memo::equivalent(&i, &j));
+        let mut j = i.clone();
+        j.metadata.quote_delimiter = Some('"');
+        assert!(!Memo::equivalent(&i, &j));
+        let mut j = i.clone();
+        j.remaining_input = Some(Box::new(Input::new("bar")));
+        assert!(!Memo::equivalent(&i, &j));
+    }
+}
@@ -1,16 +1,32 @@
+use super::encoding::Encoding;
 use super::heredoc::HeredocMetadata;
+use super::memo::Memo;
+use super::token_log::TokenLog;
+use crate::error::Failures;
+use std::cell::RefCell;
 
 /// Tracks parser-specific metadata
 #[derive(Debug, Clone, Default)]
 pub struct Metadata<'a> {
     /// Provides a reference to the name of the file being parsed
     pub file: Option<&'a str>,
+    /// Discards everything before the first `#!` line that mentions `ruby` (like `ruby -x`)
+    pub skip_leading_garbage: bool,
+    /// Treats the input as possibly incomplete (such as a line entered into a REPL)
+    pub partial: bool,
+    /// The encoding of the program's source
+    pub encoding: Encoding,
     /// Tracks the delimiter used when parsing a quoted string
     pub(crate) quote_delimiter: Option<char>,
     /// Tracks heredoc-specific lexer state
     pub(crate) heredoc: Option<Box<HeredocMetadata<'a>>>,
-    /// Tracks parser stack depth
-    pub(crate) stack_depth: usize,
+    /// Records the furthest failure for error reporting (only tracked by `parse`)
+    pub(crate) failures: Option<&'a RefCell<Failures>>,
+    /// Remembers the results of expensive parsers (only tracked by `parse`)
+    pub(crate) memo: Option<&'a RefCell<Memo<'a>>>,
+    /// Collects every comment that is parsed, along with the tokens of the successful parse when
+    /// building a concrete syntax tree (only tracked by `parse`)
+    pub(crate) tokens: Option<&'a RefCell<TokenLog<'a>>>,
 }
 
 #[cfg(test)]
@@ -19,6 +35,6 @@ mod test {
 
     #[test]
     fn test_metadata_size() {
-        assert_eq!(40, std::mem::size_of::<Metadata>());
+        assert_eq!(56, std::mem::size_of::<Metadata>());
     }
 }
@@ -1,20 +1,29 @@
 //! Provides types / enums / structs for defining parser combinators
 
+mod encoding;
 mod heredoc;
 mod interpolable;
+mod memo;
 mod metadata;
 mod nom_prelude;
 mod segment;
+mod token;
+mod token_log;
 mod tracked_location;
 
 pub use crate::ast::{
-    Identifier, IdentifierKind, Interpolated, Literal, Node, Parameter, Program, WhenClause,
+    Identifier, IdentifierKind, Interpolated, Literal, Node, NodeKind, Parameter, Position,
+    Program, Span, WhenClause,
 };
+pub use encoding::{Encoding, EncodingError, Source};
 pub use heredoc::{HeredocIndentation, HeredocMetadata, HeredocQuoteType};
 pub use interpolable::Interpolatable;
+pub(crate) use memo::Memo;
 pub use metadata::Metadata;
 pub(crate) use nom_prelude::*;
 pub use segment::Segment;
+pub use token::{LiteralKind, Token, TokenKind, Tokens};
+pub(crate) use token_log::TokenLog;
 pub use tracked_location::TrackedLocation;
 
 /// Describes the parser's input type
@@ -38,6 +47,9 @@ pub(crate) type LiteralResult<'a> = Parsed<'a, Literal>;
 /// Describes a parsed character
 pub(crate) type CharResult<'a> = Parsed<'a, char>;
 
+/// Describes a parsed byte
+pub(crate) type ByteResult<'a> = Parsed<'a, u8>;
+
 /// Describes an interpolated result
 pub(crate) type InterpolatableResult<'a> = Parsed<'a, Interpolatable>;
 
@@ -1,13 +1,17 @@
 pub(crate) use crate::parsers::program::{no_lt, ws0};
 pub(crate) use nom::branch::alt;
-pub(crate) use nom::character::complete::{anychar, char, none_of, one_of};
+pub(crate) use nom::character::complete::{anychar, none_of, one_of};
 pub(crate) use nom::combinator::{map, not, opt, peek, recognize, value, verify};
 pub(crate) use nom::multi::{many0, many1, many_m_n, separated_list0, separated_list1};
 pub(crate) use nom::sequence::{delimited, preceded, terminated, tuple};
 
-use crate::lexer::{Input, LexResult};
+use crate::ast::Span;
+use crate::lexer::{
+    CharResult, Input, InterpolatableResult, LexResult, NodeResult, Parsed, TokenKind,
+};
 use crate::parsers::token::identifier::identifier_character;
 use nom::bytes::complete::tag as nom_tag;
+use nom::character::complete::char as nom_char;
 
 /// Recognizes a pattern
 ///
@@ -15,15 +19,260 @@ use nom::bytes::complete::tag as nom_tag;
 ///
 /// It will return `Err(Err::Error((_, ErrorKind::Tag)))` if the input doesn't match the pattern
 ///
+/// A matching tag is recorded as a token of the concrete syntax tree: a reserved word, punctuation
+/// or an operator.
+///
 /// See documentation for `nom::bytes::complete::tag` for more info.
 pub fn tag(tag: &str) -> impl Fn(Input) -> LexResult + '_
 where {
     move |i: Input| {
-        let is_identchar = peek(identifier_character)(Input::new(tag.clone())).is_ok();
-        let (i, res) = nom_tag(tag)(i)?;
+        let res = matching_tag(tag, i.clone());
+        match &res {
+            Ok((j, _)) => record(&i, j, tag_kind(tag)),
+            Err(_) => expected(&i, tag),
+        }
+        res
+    }
+}
+
+/// Recognizes a single character
+///
+/// See documentation for `nom::character::complete::char` for more info.
+pub fn char<'a>(c: char) -> impl Fn(Input<'a>) -> CharResult<'a> {
+    move |i: Input<'a>| {
+        let res = nom_char(c)(i.clone());
+        let mut buffer = [0; 4];
+        let c = c.encode_utf8(&mut buffer);
+        match &res {
+            Ok((j, _)) => record(&i, j, tag_kind(c)),
+            Err(_) => expected(&i, c),
+        }
+        res
+    }
+}
+
+/// Describes the token that a grammar-level tag is recorded as
+fn tag_kind(tag: &str) -> TokenKind {
+    match tag {
+        "::" | "=>" | "&." | "." | "(" | ")" | "[" | "]" | "{" | "}" | "," | ";" => {
+            TokenKind::Punctuation
+        }
+        _ if tag.starts_with(|c: char| c.is_alphabetic() || c == '_') => TokenKind::Keyword,
+        _ => TokenKind::Operator,
+    }
+}
+
+/// Variants of `tag` and `char` for recognizing the characters that make up a token
+///
+/// These are imported in place of the grammar-level variants by the parsers of identifiers,
+/// literals and comments. Their failures only record how far parsing got, since an expected
+/// `_` or `\` doesn't help explain an error (with the exception of the token that closes an
+/// open construct, such as the `"` of a string). Nor are their matches recorded as tokens, since
+/// the parsers that use them record the entire token.
+pub mod lexical {
+    use super::{matching_tag, nom_char};
+    use crate::lexer::{CharResult, Input, LexResult};
+
+    /// Recognizes a pattern within a token
+    pub fn tag(tag: &str) -> impl Fn(Input) -> LexResult + '_
+where {
+        move |i: Input| {
+            let res = matching_tag(tag, i.clone());
+            if res.is_err() {
+                probed(&i, tag);
+            }
+            res
+        }
+    }
+
+    /// Recognizes a single character within a token
+    pub fn char<'a>(c: char) -> impl Fn(Input<'a>) -> CharResult<'a> {
+        move |i: Input<'a>| {
+            let res = nom_char(c)(i.clone());
+            if res.is_err() {
+                probed(&i, c.encode_utf8(&mut [0; 4]));
+            }
+            res
+        }
+    }
+
+    /// Records how far parsing got for error reporting
+    fn probed(i: &Input, token: &str) {
+        if let Some(failures) = i.metadata.failures {
+            failures.borrow_mut().probed(i.position(), token);
+        }
+    }
+}
+
+/// Matches a tag, which must not be followed by an identifying character when it ends with one
+fn matching_tag<'a>(tag: &str, i: Input<'a>) -> LexResult<'a> {
+    let is_identchar = peek(identifier_character)(Input::new(tag.clone())).is_ok();
+    nom_tag(tag)(i).and_then(|(j, res)| {
         if is_identchar {
-            peek(not(identifier_character))(i.clone())?;
+            peek(not(identifier_character))(j.clone())?;
+        }
+        Ok((j, res))
+    })
+}
+
+/// Records a token that failed to match for error reporting
+fn expected(i: &Input, token: &str) {
+    if let Some(failures) = i.metadata.failures {
+        failures.borrow_mut().expected(i.position(), token);
+    }
+}
+
+/// Records the token that a parser consumed for the concrete syntax tree (see `TokenLog`)
+///
+/// The token's kind is described by the parser's output.
+pub fn token<'a, O, F, K>(kind: K, mut f: F) -> impl FnMut(Input<'a>) -> Parsed<'a, O>
+where
+    F: FnMut(Input<'a>) -> Parsed<'a, O>,
+    K: Fn(&O) -> TokenKind,
+{
+    move |i: Input<'a>| {
+        let (j, res) = f(i.clone())?;
+        record(&i, &j, kind(&res));
+        Ok((j, res))
+    }
+}
+
+/// Records a token for the concrete syntax tree
+fn record<'a>(i: &Input<'a>, j: &Input<'a>, kind: TokenKind) {
+    if let Some(tokens) = i.metadata.tokens {
+        tokens.borrow_mut().record(kind, i, j);
+    }
+}
+
+/// Parses a construct that begins with `opener` and must be closed by `closer`
+///
+/// A missing closer is reported along with the location of its opener.
+pub fn construct<'a, O, F>(
+    opener: &'static str,
+    closer: &'static str,
+    mut f: F,
+) -> impl FnMut(Input<'a>) -> Parsed<'a, O>
+where
+    F: FnMut(Input<'a>) -> Parsed<'a, O>,
+{
+    move |i: Input<'a>| match i.metadata.failures {
+        Some(failures) if i.starts_with(opener) => {
+            failures.borrow_mut().open(opener, closer, i.position());
+            let res = f(i);
+            failures.borrow_mut().close();
+            res
+        }
+        _ => f(i),
+    }
+}
+
+/// Parses a statement, recording where it began so that recovery can resume after it
+pub fn recoverable<'a, O, F>(mut f: F) -> impl FnMut(Input<'a>) -> Parsed<'a, O>
+where
+    F: FnMut(Input<'a>) -> Parsed<'a, O>,
+{
+    move |i: Input<'a>| match i.metadata.failures {
+        Some(failures) => {
+            failures.borrow_mut().begin_statement(i.offset());
+            let res = f(i);
+            failures.borrow_mut().end_statement();
+            res
+        }
+        None => f(i),
+    }
+}
+
+/// Parses a percent literal (such as `%w[...]`) as a construct closed by its ending delimiter
+pub fn percent_construct<'a, O, F>(mut f: F) -> impl FnMut(Input<'a>) -> Parsed<'a, O>
+where
+    F: FnMut(Input<'a>) -> Parsed<'a, O>,
+{
+    move |i: Input<'a>| {
+        let failures = match i.metadata.failures {
+            Some(failures) if i.starts_with('%') => failures,
+            _ => return f(i),
+        };
+        let prefix = 1 + i[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .count();
+        let delimiter = match i[prefix..].chars().next() {
+            Some(c) => c,
+            None => return f(i),
+        };
+        let closer = match delimiter {
+            '{' => '}',
+            '(' => ')',
+            '[' => ']',
+            '<' => '>',
+            c => c,
+        };
+        let opener = i[..prefix + delimiter.len_utf8()].to_owned();
+        failures
+            .borrow_mut()
+            .open(opener, closer.to_string(), i.position());
+        let res = f(i);
+        failures.borrow_mut().close();
+        res
+    }
+}
+
+/// Remembers the result of a parser at each position, so that backtracking doesn't repeat it
+///
+/// Parsers that are memoized must not depend on anything but their input. The tokens that a
+/// parser recorded are recorded again when its result is reused.
+pub fn memoized<'a, F>(name: &'static str, mut f: F) -> impl FnMut(Input<'a>) -> NodeResult<'a>
+where
+    F: FnMut(Input<'a>) -> NodeResult<'a>,
+{
+    move |i: Input<'a>| {
+        let memo = match i.metadata.memo {
+            Some(memo) => memo,
+            None => return f(i),
+        };
+        if let Some((res, recorded)) = memo.borrow().get(name, &i) {
+            if let Some(tokens) = i.metadata.tokens {
+                let mut tokens = tokens.borrow_mut();
+                recorded.iter().for_each(|token| tokens.push(token.clone()));
+            }
+            return res;
+        }
+        let res = f(i.clone());
+        let recorded = match (&res, i.metadata.tokens) {
+            (Ok(_), Some(tokens)) => tokens.borrow().since(i.offset()),
+            _ => vec![],
+        };
+        memo.borrow_mut().insert(name, i, res.clone(), recorded);
+        res
+    }
+}
+
+/// Records the spans of source that the string segments of interpolated content were parsed from
+pub fn interpolatable<'a, F>(mut f: F) -> impl FnMut(Input<'a>) -> InterpolatableResult<'a>
+where
+    F: FnMut(Input<'a>) -> InterpolatableResult<'a>,
+{
+    move |i: Input<'a>| {
+        let start = i.position();
+        let (i, contents) = f(i)?;
+        let span = Span::new(start, i.position());
+        Ok((i, contents.with_segment_spans(span)))
+    }
+}
+
+/// Records the span of source that a node's parser consumed
+///
+/// Nodes that were given a span by an inner parser (such as those passed through by `alt`) keep it.
+pub fn spanned<'a, F>(mut f: F) -> impl FnMut(Input<'a>) -> NodeResult<'a>
+where
+    F: FnMut(Input<'a>) -> NodeResult<'a>,
+{
+    move |i: Input<'a>| {
+        let start = i.position();
+        let (i, mut node) = f(i)?;
+        if node.span.is_unset() {
+            node.span = Span::new(start, i.position());
         }
-        Ok((i, res))
+        Ok((i, node))
     }
 }
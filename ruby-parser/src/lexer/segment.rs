@@ -1,10 +1,13 @@
 use super::*;
 
 /// Defines a segment of something that may be interpolated
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Segment {
     Char(char),
     String(String),
+    /// Raw bytes that may not form valid UTF-8 (such as from `\xff` or `\377`)
+    Bytes(Vec<u8>),
     Expr(Box<Node>),
 }
 
@@ -12,4 +15,12 @@ impl Segment {
     pub fn expr(v: Node) -> Self {
         Self::Expr(Box::new(v))
     }
+    /// Creates a segment from a single byte, which is only a character when within the ASCII range
+    pub fn byte(v: u8) -> Self {
+        if v.is_ascii() {
+            Self::String((v as char).to_string())
+        } else {
+            Self::Bytes(vec![v])
+        }
+    }
 }
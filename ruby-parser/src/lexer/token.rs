@@ -0,0 +1,725 @@
+use crate::lexer::*;
+use crate::parsers::comment::comment;
+use crate::parsers::expression::conditional::recursing_conditional_operator_expression;
+use crate::parsers::program::{
+    end_of_program_marker, line_terminator, line_terminator_escape_sequence,
+};
+use crate::parsers::token::identifier::{
+    assignment_like_method_identifier, class_variable_identifier, constant_identifier,
+    global_variable_identifier, identifier_character, identifier_start_character,
+    instance_variable_identifier, local_variable_identifier, method_only_identifier,
+};
+use crate::parsers::token::keyword::keyword;
+use crate::parsers::token::literal::string::{
+    character_literal, double_quoted_string, external_command_execution, here_document,
+    quoted_expanded_literal_string, quoted_non_expanded_literal_string, single_quoted_string,
+};
+use crate::parsers::token::literal::{
+    array_literal, numeric_literal, regular_expression_literal, symbol,
+};
+use crate::parsers::token::operator::{assignment_operator, operator};
+use nom::InputTake;
+use std::collections::VecDeque;
+
+/// A token of a program's source
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The source that the token was lexed from
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Describes the kind of a token
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenKind {
+    /// A reserved word (such as `def` or `end`)
+    Keyword,
+    /// An identifier (reserved words that are used as method names are method identifiers)
+    Identifier(IdentifierKind),
+    /// A literal (string-like literals are single tokens, including any interpolated code)
+    Literal(LiteralKind),
+    /// An operator (such as `+` or `&&=`)
+    Operator,
+    /// Punctuation (such as `(`, `,` or the `|` that delimits a block's parameters)
+    Punctuation,
+    /// A single line comment or an `=begin`/`=end` block (or the garbage that precedes a program's
+    /// shebang when it is skipped)
+    Comment,
+    /// Whitespace within a line (including an escaped line terminator and a byte order mark)
+    Whitespace,
+    /// A line terminator
+    Newline,
+    /// The lines of a heredoc, up to and including its ending identifier
+    HeredocBody,
+    /// `__END__` and the data that follows it
+    EndOfProgram,
+    /// A character that could not be lexed
+    Error,
+}
+
+/// Describes the kind of a literal token
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum LiteralKind {
+    Integer,
+    Float,
+    String,
+    Character,
+    Command,
+    Symbol,
+    Regex,
+    /// A percent array (such as `%W[a b]`)
+    Array,
+    /// A heredoc's signifier (such as `<<~EOS`), whose lines follow as a `HeredocBody` token
+    Heredoc,
+}
+
+/// Tracks what the parser would expect to follow the previous token
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum State {
+    /// The beginning of an expression, where `/` begins a regex and `?` a character literal
+    Beginning,
+    /// The end of an expression, where `/` divides and `?` begins a conditional
+    End,
+    /// After an identifier that may be a command, where an operator-like character that follows
+    /// whitespace (but isn't itself followed by whitespace) begins the command's first argument
+    Argument { spaced: bool },
+    /// After `.`, `::` or `def`, where reserved words and operators are method names
+    Name { after_def: bool },
+}
+
+/// Tracks whether a `|` delimits a block's parameters
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum BlockParameters {
+    None,
+    /// A block has just been opened
+    Opened,
+    /// Within a block's parameters
+    Open,
+}
+
+/// Lexes a program's source into a stream of tokens
+///
+/// Every character of the source belongs to exactly one token, and tokens are yielded in order
+/// of appearance. A heredoc's signifier is followed by the rest of its starting line, after which
+/// the heredoc's lines are yielded as a `HeredocBody` token. The bodies of the heredocs started
+/// within a token's interpolated code follow as a single `HeredocBody` token.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    input: Input<'a>,
+    /// The entire source, for reading the bodies of heredocs
+    source: &'a str,
+    /// The offset of the start of the source
+    base: usize,
+    state: State,
+    block: BlockParameters,
+    /// The offsets at which the bodies of the heredocs started on the current line end (at any
+    /// depth of interpolation)
+    heredocs: Vec<usize>,
+    /// The number of conditionals whose `?` hasn't been followed by its `:` yet
+    conditionals: usize,
+    queued: VecDeque<Token<'a>>,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(input: Input<'a>) -> Self {
+        Self {
+            source: *input,
+            base: input.offset(),
+            input,
+            state: State::Beginning,
+            block: BlockParameters::None,
+            heredocs: vec![],
+            conditionals: 0,
+            queued: VecDeque::new(),
+        }
+    }
+    /// Lexes the token at the start of the input
+    fn token(&self, i: Input<'a>) -> Parsed<'a, TokenKind> {
+        let beginning = match self.state {
+            State::Beginning => true,
+            State::Argument { spaced: true } => {
+                let mut chars = i.chars();
+                let first = chars.next();
+                matches!(first, Some(c) if "/-+*&?:[%<".contains(c))
+                    && matches!(chars.next(), Some(c) if !c.is_whitespace())
+                    // Like the parser, `a ?b :c` is read as a conditional
+                    && match first {
+                        Some('?') => recursing_conditional_operator_expression(i.clone()).is_err(),
+                        Some(':') => self.conditionals == 0,
+                        _ => true,
+                    }
+            }
+            _ => false,
+        };
+        if let Ok(res) = alt((
+            value(TokenKind::Newline, line_terminator),
+            value(
+                TokenKind::Whitespace,
+                alt((
+                    recognize(many1(one_of(" \t\x0b\x0c\r"))),
+                    line_terminator_escape_sequence,
+                )),
+            ),
+            value(TokenKind::Comment, comment),
+            value(TokenKind::EndOfProgram, end_of_program_marker),
+        ))(i.clone())
+        {
+            return Ok(res);
+        }
+        if let State::Name { after_def } = self.state {
+            if let Ok(res) = method_name(i.clone(), after_def) {
+                return Ok(res);
+            }
+        }
+        if beginning {
+            if let Ok(res) = alt((
+                map(here_document, |_| TokenKind::Literal(LiteralKind::Heredoc)),
+                map(regular_expression_literal, |_| {
+                    TokenKind::Literal(LiteralKind::Regex)
+                }),
+                map(character_literal, |_| {
+                    TokenKind::Literal(LiteralKind::Character)
+                }),
+                map(array_literal, |_| TokenKind::Literal(LiteralKind::Array)),
+                map(quoted_non_expanded_literal_string, |_| {
+                    TokenKind::Literal(LiteralKind::String)
+                }),
+                map(quoted_expanded_literal_string, |_| {
+                    TokenKind::Literal(LiteralKind::String)
+                }),
+                map(symbol, |_| TokenKind::Literal(LiteralKind::Symbol)),
+                numeric,
+            ))(i.clone())
+            {
+                return Ok(res);
+            }
+        }
+        if self.block != BlockParameters::None {
+            let delimiter = match self.block {
+                BlockParameters::Opened => alt((tag("||"), tag("|")))(i.clone()),
+                _ => tag("|")(i.clone()),
+            };
+            if let Ok((i, _)) = delimiter {
+                return Ok((i, TokenKind::Punctuation));
+            }
+        }
+        alt((
+            map(single_quoted_string, |_| {
+                TokenKind::Literal(LiteralKind::String)
+            }),
+            map(double_quoted_string, |_| {
+                TokenKind::Literal(LiteralKind::String)
+            }),
+            map(external_command_execution, |_| {
+                TokenKind::Literal(LiteralKind::Command)
+            }),
+            // Only a number without a sign can follow the end of an expression
+            preceded(peek(one_of("0123456789")), numeric),
+            value(TokenKind::Keyword, keyword),
+            map(
+                alt((
+                    terminated(method_only_identifier, peek(not(char('=')))),
+                    local_variable_identifier,
+                    global_variable_identifier,
+                    class_variable_identifier,
+                    instance_variable_identifier,
+                    constant_identifier,
+                )),
+                |identifier| TokenKind::Identifier(identifier.kind),
+            ),
+            value(
+                TokenKind::Punctuation,
+                alt((
+                    tag("::"),
+                    tag("=>"),
+                    tag("&."),
+                    recognize(terminated(char('.'), peek(not(char('.'))))),
+                    recognize(one_of("()[]{},;")),
+                )),
+            ),
+            value(
+                TokenKind::Operator,
+                alt((
+                    assignment_operator,
+                    tag("->"),
+                    tag("..."),
+                    tag(".."),
+                    tag("||"),
+                    tag("&&"),
+                    tag("!="),
+                    tag("!~"),
+                    operator,
+                    recognize(one_of("?:")),
+                )),
+            ),
+            value(TokenKind::Error, recognize(anychar)),
+        ))(i)
+    }
+    /// Updates the lexer's state to reflect the token that was just lexed
+    fn advance(&mut self, token: &Token) {
+        match (&token.kind, token.text) {
+            (TokenKind::Operator, "?") => self.conditionals += 1,
+            (TokenKind::Operator, ":") => self.conditionals = self.conditionals.saturating_sub(1),
+            _ => {}
+        }
+        self.block = match (self.block, &token.kind, token.text) {
+            (_, TokenKind::Whitespace, _)
+            | (_, TokenKind::Newline, _)
+            | (_, TokenKind::Comment, _) => self.block,
+            (BlockParameters::Opened, TokenKind::Punctuation, "|") => BlockParameters::Open,
+            (BlockParameters::Open, TokenKind::Punctuation, "|") => BlockParameters::None,
+            (BlockParameters::Open, _, _) => BlockParameters::Open,
+            (_, TokenKind::Keyword, "do") => BlockParameters::Opened,
+            (_, TokenKind::Punctuation, "{") if self.state != State::Beginning => {
+                BlockParameters::Opened
+            }
+            _ => BlockParameters::None,
+        };
+        self.state = match (&token.kind, token.text) {
+            (TokenKind::Whitespace, _) => match self.state {
+                State::Argument { .. } => State::Argument { spaced: true },
+                state => state,
+            },
+            (TokenKind::Comment, _) => self.state,
+            (TokenKind::Keyword, "def") => State::Name { after_def: true },
+            // `def self.name`
+            (TokenKind::Keyword, "self") if self.state == State::Name { after_def: true } => {
+                self.state
+            }
+            (TokenKind::Keyword, "super") | (TokenKind::Keyword, "yield") => {
+                State::Argument { spaced: false }
+            }
+            (
+                TokenKind::Keyword,
+                "end" | "self" | "nil" | "true" | "false" | "redo" | "retry" | "__FILE__"
+                | "__LINE__" | "__ENCODING__",
+            ) => State::End,
+            (TokenKind::Identifier(kind), _) => match (self.state, kind) {
+                (State::Name { after_def: true }, _)
+                | (_, IdentifierKind::AssignmentMethod)
+                | (_, IdentifierKind::GlobalVariable)
+                | (_, IdentifierKind::NthReference)
+                | (_, IdentifierKind::BackReference)
+                | (_, IdentifierKind::SpecialVariable)
+                | (_, IdentifierKind::ClassVariable)
+                | (_, IdentifierKind::InstanceVariable) => State::End,
+                _ => State::Argument { spaced: false },
+            },
+            (TokenKind::Literal(_), _) => State::End,
+            (TokenKind::Punctuation, ")") | (TokenKind::Punctuation, "]") => State::End,
+            (TokenKind::Punctuation, "}") => State::End,
+            (TokenKind::Punctuation, ".")
+            | (TokenKind::Punctuation, "&.")
+            | (TokenKind::Punctuation, "::") => State::Name {
+                after_def: self.state == State::Name { after_def: true },
+            },
+            (TokenKind::Operator, _) if matches!(self.state, State::Name { .. }) => State::End,
+            _ => State::Beginning,
+        };
+    }
+    /// Whether the bodies of the heredocs started on the current line end before `offset`
+    fn ends_before(&self, offset: usize) -> bool {
+        self.heredocs.iter().all(|end| *end < offset)
+    }
+    /// Queues the line terminator that ends a heredoc's starting line, followed by the bodies of
+    /// the heredocs that were started on it
+    ///
+    /// Returns the input following the last body.
+    fn queue_heredoc_bodies(&mut self, at: &Input<'a>) -> Input<'a> {
+        let start = at.offset() - self.base;
+        let mut i = Input::new_with_pos(&self.source[start..], at.offset(), at.line(), at.char());
+        for end in std::mem::take(&mut self.heredocs) {
+            let (rest, newline) = match line_terminator(i.clone()) {
+                Ok(res) => res,
+                Err(_) => break,
+            };
+            self.queued
+                .push_back(token(TokenKind::Newline, &i, newline.len()).1);
+            let (rest, body) = token(TokenKind::HeredocBody, &rest, end - rest.offset());
+            self.queued.push_back(body);
+            i = rest;
+        }
+        self.state = State::Beginning;
+        i
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.queued.pop_front() {
+            return Some(token);
+        }
+        if self.input.is_empty() {
+            // The rest of a heredoc's starting line has been lexed
+            let next = self.input.jump()?;
+            let at = self.input.clone();
+            self.queue_heredoc_bodies(&at);
+            self.input = next;
+            return self.next();
+        }
+        let i = self.input.clone();
+        let (rest, kind) = self.token(i.clone()).ok()?;
+        let heredoc = kind == TokenKind::Literal(LiteralKind::Heredoc);
+        // Heredocs may also be started within a token's interpolated code (such as `"#{<<A}"`)
+        let started = match &rest.remaining_input {
+            Some(remaining) => self.ends_before(remaining.offset()),
+            None => false,
+        };
+        let token = match &rest.remaining_input {
+            // Lexing continues with the rest of the heredoc's starting line
+            Some(remaining) if started => {
+                self.heredocs.push(remaining.offset());
+                token(kind, &i, rest.offset() - i.offset()).1
+            }
+            // The heredoc ended its starting line, so its body has already been consumed
+            _ if heredoc => {
+                // The heredoc consumed the line terminator ending the input, which is lexed
+                // after its body
+                let consumed = &self.source[..rest.offset() - self.base];
+                let end = consumed.trim_end_matches('\n').trim_end_matches('\r').len();
+                let end = if rest.is_empty() {
+                    self.base + end
+                } else {
+                    rest.offset()
+                };
+                self.heredocs.push(end);
+                let len = i.find(&['\r', '\n'][..]).unwrap_or(i.len());
+                let (at, token) = token(kind, &i, len);
+                let after = self.queue_heredoc_bodies(&at);
+                self.input = if end < rest.offset() { after } else { rest };
+                return Some(token);
+            }
+            // The token reached the end of a heredoc's starting line, and parsing jumped past
+            // the heredoc's body
+            _ if rest.offset() - i.offset() > i.len() => {
+                let (at, token) = token(kind, &i, i.len());
+                // Source that the token consumed after the bodies (such as the body of a heredoc
+                // started within its interpolated code) follows them
+                if self.ends_before(rest.offset()) {
+                    self.heredocs.push(rest.offset());
+                }
+                self.advance(&token);
+                self.queue_heredoc_bodies(&at);
+                self.input = rest;
+                return Some(token);
+            }
+            _ => token(kind, &i, rest.offset() - i.offset()).1,
+        };
+        self.input = rest;
+        self.advance(&token);
+        Some(token)
+    }
+}
+
+/// Returns the token of the given length at the start of the input, along with the rest of the input
+pub(crate) fn token<'a>(kind: TokenKind, i: &Input<'a>, len: usize) -> (Input<'a>, Token<'a>) {
+    // Taking the rest of a heredoc's starting line shouldn't jump past the heredoc's body
+    let mut i = i.clone();
+    i.remaining_input = None;
+    let (rest, text) = i.take_split(len);
+    let span = Span::new(i.position(), rest.position());
+    (
+        rest,
+        Token {
+            kind,
+            text: *text,
+            span,
+        },
+    )
+}
+
+/// Lexes a numeric literal
+fn numeric(i: Input) -> Parsed<TokenKind> {
+    map(numeric_literal, |node| match node.kind {
+        NodeKind::Literal(Literal::Float(_)) => TokenKind::Literal(LiteralKind::Float),
+        _ => TokenKind::Literal(LiteralKind::Integer),
+    })(i)
+}
+
+/// Lexes a method name that follows `.`, `::` or `def`
+fn method_name(i: Input, after_def: bool) -> Parsed<TokenKind> {
+    if after_def {
+        let res = alt((
+            value(TokenKind::Keyword, terminated(tag("self"), peek(char('.')))),
+            map(assignment_like_method_identifier, |identifier| {
+                TokenKind::Identifier(identifier.kind)
+            }),
+            value(
+                TokenKind::Operator,
+                alt((
+                    tag("[]="),
+                    tag("[]"),
+                    tag("+@"),
+                    tag("-@"),
+                    tag("!="),
+                    tag("!~"),
+                    tag("!"),
+                    operator,
+                )),
+            ),
+        ))(i.clone());
+        if res.is_ok() {
+            return res;
+        }
+    }
+    alt((
+        map(constant_identifier, |identifier| {
+            TokenKind::Identifier(identifier.kind)
+        }),
+        value(
+            TokenKind::Identifier(IdentifierKind::Method),
+            recognize(tuple((
+                identifier_start_character,
+                many0(identifier_character),
+                opt(terminated(one_of("!?"), peek(not(char('='))))),
+            ))),
+        ),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lexes the source, omitting whitespace
+    fn lex(source: &str) -> Vec<(TokenKind, &str)> {
+        Tokens::new(Input::new(source))
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        use IdentifierKind::*;
+        use LiteralKind::*;
+        use TokenKind::*;
+        assert_eq!(lex(""), vec![]);
+        assert_eq!(
+            lex("def foo(a) # hi\n  a.end?\nend"),
+            vec![
+                (Keyword, "def"),
+                (Identifier(Method), "foo"),
+                (Punctuation, "("),
+                (Identifier(LocalVariable), "a"),
+                (Punctuation, ")"),
+                (Comment, "# hi"),
+                (Newline, "\n"),
+                (Identifier(LocalVariable), "a"),
+                (Punctuation, "."),
+                (Identifier(Method), "end?"),
+                (Newline, "\n"),
+                (Keyword, "end"),
+            ]
+        );
+        // `/` and `?` depend on what precedes them
+        assert_eq!(
+            lex("a /b/ + c / 2 ? ?d : :e"),
+            vec![
+                (Identifier(LocalVariable), "a"),
+                (Literal(Regex), "/b/"),
+                (Operator, "+"),
+                (Identifier(LocalVariable), "c"),
+                (Operator, "/"),
+                (Literal(Integer), "2"),
+                (Operator, "?"),
+                (Literal(Character), "?d"),
+                (Operator, ":"),
+                (Literal(Symbol), ":e"),
+            ]
+        );
+        assert_eq!(
+            lex("x -1; x - 1"),
+            vec![
+                (Identifier(LocalVariable), "x"),
+                (Literal(Integer), "-1"),
+                (Punctuation, ";"),
+                (Identifier(LocalVariable), "x"),
+                (Operator, "-"),
+                (Literal(Integer), "1"),
+            ]
+        );
+        assert_eq!(
+            lex("f { |x| x || @y }"),
+            vec![
+                (Identifier(LocalVariable), "f"),
+                (Punctuation, "{"),
+                (Punctuation, "|"),
+                (Identifier(LocalVariable), "x"),
+                (Punctuation, "|"),
+                (Identifier(LocalVariable), "x"),
+                (Operator, "||"),
+                (Identifier(InstanceVariable), "@y"),
+                (Punctuation, "}"),
+            ]
+        );
+        assert_eq!(
+            lex("=begin\nx\n=end\n__END__\ndata"),
+            vec![
+                (Comment, "=begin\nx\n=end\n"),
+                (EndOfProgram, "__END__\ndata")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditional_tokens() {
+        use IdentifierKind::*;
+        use TokenKind::*;
+        // The tokens agree with the parser, which reads a conditional rather than a command
+        // called with a character literal and a symbol
+        for source in ["a ?b :c", "a ?b : c", "a ? b :c"].iter() {
+            let program = crate::parse(Input::new(source)).unwrap();
+            let spans = match &program.program.kind {
+                NodeKind::Block(nodes) => match &nodes[0].kind {
+                    NodeKind::Conditional(v) => vec![v.cond.span, v.then.span, v.otherwise.span],
+                    kind => panic!("{:?}", kind),
+                },
+                _ => unreachable!(),
+            };
+            let tokens: Vec<Token> = Tokens::new(Input::new(source))
+                .filter(|token| token.kind != Whitespace)
+                .collect();
+            let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind.clone()).collect();
+            assert_eq!(
+                vec![
+                    Identifier(LocalVariable),
+                    Operator,
+                    Identifier(LocalVariable),
+                    Operator,
+                    Identifier(LocalVariable)
+                ],
+                kinds,
+                "{:?}",
+                source
+            );
+            assert_eq!(
+                spans,
+                vec![tokens[0].span, tokens[2].span, tokens[4].span],
+                "{:?}",
+                source
+            );
+        }
+        assert_eq!(
+            lex("a ?b"),
+            vec![
+                (Identifier(LocalVariable), "a"),
+                (Literal(LiteralKind::Character), "?b"),
+            ]
+        );
+        assert_eq!(
+            lex("a :b"),
+            vec![
+                (Identifier(LocalVariable), "a"),
+                (Literal(LiteralKind::Symbol), ":b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heredoc_tokens() {
+        use LiteralKind::*;
+        use TokenKind::*;
+        assert_eq!(
+            lex("f(<<~A, <<B)\n  a\nA\nb\nB\nx = <<C\nc\nC\n"),
+            vec![
+                (Identifier(IdentifierKind::LocalVariable), "f"),
+                (Punctuation, "("),
+                (Literal(Heredoc), "<<~A"),
+                (Punctuation, ","),
+                (Literal(Heredoc), "<<B"),
+                (Punctuation, ")"),
+                (Newline, "\n"),
+                (HeredocBody, "  a\nA"),
+                (Newline, "\n"),
+                (HeredocBody, "b\nB"),
+                (Newline, "\n"),
+                (Identifier(IdentifierKind::LocalVariable), "x"),
+                (Operator, "="),
+                (Literal(Heredoc), "<<C"),
+                (Newline, "\n"),
+                (HeredocBody, "c\nC"),
+                (Newline, "\n"),
+            ]
+        );
+        // Heredocs started within interpolated code share the line's queue
+        assert_eq!(
+            lex("[<<A, \"#{<<B}\"]\na\nA\nb\nB\n__LINE__"),
+            vec![
+                (Punctuation, "["),
+                (Literal(Heredoc), "<<A"),
+                (Punctuation, ","),
+                (Literal(String), "\"#{<<B}\""),
+                (Punctuation, "]"),
+                (Newline, "\n"),
+                (HeredocBody, "a\nA"),
+                (Newline, "\n"),
+                (HeredocBody, "b\nB"),
+                (Newline, "\n"),
+                (Keyword, "__LINE__"),
+            ]
+        );
+        assert_eq!(
+            lex("<<A + \"#{<<B}\"\na\nA\nb\nB\n")[3..],
+            [
+                (Newline, "\n"),
+                (HeredocBody, "a\nA"),
+                (Newline, "\n"),
+                (HeredocBody, "b\nB"),
+                (Newline, "\n"),
+            ]
+        );
+        // Spans cover the source without gaps
+        let source = "f(<<A) + 1\nb\nA\n";
+        let spans: Vec<(usize, usize, usize)> = Tokens::new(Input::new(source))
+            .map(|token| {
+                (
+                    token.span.start.offset,
+                    token.span.end.offset,
+                    token.span.start.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 1, 1),
+                (1, 2, 1),
+                (2, 5, 1),
+                (5, 6, 1),
+                (6, 7, 1),
+                (7, 8, 1),
+                (8, 9, 1),
+                (9, 10, 1),
+                (10, 11, 1),
+                (11, 14, 2),
+                (14, 15, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lossless() {
+        for source in [
+            include_str!("../../../test.rb"),
+            "#!/usr/bin/env ruby\n# encoding: utf-8\n\nx = 1 # trailing\n",
+            "=begin\ndoc\n=end\nfoo\r\nbar \\\n  baz\n__END__\ndata",
+            "'a\\'b' \"c#{d}e\" `ls #{g}` %w[h i] %Q(j#{k}) ?l",
+            ":sym :\"quoted #{m}\" :+ /re#{n}/i {o: 1, 'p': 2, q:, **r}",
+            "0x1f 1_000 2.5e-3 3r 4i -5 @a @@b $c ::D E::F",
+            "[<<A, \"#{<<B}\"]\na\nA\nb\nB\n__LINE__",
+            "x(\"#{<<B + <<C}\", <<D)\nb\nB\nc\nC\nd\nD\ny",
+            "<<~'A' if x\n  a\n  A\n",
+        ]
+        .iter()
+        {
+            let text: String = Tokens::new(Input::new(source))
+                .map(|token| token.text)
+                .collect();
+            assert_eq!(&text, source);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use super::token::token;
+use super::{Input, LiteralKind, Node, Token, TokenKind};
+use crate::parsers::program::line_terminator;
+use std::collections::{BTreeMap, HashSet};
+
+/// Records the comments and tokens that the parser consumes
+///
+/// Comments are always collected, so that they can be returned alongside the program. Tokens are
+/// only recorded when building the concrete syntax tree (see `TokenLog::recording`).
+///
+/// Tokens are recorded as the parsers of identifiers, literals, reserved words, punctuation and
+/// trivia succeed. The parser backtracks freely, so a recorded token replaces any token that ends
+/// after it begins: once parsing succeeds, the log holds the tokens of the successful parse.
+///
+/// The bodies of heredocs are jumped over rather than consumed in order, so their extents are
+/// recorded separately and fill in the gaps that they leave.
+#[derive(Debug, Default)]
+pub(crate) struct TokenLog<'a> {
+    /// Every comment that is parsed, keyed by its offset
+    pub(crate) comments: BTreeMap<usize, Node>,
+    /// The source whose tokens are recorded
+    input: Option<Input<'a>>,
+    tokens: Vec<Token<'a>>,
+    /// The start offsets of heredoc signifiers and the end offsets of their bodies, keyed by the
+    /// start offsets of the bodies
+    heredocs: BTreeMap<usize, (usize, usize)>,
+}
+
+impl<'a> TokenLog<'a> {
+    /// Returns a log that also records the tokens of the given source
+    pub(crate) fn recording(input: &Input<'a>) -> Self {
+        let mut input = input.clone();
+        input.metadata = Default::default();
+        input.remaining_input = None;
+        Self {
+            input: Some(input),
+            ..Self::default()
+        }
+    }
+
+    /// Records a token that was parsed from `i`, leaving `j`
+    ///
+    /// A token that reaches the end of a heredoc's starting line ends there, as parsing then
+    /// jumps past the heredoc's body.
+    pub(crate) fn record(&mut self, kind: TokenKind, i: &Input<'a>, j: &Input<'a>) {
+        let len = j.offset().saturating_sub(i.offset()).min(i.len());
+        if len > 0 && self.input.is_some() {
+            self.push(token(kind, i, len).1);
+        }
+    }
+
+    /// Records a token that was recorded by an earlier parse of the same input
+    pub(crate) fn push(&mut self, token: Token<'a>) {
+        let start = token.span.start.offset;
+        while matches!(self.tokens.last(), Some(last) if last.span.end.offset > start) {
+            self.tokens.pop();
+        }
+        self.tokens.push(token);
+    }
+
+    /// Records the extent of a heredoc's body, up to and including its ending identifier, along
+    /// with where the heredoc's signifier starts
+    pub(crate) fn heredoc(&mut self, signifier: usize, start: usize, end: usize) {
+        if self.input.is_some() {
+            self.heredocs.insert(start, (signifier, end));
+        }
+    }
+
+    /// Returns the tokens that have been recorded since the given offset
+    pub(crate) fn since(&self, offset: usize) -> Vec<Token<'a>> {
+        let start = self
+            .tokens
+            .iter()
+            .rposition(|token| token.span.start.offset < offset)
+            .map_or(0, |index| index + 1);
+        self.tokens[start..].to_vec()
+    }
+
+    /// Returns every token of the source, in order of appearance
+    ///
+    /// The gaps left by heredoc bodies are filled by a line terminator and the body of each
+    /// heredoc. Blank whitespace that was consumed piecemeal is joined into a single token.
+    ///
+    /// The tokens' text is taken from `source`, which must be the source that was parsed.
+    pub(crate) fn to_tokens<'s>(&self, source: &'s str) -> Vec<Token<'s>> {
+        let input = match &self.input {
+            Some(input) => input,
+            None => return vec![],
+        };
+        // Only the bodies of heredocs that were part of the successful parse fill gaps
+        let signifiers: HashSet<usize> = self
+            .heredocs
+            .values()
+            .map(|(signifier, _)| *signifier)
+            .filter(|signifier| self.started(*signifier))
+            .collect();
+        let mut tokens: Vec<Token<'a>> = vec![];
+        let mut i = input.clone();
+        for recorded in self.tokens.iter().cloned() {
+            i = self.fill(&mut tokens, i, recorded.span.start.offset, &signifiers);
+            // Tokens of code interpolated into a heredoc's body belong to the body
+            if recorded.span.start.offset < i.offset() {
+                continue;
+            }
+            i = token(TokenKind::Whitespace, &i, recorded.text.len()).0;
+            match tokens.last_mut() {
+                Some(last) if blank(last) && blank(&recorded) => last.span.end = recorded.span.end,
+                _ => tokens.push(recorded),
+            }
+        }
+        let end = input.offset() + input.len();
+        self.fill(&mut tokens, i, end, &signifiers);
+        let base = input.offset();
+        tokens
+            .into_iter()
+            .map(|token| Token {
+                kind: token.kind,
+                text: &source[token.span.start.offset - base..token.span.end.offset - base],
+                span: token.span,
+            })
+            .collect()
+    }
+
+    /// Whether a heredoc whose signifier starts at the given offset was part of the successful
+    /// parse, either as a token of its own or within a literal's interpolated code
+    fn started(&self, signifier: usize) -> bool {
+        let index = self
+            .tokens
+            .partition_point(|token| token.span.start.offset <= signifier);
+        match index.checked_sub(1).map(|index| &self.tokens[index]) {
+            Some(token) if token.span.start.offset == signifier => {
+                token.kind == TokenKind::Literal(LiteralKind::Heredoc)
+            }
+            Some(token) => {
+                matches!(token.kind, TokenKind::Literal(_)) && token.span.end.offset > signifier
+            }
+            None => false,
+        }
+    }
+
+    /// Fills the gap up to the given offset, returning the input that follows it
+    ///
+    /// A heredoc body that begins at the end of the gap is also taken, since the tokens recorded
+    /// within it belong to it.
+    fn fill(
+        &self,
+        tokens: &mut Vec<Token<'a>>,
+        mut i: Input<'a>,
+        end: usize,
+        signifiers: &HashSet<usize>,
+    ) -> Input<'a> {
+        loop {
+            let body = match self.heredocs.get(&i.offset()) {
+                Some((signifier, body)) if signifiers.contains(signifier) => Some(*body),
+                _ => None,
+            };
+            let (rest, token) = if let Some(body) = body {
+                token(TokenKind::HeredocBody, &i, body - i.offset())
+            } else if i.offset() >= end {
+                return i;
+            } else if let Ok((rest, _)) = line_terminator(i.clone()) {
+                token(TokenKind::Newline, &i, rest.offset() - i.offset())
+            } else {
+                let len = end - i.offset();
+                let len = i[..len].find('\n').filter(|n| *n > 0).unwrap_or(len);
+                token(TokenKind::Error, &i, len)
+            };
+            tokens.push(token);
+            i = rest;
+        }
+    }
+}
+
+/// Whether the token is whitespace other than an escaped line terminator
+fn blank(token: &Token) -> bool {
+    token.kind == TokenKind::Whitespace && !token.text.starts_with('\\')
+}
@@ -1,3 +1,4 @@
+use crate::ast::Position;
 use core::ops::{Deref, Range, RangeFrom, RangeFull, RangeTo};
 use core::str::{CharIndices, Chars, FromStr};
 use nom::error::{ErrorKind, ParseError};
@@ -59,6 +60,9 @@ impl<T: AsBytes, X> TrackedLocation<T, X> {
     pub fn beginning_of_line(&self) -> bool {
         self.char == 1
     }
+    pub fn position(&self) -> Position {
+        Position::new(self.offset, self.line, self.char)
+    }
 }
 
 impl<T: AsBytes, X: Default> TrackedLocation<T, X> {
@@ -272,33 +276,45 @@ impl<'a, X: Clone> Slice<RangeFrom<usize>> for TrackedLocation<&'a str, X> {
             return self.clone();
         }
         let next_fragment = self.input.slice(range);
-        if let Some(j) = &self.remaining_input {
-            if next_fragment.input_len() == 0 {
-                return (**j).to_owned();
+        if next_fragment.input_len() == 0 {
+            if let Some(j) = self.jump() {
+                return j;
             }
         };
         self.next_from_slice(next_fragment)
     }
 }
 
+// Ranges may extend past a jump in the input (such as when recognizing whitespace that
+// continues after a heredoc), in which case only the fragment before the jump is kept
 impl<'a, X: Clone> Slice<RangeTo<usize>> for TrackedLocation<&'a str, X> {
     fn slice(&self, range: RangeTo<usize>) -> Self {
-        self.next_from_slice(self.input.slice(range))
+        let end = range.end.min(self.input.len());
+        self.next_from_slice(self.input.slice(..end))
     }
 }
 
 impl<'a, X: Clone> Slice<Range<usize>> for TrackedLocation<&'a str, X> {
     fn slice(&self, range: Range<usize>) -> Self {
-        self.next_from_slice(self.input.slice(range))
+        let end = range.end.min(self.input.len());
+        self.next_from_slice(self.input.slice(range.start.min(end)..end))
     }
 }
 
 impl<'a, X: Clone> TrackedLocation<&'a str, X> {
+    /// Resumes parsing from the remaining input, carrying over the current metadata
+    pub(crate) fn jump(&self) -> Option<Self> {
+        self.remaining_input.as_ref().map(|j| {
+            let mut j = (**j).to_owned();
+            j.metadata = self.metadata.clone();
+            j
+        })
+    }
     fn next_from_slice(&self, next_fragment: &'a str) -> Self {
         let consumed_len = self.input.offset(&next_fragment);
-        if let Some(j) = &self.remaining_input {
-            if self.input.input_len() == 0 {
-                return (**j).to_owned();
+        if self.input.input_len() == 0 {
+            if let Some(j) = self.jump() {
+                return j;
             }
         };
         if consumed_len == 0 {
@@ -12,6 +12,31 @@ but minor deviations from the spec have been made when necessary (namely, re-ord
 parsers to consume the largest production first).
 
 The top-level parser combinators that return tokens are publically exported within the parsers module.
+
+## Tokens
+
+Tools that only need tokens (such as syntax highlighters) can lex a program with `tokenize`, which
+yields every token of the source (including comments and whitespace) along with its span.
+`parse_syntax_tree` parses a program into a lossless concrete syntax tree (see `cst::SyntaxTree`)
+instead, built from the tokens that the parser consumed, which reproduces the source exactly.
+
+## Traversal
+
+The `visit` module provides the `Visit`, `VisitMut` and `Fold` traits, which walk every node of
+an abstract syntax tree so that consumers only need to handle the nodes they care about.
+`Node::children()` and `Node::children_mut()` iterate over a single node's children.
+
+## Printing
+
+`unparse` renders a program back to Ruby source (and `unparse::node` renders a single node),
+inserting parentheses where precedence requires them. Parsing the printed source produces an equal
+program, which makes it suitable for code generation and rewriting tools.
+
+## Serialization
+
+With the `serde` feature enabled, every type of the syntax tree implements serde's `Serialize` and
+`Deserialize`. The `serialize` module encodes programs as JSON (following a documented schema) or
+as a compact, versioned binary encoding for caching.
 !*/
 
 extern crate nom;
@@ -19,12 +44,119 @@ extern crate nom;
 #[macro_use]
 mod macros;
 pub mod ast;
+pub mod cst;
+mod error;
 pub mod lexer;
 mod parsers;
+mod recovery;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod unparse;
+pub mod visit;
 
+pub use error::{Expected, Incomplete, ParseError, Unclosed};
 pub use nom::error::ErrorKind;
+pub use unparse::UnparseError;
 
 /// Parses a ruby program
-pub fn parse(i: lexer::Input) -> lexer::ProgramResult {
-    parsers::program::program(i)
+///
+/// The entire input must be consumed. When it can't be, the error describes the furthest point
+/// that parsing reached. Partial input (see `Metadata::partial`) that ends before the program does
+/// produces an error that describes what is still open.
+#[allow(clippy::result_large_err)]
+pub fn parse(i: lexer::Input) -> Result<ast::Program, ParseError> {
+    parse_program(i, false).map(|(program, _)| program)
+}
+
+/// Parses a ruby program into a lossless concrete syntax tree
+///
+/// The tree is built from the tokens that the parser consumed (including whitespace, comments,
+/// parentheses and keywords), and concatenating them reproduces the source byte-for-byte. Errors
+/// are reported like `parse`.
+#[allow(clippy::result_large_err)]
+pub fn parse_syntax_tree(i: lexer::Input) -> Result<cst::SyntaxTree, ParseError> {
+    parse_program(i, true).map(|(program, tokens)| cst::SyntaxTree::new(program, tokens))
+}
+
+/// Parses a ruby program, recording the tokens that were consumed if requested
+#[allow(clippy::result_large_err)]
+fn parse_program(
+    i: lexer::Input,
+    record: bool,
+) -> Result<(ast::Program, Vec<lexer::Token>), ParseError> {
+    let failures = std::cell::RefCell::new(error::Failures::default());
+    let (source, file, partial) = (*i, i.metadata.file, i.metadata.partial);
+    let res = {
+        let memo = std::cell::RefCell::new(lexer::Memo::default());
+        let tokens = std::cell::RefCell::new(if record {
+            let input = lexer::Input::new_with_pos(source, i.offset(), i.line(), i.char());
+            lexer::TokenLog::recording(&input)
+        } else {
+            lexer::TokenLog::default()
+        });
+        let metadata = lexer::Metadata {
+            file,
+            skip_leading_garbage: i.metadata.skip_leading_garbage,
+            partial,
+            encoding: i.metadata.encoding,
+            failures: Some(&failures),
+            memo: Some(&memo),
+            tokens: Some(&tokens),
+            ..lexer::Metadata::default()
+        };
+        let input =
+            lexer::Input::new_with_pos_and_meta(source, i.offset(), i.line(), i.char(), metadata);
+        let res = nom::combinator::all_consuming(parsers::program::program)(input.clone());
+        match res {
+            Ok((_, program)) if record => Ok((program, tokens.borrow().to_tokens(source))),
+            Ok((_, program)) => Ok((program, vec![])),
+            Err(e) => Err(error::failure_position(&input, e)),
+        }
+    };
+    match res {
+        Ok(res) => Ok(res),
+        Err(position) => Err(ParseError::new(
+            source,
+            file,
+            partial,
+            failures.into_inner(),
+            position,
+        )),
+    }
+}
+
+/// Lexes a ruby program into a stream of tokens
+///
+/// Context-sensitive tokens (such as `/`, which may begin a regex or divide) are lexed as the
+/// parser would lex them. Source that can't be lexed is yielded as error tokens.
+pub fn tokenize(i: lexer::Input) -> lexer::Tokens {
+    lexer::Tokens::new(i)
+}
+
+/// Renders a ruby program back to source
+///
+/// Parsing the rendered source produces a program with an equal body and `BEGIN` blocks. Ordinary
+/// comments are not rendered. Programs that hold `NodeKind::Placeholder` nodes (which the parser
+/// currently produces for method calls, assignments, definitions and other constructs) can only be
+/// rendered by `unparse_with_source`. See the `unparse` module for the nodes that are rendered as
+/// different source.
+pub fn unparse(program: &ast::Program) -> Result<String, UnparseError> {
+    unparse::program(program)
+}
+
+/// Renders a ruby program that was parsed from `source` back to source
+///
+/// This renders programs like `unparse`, except that `NodeKind::Placeholder` nodes are rendered
+/// as the source they were parsed from.
+pub fn unparse_with_source(program: &ast::Program, source: &str) -> Result<String, UnparseError> {
+    unparse::program_with_source(program, source)
+}
+
+/// Parses a ruby program, recovering from syntax errors
+///
+/// Statements that can't be parsed are skipped up to the next statement separator (or the `end` or
+/// `}` that closes their enclosing construct) and replaced by `NodeKind::Error` nodes. Every error
+/// is returned alongside the tree for the rest of the program.
+pub fn parse_with_recovery(i: lexer::Input) -> (ast::Program, Vec<ParseError>) {
+    recovery::parse_with_recovery(i)
 }
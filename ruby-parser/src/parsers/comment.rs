@@ -1,12 +1,32 @@
 //! Provides parsers for comments
 
+use crate::ast::{MagicComments, ShareableConstantValue};
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::*;
+use crate::parsers::token::identifier::identifier_character;
+use nom::bytes::complete::tag_no_case;
+use nom::multi::many_till;
 
 /// *single_line_comment* | *multi_line_comment*
+///
+/// Comments are recorded as they are parsed, so that they can be returned alongside the program
 pub(crate) fn comment(i: Input) -> NodeResult {
-    let (i, content) = alt((single_line_comment, multi_line_comment))(i)?;
-    Ok((i, Node::Comment(content.to_string())))
+    let (i, comment) = token(
+        |_| TokenKind::Comment,
+        spanned(map(
+            alt((single_line_comment, multi_line_comment)),
+            |content| Node::new(NodeKind::Comment(content.to_string())),
+        )),
+    )(i)?;
+    if let Some(tokens) = i.metadata.tokens {
+        tokens
+            .borrow_mut()
+            .comments
+            .entry(comment.span.start.offset)
+            .or_insert_with(|| comment.clone());
+    }
+    Ok((i, comment))
 }
 
 /// `#` *comment_content*?
@@ -70,6 +90,121 @@ pub(crate) fn comment_line(i: Input) -> LexResult {
     recognize(tuple((opt(comment_content), line_terminator)))(i)
 }
 
+/// ( *whitespace* | *line_terminator* | *comment* )*
+///
+/// Consumes the comments that precede a program's first token, interpreting any magic comments
+pub(crate) fn leading_comments(i: Input) -> Parsed<MagicComments> {
+    let first_line = i.line();
+    let (i, comments) = many0(alt((
+        map(comment, Some),
+        map(alt((whitespace, line_terminator)), |_| None),
+    )))(i)?;
+    let mut magic = MagicComments::default();
+    let mut after_shebang = false;
+    for comment in comments.into_iter().flatten() {
+        let (line, comment) = match comment.kind {
+            NodeKind::Comment(text) => (comment.span.start.line, text),
+            _ => unreachable!(),
+        };
+        if line == first_line && comment.starts_with("#!") {
+            after_shebang = true;
+        }
+        if line == first_line || (line == first_line + 1 && after_shebang) {
+            if let Ok((_, encoding)) = encoding_comment(Input::new(&comment)) {
+                magic.encoding = Some(encoding);
+            }
+        }
+        if let Ok((_, pairs)) = magic_comment(Input::new(&comment)) {
+            for (key, value) in pairs {
+                apply_magic_comment(&mut magic, &key, &value);
+            }
+        }
+    }
+    Ok((i, magic))
+}
+
+/// `#` 0x20* ( *emacs_magic_comment* | *magic_comment_pair* [ end of a comment ] )
+pub(crate) fn magic_comment(i: Input) -> Parsed<Vec<(String, String)>> {
+    preceded(
+        tuple((char('#'), magic_comment_space)),
+        alt((
+            emacs_magic_comment,
+            map(terminated(magic_comment_pair, not(anychar)), |p| vec![p]),
+        )),
+    )(i)
+}
+
+/// *source_character** `-*-` *magic_comment_pair* ( `;` *magic_comment_pair* )* `;`? `-*-`
+fn emacs_magic_comment(i: Input) -> Parsed<Vec<(String, String)>> {
+    let (i, _) = many_till(anychar, tag("-*-"))(i)?;
+    terminated(
+        separated_list1(char(';'), magic_comment_pair),
+        tuple((opt(char(';')), magic_comment_space, tag("-*-"))),
+    )(i)
+}
+
+/// *magic_comment_key* `:` *magic_comment_value*
+fn magic_comment_pair(i: Input) -> Parsed<(String, String)> {
+    map(
+        tuple((
+            magic_comment_space,
+            recognize(many1(alt((
+                recognize(identifier_character),
+                recognize(char('-')),
+            )))),
+            magic_comment_space,
+            char(':'),
+            magic_comment_space,
+            recognize(many1(none_of(" \t;"))),
+            magic_comment_space,
+        )),
+        |t| (t.1.to_lowercase().replace('-', "_"), t.5.to_string()),
+    )(i)
+}
+
+/// *source_character** `coding` ( `:` | `=` ) 0x20* *encoding_name*
+pub(crate) fn encoding_comment(i: Input) -> StringResult {
+    let (i, _) = many_till(anychar, tuple((tag_no_case("coding"), one_of(":="))))(i)?;
+    map(
+        preceded(
+            magic_comment_space,
+            recognize(many1(alt((
+                recognize(identifier_character),
+                recognize(one_of("-.")),
+            )))),
+        ),
+        |s| s.to_string(),
+    )(i)
+}
+
+fn magic_comment_space(i: Input) -> LexResult {
+    recognize(many0(one_of(" \t")))(i)
+}
+
+/// Applies a magic comment's directive (unrecognized keys and values are ignored, as in MRI)
+fn apply_magic_comment(magic: &mut MagicComments, key: &str, value: &str) {
+    let flag = match value.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    };
+    match key {
+        "frozen_string_literal" if flag.is_some() => magic.frozen_string_literal = flag,
+        "warn_indent" if flag.is_some() => magic.warn_indent = flag,
+        "shareable_constant_value" => {
+            let v = match value.to_lowercase().as_str() {
+                "none" => ShareableConstantValue::None,
+                "literal" => ShareableConstantValue::Literal,
+                "experimental_everything" => ShareableConstantValue::ExperimentalEverything,
+                "experimental_copy" => ShareableConstantValue::ExperimentalCopy,
+                _ => return,
+            };
+            magic.shareable_constant_value = Some(v);
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +228,66 @@ mod tests {
         assert_partial!("# With newline\nfoobar\n", "# With newline");
     }
 
+    #[test]
+    fn test_leading_comments() {
+        use_parser!(leading_comments);
+        fn m(
+            encoding: Option<&str>,
+            frozen_string_literal: Option<bool>,
+            warn_indent: Option<bool>,
+            shareable_constant_value: Option<ShareableConstantValue>,
+        ) -> MagicComments {
+            MagicComments {
+                encoding: encoding.map(|s| s.to_owned()),
+                frozen_string_literal,
+                warn_indent,
+                shareable_constant_value,
+            }
+        }
+        // Success cases
+        assert_ok!("", MagicComments::default());
+        assert_ok!("# just a comment\n\n", MagicComments::default());
+        assert_ok!(
+            "# frozen_string_literal: true\n",
+            m(None, Some(true), None, None)
+        );
+        assert_ok!(
+            "# encoding: ascii-8bit\n# Frozen-String-Literal: FALSE\n# warn_indent: true\n",
+            m(Some("ascii-8bit"), Some(false), Some(true), None)
+        );
+        assert_ok!(
+            "#!/usr/bin/env ruby\n# -*- coding: euc-jp; shareable_constant_value: literal -*-\n",
+            m(
+                Some("euc-jp"),
+                None,
+                None,
+                Some(ShareableConstantValue::Literal)
+            )
+        );
+        assert_ok!(
+            "# vim: set fileencoding=Shift_JIS :",
+            m(Some("Shift_JIS"), None, None, None)
+        );
+        // Encodings are only honored within the first two lines
+        assert_ok!("\n# encoding: utf-8", MagicComments::default());
+        assert_ok!("# foo\n# encoding: utf-8", MagicComments::default());
+        // Magic comments must be the entire comment
+        assert_ok!(
+            "# this is not frozen_string_literal: true",
+            MagicComments::default()
+        );
+        // Invalid values are ignored
+        assert_ok!(
+            "# frozen_string_literal: yes\n# shareable_constant_value: all",
+            MagicComments::default()
+        );
+        // Comments after the first token are not consumed
+        assert_partial!(
+            "# warn_indent: false\nfoo\n# frozen_string_literal: true",
+            m(None, None, Some(false), None)
+        );
+    }
+
     #[test]
     fn test_multi_line_comment() {
         use_parser!(multi_line_comment);
@@ -111,4 +306,73 @@ mod tests {
         assert_ok!("=begin extra\n\tcontent line\n=end extra\n");
         assert_ok!("=begin extra\n\tcontent line\n\n\nanother_line\n\n=end extra\n");
     }
+
+    #[test]
+    fn test_program_comments() {
+        use crate::ast::CommentPlacement;
+        let program = crate::parse(Input::new(
+            "# a\nfoo # b\n=begin\nc\n=end\nbar\n'# d'\n[1, # e\n 2]",
+        ))
+        .unwrap();
+        let comment = |text: &str, start: Position, end: Position| {
+            Node::with_span(NodeKind::Comment(text.to_owned()), Span::new(start, end))
+        };
+        assert_eq!(
+            program.comments,
+            vec![
+                comment("# a", Position::new(0, 1, 1), Position::new(3, 1, 4)),
+                comment("# b", Position::new(8, 2, 5), Position::new(11, 2, 8)),
+                comment(
+                    "=begin\nc\n=end\n",
+                    Position::new(12, 3, 1),
+                    Position::new(26, 6, 1)
+                ),
+                comment("# e", Position::new(40, 8, 5), Position::new(43, 8, 8)),
+            ]
+        );
+        // Nodes compare equal regardless of span
+        let spans: Vec<Span> = program.comments.iter().map(|c| c.span).collect();
+        assert_eq!(
+            spans[1..3],
+            [
+                Span::new(Position::new(8, 2, 5), Position::new(11, 2, 8)),
+                Span::new(Position::new(12, 3, 1), Position::new(26, 6, 1))
+            ]
+        );
+        let map = program.attach_comments();
+        let placements: Vec<(&Node, CommentPlacement, Node)> = map
+            .attached
+            .iter()
+            .map(|a| (a.comment, a.placement, a.node.clone()))
+            .collect();
+        let foo = Node::ident("foo", IdentifierKind::LocalVariable);
+        let bar = Node::ident("bar", IdentifierKind::LocalVariable);
+        assert_eq!(
+            placements,
+            vec![
+                (&program.comments[0], CommentPlacement::Leading, foo.clone()),
+                (&program.comments[1], CommentPlacement::Trailing, foo),
+                (&program.comments[2], CommentPlacement::Leading, bar),
+                (
+                    &program.comments[3],
+                    CommentPlacement::Trailing,
+                    Node::int(1)
+                ),
+            ]
+        );
+        let statements: Vec<&Node> = program.program.children().collect();
+        assert_eq!(map.leading(statements[0]), vec![&program.comments[0]]);
+        assert_eq!(map.trailing(statements[0]), vec![&program.comments[1]]);
+        assert_eq!(map.dangling(statements[0]), Vec::<&Node>::new());
+        // Comments in an empty program dangle within it
+        let program = crate::parse(Input::new("# only\n")).unwrap();
+        let map = program.attach_comments();
+        assert_eq!(map.dangling(&program.program), vec![&program.comments[0]]);
+        // Comments on their own line after the last statement dangle within the program
+        let program = crate::parse(Input::new("foo # a\n# b\n")).unwrap();
+        let map = program.attach_comments();
+        let statements: Vec<&Node> = program.program.children().collect();
+        assert_eq!(map.trailing(statements[0]), vec![&program.comments[0]]);
+        assert_eq!(map.dangling(&program.program), vec![&program.comments[1]]);
+    }
 }
@@ -9,6 +9,9 @@ use crate::parsers::expression::operator_expression;
 pub(crate) fn indexing_argument_list(i: Input) -> NodeListResult {
     alt((
         map(command, |v| vec![v]),
+        map(terminated(association_list, opt(comma)), |v| {
+            vec![Node::hash(v)]
+        }),
         terminated(operator_expression_list, opt(comma)),
         map(
             tuple((operator_expression_list, comma, splatting_argument)),
@@ -17,7 +20,6 @@ pub(crate) fn indexing_argument_list(i: Input) -> NodeListResult {
                 t.0
             },
         ),
-        terminated(association_list, opt(comma)),
         map(splatting_argument, |v| vec![v]),
     ))(i)
 }
@@ -29,9 +31,9 @@ pub(crate) fn comma(i: Input) -> LexResult {
 
 /// `*` *operator_expression*
 pub(crate) fn splatting_argument(i: Input) -> NodeResult {
-    map(tuple((char('*'), ws0, operator_expression)), |t| {
-        Node::Splat(Box::new(t.2))
-    })(i)
+    spanned(map(tuple((char('*'), ws0, operator_expression)), |t| {
+        Node::new(NodeKind::Splat(Box::new(t.2)))
+    }))(i)
 }
 
 /// *operator_expression* ( [ no ⏎ ] `,` *operator_expression* **but not** *association* )*
@@ -53,37 +55,41 @@ pub(crate) fn operator_expression_list(i: Input) -> NodeListResult {
 
 /// `()` | `(` *argument_list* `)` | `(` *operator_expression_list* [ no ⏎ ] `,` *chained_command_with_do_block* `)` | `(` *chained_command_with_do_block* `)`
 pub(crate) fn argument_with_parenthesis(i: Input) -> NodeListResult {
-    alt((
-        map(tuple((char('('), ws0, char(')'))), |_| {
-            vec![Node::Placeholder]
-        }),
-        map(
-            tuple((char('('), ws0, argument_list, ws0, char(')'))),
-            |_| vec![Node::Placeholder],
-        ),
-        map(
-            tuple((
-                char('('),
-                ws0,
-                operator_expression_list,
-                comma,
-                chained_command_with_do_block,
-                ws0,
-                char(')'),
-            )),
-            |_| vec![Node::Placeholder],
-        ),
-        map(
-            tuple((
-                char('('),
-                ws0,
-                chained_command_with_do_block,
-                ws0,
-                char(')'),
-            )),
-            |_| vec![Node::Placeholder],
-        ),
-    ))(i)
+    construct(
+        "(",
+        ")",
+        alt((
+            map(tuple((char('('), ws0, char(')'))), |_| {
+                vec![Node::new(NodeKind::Placeholder)]
+            }),
+            map(
+                tuple((char('('), ws0, argument_list, ws0, char(')'))),
+                |_| vec![Node::new(NodeKind::Placeholder)],
+            ),
+            map(
+                tuple((
+                    char('('),
+                    ws0,
+                    operator_expression_list,
+                    comma,
+                    chained_command_with_do_block,
+                    ws0,
+                    char(')'),
+                )),
+                |_| vec![Node::new(NodeKind::Placeholder)],
+            ),
+            map(
+                tuple((
+                    char('('),
+                    ws0,
+                    chained_command_with_do_block,
+                    ws0,
+                    char(')'),
+                )),
+                |_| vec![Node::new(NodeKind::Placeholder)],
+            ),
+        )),
+    )(i)
 }
 
 /// **not** `{` [ no ⏎ ] *argument_list*
@@ -133,9 +139,11 @@ pub(crate) fn argument_list(i: Input) -> NodeListResult {
         ),
         map(
             tuple((
+                // Associations are attempted first, since the key of an association is also an
+                // operator expression
                 alt((
-                    operator_expression_list,
                     map(association_list, |v| vec![Node::hash(v)]),
+                    operator_expression_list,
                 )),
                 opt(tuple((comma, ws0, splatting_argument))),
                 opt(tuple((comma, ws0, block_argument))),
@@ -157,15 +165,15 @@ pub(crate) fn argument_list(i: Input) -> NodeListResult {
 
 /// `&` *operator_expression*
 pub(crate) fn block_argument(i: Input) -> NodeResult {
-    map(tuple((char('&'), ws0, operator_expression)), |t| {
-        Node::BlockArg(Box::new(t.2))
-    })(i)
+    spanned(map(tuple((char('&'), ws0, operator_expression)), |t| {
+        Node::new(NodeKind::BlockArg(Box::new(t.2)))
+    }))(i)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ast::BinaryOpKind;
+    use crate::ast::{Association, BinaryOpKind, PairSyntax};
 
     #[test]
     fn test_argument_list() {
@@ -185,13 +193,42 @@ mod tests {
             vec![
                 Node::int(1),
                 Node::hash(vec![
-                    Node::literal_symbol("foo"),
-                    Node::int(2),
-                    Node::int(3),
-                    Node::int(4)
+                    Association::pair(Node::literal_symbol("foo"), Node::int(2), PairSyntax::Label),
+                    Association::pair(Node::int(3), Node::int(4), PairSyntax::Rocket)
+                ])
+            ]
+        );
+        assert_ok!(
+            "active: true",
+            vec![Node::hash(vec![Association::pair(
+                Node::literal_symbol("active"),
+                Node::boolean(true),
+                PairSyntax::Label
+            )])]
+        );
+        assert_ok!(
+            "1, foo:, **opts",
+            vec![
+                Node::int(1),
+                Node::hash(vec![
+                    Association::pair(
+                        Node::literal_symbol("foo"),
+                        Node::ident("foo", IdentifierKind::LocalVariable),
+                        PairSyntax::Shorthand
+                    ),
+                    Association::double_splat(Node::ident("opts", IdentifierKind::LocalVariable))
                 ])
             ]
         );
+        let foo = || {
+            vec![Node::hash(vec![Association::pair(
+                Node::literal_symbol("foo"),
+                Node::ident("foo", IdentifierKind::LocalVariable),
+                PairSyntax::Shorthand,
+            )])]
+        };
+        assert_ok!("foo:", foo());
+        assert_remaining!("foo:\n", "\n");
     }
 
     #[test]
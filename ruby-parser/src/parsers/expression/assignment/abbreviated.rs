@@ -10,25 +10,25 @@ use crate::parsers::token::operator::assignment_operator;
 
 /// *abbreviated_variable_assignment_expression* | *abbreviated_indexing_assignment_expression* | *abbreviated_method_assignment_expression*
 pub(crate) fn abbreviated_assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         abbreviated_variable_assignment_expression,
         abbreviated_indexing_assignment_expression,
         abbreviated_method_assignment_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *abbreviated_variable_assignment_statement* | *abbreviated_indexing_assignment_statement* | *abbreviated_method_assignment_statement*
 pub(crate) fn abbreviated_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         abbreviated_variable_assignment_statement,
         abbreviated_indexing_assignment_statement,
         abbreviated_method_assignment_statement,
-    ))(i)
+    )))(i)
 }
 
 /// *variable* [ no ⏎ ] *assignment_operator* *operator_expression*
 pub(crate) fn abbreviated_variable_assignment_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             variable,
             no_lt,
@@ -36,13 +36,13 @@ pub(crate) fn abbreviated_variable_assignment_expression(i: Input) -> NodeResult
             ws0,
             operator_expression,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *variable* [ no ⏎ ] *assignment_operator* *method_invocation_without_parenthesis*
 pub(crate) fn abbreviated_variable_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             variable,
             no_lt,
@@ -50,13 +50,13 @@ pub(crate) fn abbreviated_variable_assignment_statement(i: Input) -> NodeResult
             ws0,
             method_invocation_without_parenthesis,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]` [ no ⏎ ] *assignment_operator* *operator_expression*
 pub(crate) fn abbreviated_indexing_assignment_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             primary_expression,
             char('['),
@@ -69,13 +69,13 @@ pub(crate) fn abbreviated_indexing_assignment_expression(i: Input) -> NodeResult
             ws0,
             operator_expression,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]` [ no ⏎ ] *assignment_operator* *method_invocation_without_parenthesis*
 pub(crate) fn abbreviated_indexing_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             primary_expression,
             char('['),
@@ -88,13 +88,13 @@ pub(crate) fn abbreviated_indexing_assignment_statement(i: Input) -> NodeResult
             ws0,
             method_invocation_without_parenthesis,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] ( `.` | `::` ) *local_variable_identifier* [ no ⏎ ] *assignment_operator* *operator_expression* | *primary_expression* [ no ⏎ ] `.` *constant_identifier* [ no ⏎ ] *assignment_operator* *operator_expression*
 pub(crate) fn abbreviated_method_assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 primary_expression,
@@ -106,7 +106,7 @@ pub(crate) fn abbreviated_method_assignment_expression(i: Input) -> NodeResult {
                 ws0,
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -119,14 +119,14 @@ pub(crate) fn abbreviated_method_assignment_expression(i: Input) -> NodeResult {
                 ws0,
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] ( `.` | `::` ) *local_variable_identifier* [ no ⏎ ] *assignment_operator* *method_invocation_without_parenthesis* | *primary_expression* [ no ⏎ ] `.` *constant_identifier* [ no ⏎ ] *assignment_operator* *method_invocation_without_parenthesis*
 pub(crate) fn abbreviated_method_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 primary_expression,
@@ -138,7 +138,7 @@ pub(crate) fn abbreviated_method_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -151,7 +151,7 @@ pub(crate) fn abbreviated_method_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
@@ -9,25 +9,25 @@ pub(crate) mod single;
 
 /// *single_assignment_expression* | *abbreviated_assignment_expression* | *assignment_with_rescue_modifier*
 pub(crate) fn assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         single::single_assignment_expression,
         abbreviated::abbreviated_assignment_expression,
         assignment_with_rescue_modifier,
-    ))(i)
+    )))(i)
 }
 
 /// *single_assignment_statement* | *abbreviated_assignment_statement* | *multiple_assignment_statement*
 pub(crate) fn assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         single::single_assignment_statement,
         abbreviated::abbreviated_assignment_statement,
         multiple_assignment_statement,
-    ))(i)
+    )))(i)
 }
 
 /// *left_hand_side* [ no ⏎ ] `=` *operator_expression* [ no ⏎ ] `rescue` *operator_expression*
 pub(crate) fn assignment_with_rescue_modifier(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             left_hand_side,
             no_lt,
@@ -39,6 +39,6 @@ pub(crate) fn assignment_with_rescue_modifier(i: Input) -> NodeResult {
             ws0,
             operator_expression,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
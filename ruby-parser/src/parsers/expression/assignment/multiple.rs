@@ -11,16 +11,16 @@ use crate::parsers::token::identifier::local_variable_identifier;
 
 /// *many_to_one_assignment_statement* | *one_to_packing_assignment_statement* | *many_to_many_assignment_statement*
 pub(crate) fn multiple_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         many_to_one_assignment_statement,
         one_to_packing_assignment_statement,
         many_to_many_assignment_statement,
-    ))(i)
+    )))(i)
 }
 
 /// *left_hand_side* [ no ⏎ ] `=` *multiple_right_hand_side*
 pub(crate) fn many_to_one_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             left_hand_side,
             no_lt,
@@ -28,13 +28,13 @@ pub(crate) fn many_to_one_assignment_statement(i: Input) -> NodeResult {
             ws0,
             multiple_right_hand_side,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *packing_left_hand_side* [ no ⏎ ] `=` *rhs_expression*
 pub(crate) fn one_to_packing_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             packing_left_hand_side,
             no_lt,
@@ -42,13 +42,13 @@ pub(crate) fn one_to_packing_assignment_statement(i: Input) -> NodeResult {
             ws0,
             rhs_expression,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *multiple_left_hand_side* [ no ⏎ ] `=` *multiple_right_hand_side* | ( *multiple_left_hand_side* **but not** *packing_left_hand_side* ) [ no ⏎ ] `=` *rhs_expression*
 pub(crate) fn many_to_many_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 multiple_left_hand_side,
@@ -57,7 +57,7 @@ pub(crate) fn many_to_many_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 multiple_right_hand_side,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -68,20 +68,23 @@ pub(crate) fn many_to_many_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 rhs_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *method_invocation_without_parenthesis* | *operator_expression*
 pub(crate) fn rhs_expression(i: Input) -> NodeResult {
-    alt((method_invocation_without_parenthesis, operator_expression))(i)
+    spanned(alt((
+        method_invocation_without_parenthesis,
+        operator_expression,
+    )))(i)
 }
 
 /// *variable* | *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]` | *primary_expression* [ no ⏎ ] ( `.` | `::` ) ( *local_variable_identifier* | *constant_identifier* ) | `::` *constant_identifier*
 pub(crate) fn left_hand_side(i: Input) -> NodeResult {
-    alt((
-        map(variable, |_| Node::Placeholder),
+    spanned(alt((
+        map(variable, |_| Node::new(NodeKind::Placeholder)),
         map(
             tuple((
                 primary_expression,
@@ -91,7 +94,7 @@ pub(crate) fn left_hand_side(i: Input) -> NodeResult {
                 ws0,
                 char(']'),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -101,61 +104,61 @@ pub(crate) fn left_hand_side(i: Input) -> NodeResult {
                 ws0,
                 alt((local_variable_identifier, constant_identifier)),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(tuple((tag("::"), ws0, constant_identifier)), |_| {
-            Node::Placeholder
+            Node::new(NodeKind::Placeholder)
         }),
-    ))(i)
+    )))(i)
 }
 
 /// ( *multiple_left_hand_side_item* [ no ⏎ ] `,` )+ *multiple_left_hand_side_item*? | ( *multiple_left_hand_side_item* [ no ⏎ ] `,` )+ *packing_left_hand_side*? | *packing_left_hand_side* | *grouped_left_hand_side*
 pub(crate) fn multiple_left_hand_side(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 many1(tuple((multiple_left_hand_side_item, no_lt, char(',')))),
                 opt(alt((multiple_left_hand_side_item, packing_left_hand_side))),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         packing_left_hand_side,
         grouped_left_hand_side,
-    ))(i)
+    )))(i)
 }
 
 /// `*` *left_hand_side*?
 pub(crate) fn packing_left_hand_side(i: Input) -> NodeResult {
-    map(tuple((char('*'), ws0, opt(left_hand_side))), |_| {
-        Node::Placeholder
-    })(i)
+    spanned(map(tuple((char('*'), ws0, opt(left_hand_side))), |_| {
+        Node::new(NodeKind::Placeholder)
+    }))(i)
 }
 
 /// `(` *multiple_left_hand_side* `)`
 pub(crate) fn grouped_left_hand_side(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((char('('), ws0, multiple_left_hand_side, ws0, char(')'))),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *left_hand_side* | *grouped_left_hand_side*
 pub(crate) fn multiple_left_hand_side_item(i: Input) -> NodeResult {
-    alt((left_hand_side, grouped_left_hand_side))(i)
+    spanned(alt((left_hand_side, grouped_left_hand_side)))(i)
 }
 
 /// *operator_expression_list* ( [ no ⏎ ] `,` *splatting_right_hand_side* )? | *splatting_right_hand_side*
 pub(crate) fn multiple_right_hand_side(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 operator_expression_list,
                 opt(tuple((no_lt, char(','), ws0, splatting_right_hand_side))),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         splatting_right_hand_side,
-    ))(i)
+    )))(i)
 }
 
 /// *splatting_argument*
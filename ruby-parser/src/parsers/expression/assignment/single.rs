@@ -9,35 +9,35 @@ use crate::parsers::token::identifier::local_variable_identifier;
 
 /// *single_variable_assignment_expression* | *scoped_constant_assignment_expression* | *single_indexing_assignment_expression* | *single_method_assignment_expression*
 pub(crate) fn single_assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         single_variable_assignment_expression,
         scoped_constant_assignment_expression,
         single_indexing_assignment_expression,
         single_method_assignment_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *single_variable_assignment_statement* | *scoped_constant_assignment_statement* | *single_indexing_assignment_statement* | *single_method_assignment_statement*
 pub(crate) fn single_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         single_variable_assignment_statement,
         scoped_constant_assignment_statement,
         single_indexing_assignment_statement,
         single_method_assignment_statement,
-    ))(i)
+    )))(i)
 }
 
 /// *variable* [ no ⏎ ] `=` *operator_expression*
 pub(crate) fn single_variable_assignment_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((variable, no_lt, char('='), ws0, operator_expression)),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *variable* [ no ⏎ ] `=` *method_invocation_without_parenthesis*
 pub(crate) fn single_variable_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             variable,
             no_lt,
@@ -45,13 +45,13 @@ pub(crate) fn single_variable_assignment_statement(i: Input) -> NodeResult {
             ws0,
             method_invocation_without_parenthesis,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]` [ no ⏎ ] `=` *operator_expression*
 pub(crate) fn single_indexing_assignment_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             primary_expression,
             char('['),
@@ -64,13 +64,13 @@ pub(crate) fn single_indexing_assignment_expression(i: Input) -> NodeResult {
             ws0,
             operator_expression,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]` [ no ⏎ ] `=` *method_invocation_without_parenthesis*
 pub(crate) fn single_indexing_assignment_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             primary_expression,
             char('['),
@@ -83,13 +83,13 @@ pub(crate) fn single_indexing_assignment_statement(i: Input) -> NodeResult {
             ws0,
             method_invocation_without_parenthesis,
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] ( `.` | `::` ) *local_variable_identifier* [ no ⏎ ] `=` *operator_expression* | *primary_expression* [ no ⏎ ] `.` *constant_identifier* [ no ⏎ ] `=` *operator_expression*
 pub(crate) fn single_method_assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 primary_expression,
@@ -101,7 +101,7 @@ pub(crate) fn single_method_assignment_expression(i: Input) -> NodeResult {
                 char('='),
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -114,14 +114,14 @@ pub(crate) fn single_method_assignment_expression(i: Input) -> NodeResult {
                 char('='),
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] ( `.` | `::` ) *local_variable_identifier* [ no ⏎ ] `=` *method_invocation_without_parenthesis* | *primary_expression* [ no ⏎ ] `.` *constant_identifier* [ no ⏎ ] `=` *method_invocation_without_parenthesis*
 pub(crate) fn single_method_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 primary_expression,
@@ -133,7 +133,7 @@ pub(crate) fn single_method_assignment_statement(i: Input) -> NodeResult {
                 char('='),
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -146,14 +146,14 @@ pub(crate) fn single_method_assignment_statement(i: Input) -> NodeResult {
                 char('='),
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `::` *constant_identifier* [ no ⏎ ] `=` *operator_expression* | `::` *constant_identifier* [ no ⏎ ] `=` *operator_expression*
 pub(crate) fn scoped_constant_assignment_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 tag("::"),
@@ -163,7 +163,7 @@ pub(crate) fn scoped_constant_assignment_expression(i: Input) -> NodeResult {
                 ws0,
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -175,14 +175,14 @@ pub(crate) fn scoped_constant_assignment_expression(i: Input) -> NodeResult {
                 ws0,
                 operator_expression,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `::` *constant_identifier* [ no ⏎ ] `=` *method_invocation_without_parenthesis* | `::` *constant_identifier* [ no ⏎ ] `=` *method_invocation_without_parenthesis*
 pub(crate) fn scoped_constant_assignment_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 tag("::"),
@@ -192,7 +192,7 @@ pub(crate) fn scoped_constant_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -204,7 +204,7 @@ pub(crate) fn scoped_constant_assignment_statement(i: Input) -> NodeResult {
                 ws0,
                 method_invocation_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
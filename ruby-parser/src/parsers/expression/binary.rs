@@ -4,14 +4,14 @@ use crate::parsers::expression::unary::{unary_expression, unary_minus_expression
 
 /// *relational_expression* | *relational_expression* [ no ⏎ ] ( `<=>` | `===` | `==` | `!=` | `=~` | `!~` ) *relational_expression*
 pub(crate) fn equality_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((relational_expression, opt(recursing_equality_expression))),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_equality_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             alt((
@@ -38,19 +38,19 @@ fn recursing_equality_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *bitwise_or_expression* | *relational_expression* [ no ⏎ ] ( `>=` | `>` | `<=` | `<` ) *bitwise_or_expression*
 pub(crate) fn relational_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((bitwise_or_expression, opt(recursing_relational_expression))),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_relational_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             alt((tag(">="), tag(">"), tag("<="), tag("<"))),
@@ -68,22 +68,22 @@ fn recursing_relational_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *bitwise_and_expression* | *bitwise_or_expression* [ no ⏎ ] ( `|` | `^` ) *bitwise_and_expression*
 pub(crate) fn bitwise_or_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((bitwise_and_expression, opt(recursing_bitwise_or_expression))),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_bitwise_or_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
-            one_of("|^"),
+            token(|_| TokenKind::Operator, one_of("|^")),
             ws0,
             bitwise_and_expression,
             opt(recursing_bitwise_or_expression),
@@ -96,22 +96,22 @@ fn recursing_bitwise_or_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *bitwise_shift_expression* | *bitwise_and_expression* [ no ⏎ ] `&` *bitwise_shift_expression*
 pub(crate) fn bitwise_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             bitwise_shift_expression,
             opt(recursing_bitwise_and_expression),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_bitwise_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             char('&'),
@@ -120,19 +120,19 @@ fn recursing_bitwise_and_expression(i: Input) -> NodeResult {
             opt(recursing_bitwise_and_expression),
         )),
         |t| partial_node(Op::BitAnd, t.3, t.4),
-    )(i)
+    ))(i)
 }
 
 /// *additive_expression* | *bitwise_shift_expression* [ no ⏎ ] ( `<<` | `>>` ) *additive_expression*
 pub(crate) fn bitwise_shift_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((additive_expression, opt(recursing_bitwise_shift_expression))),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_bitwise_shift_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             alt((tag("<<"), tag(">>"))),
@@ -148,25 +148,25 @@ fn recursing_bitwise_shift_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *multiplicative_expression* | *additive_expression* [ no ⏎ ] ( `+` | `-` ) *multiplicative_expression*
 pub(crate) fn additive_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             multiplicative_expression,
             opt(recursing_additive_expression),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_additive_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
-            one_of("+-"),
+            token(|_| TokenKind::Operator, one_of("+-")),
             ws0,
             multiplicative_expression,
             opt(recursing_additive_expression),
@@ -179,25 +179,25 @@ fn recursing_additive_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *unary_minus_expression* | *multiplicative_expression* [ no ⏎ ] ( `*` | `/` | `%` ) *unary_minus_expression*
 pub(crate) fn multiplicative_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             unary_minus_expression,
             opt(recursing_multiplicative_expression),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_multiplicative_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
-            one_of("*/%"),
+            token(|_| TokenKind::Operator, one_of("*/%")),
             ws0,
             unary_minus_expression,
             opt(recursing_multiplicative_expression),
@@ -211,20 +211,24 @@ fn recursing_multiplicative_expression(i: Input) -> NodeResult {
             };
             partial_node(op, t.3, t.4)
         },
-    )(i)
+    ))(i)
 }
 
 /// *unary_expression* | *unary_expression* [ no ⏎ ] `**` *power_expression*
 pub(crate) fn power_expression(i: Input) -> NodeResult {
     let (i, lhs) = unary_expression(i)?;
     if let Ok((j, t)) = tuple((no_lt, tag("**"), ws0, power_expression))(i.clone()) {
+        let span = lhs.span.to(&t.3.span);
         Ok((
             j,
-            Node::BinaryOp(BinaryOp {
-                op: Op::Power,
-                lhs: Box::new(lhs),
-                rhs: Box::new(t.3),
-            }),
+            Node::with_span(
+                NodeKind::BinaryOp(BinaryOp {
+                    op: Op::Power,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(t.3),
+                }),
+                span,
+            ),
         ))
     } else {
         Ok((i, lhs))
@@ -232,12 +236,18 @@ pub(crate) fn power_expression(i: Input) -> NodeResult {
 }
 
 /// Constructs a partial binary op node, using a placeholder for the left hand side
+///
+/// The partial node ends where its right hand side does; `decurse` fills in its start.
 fn partial_node(op: Op, rhs: Node, ast: Option<Node>) -> Node {
-    let node = Node::BinaryOp(BinaryOp {
-        op,
-        lhs: Box::new(Node::Placeholder),
-        rhs: Box::new(rhs),
-    });
+    let span = rhs.span;
+    let node = Node::with_span(
+        NodeKind::BinaryOp(BinaryOp {
+            op,
+            lhs: Box::new(Node::new(NodeKind::Placeholder)),
+            rhs: Box::new(rhs),
+        }),
+        span,
+    );
     Node::decurse((node, ast))
 }
 
@@ -269,9 +279,11 @@ mod tests {
         );
         assert_ok!(
             "(1 == 2)",
-            Node::Block(vec![
-                Node::binary_op(Node::int(1), Op::Equal, Node::int(2),)
-            ])
+            Node::new(NodeKind::Block(vec![Node::binary_op(
+                Node::int(1),
+                Op::Equal,
+                Node::int(2),
+            )]))
         );
         assert_remaining!("1?2:3", "?2:3");
     }
@@ -459,7 +471,7 @@ mod tests {
         assert_err!("3\n** 4");
         assert_err!("3 # comment ** 4");
         // Success cases
-        assert_ok!("nil", Node::Nil);
+        assert_ok!("nil", Node::new(NodeKind::Nil));
         assert_ok!(
             "3 **\n# comment\n4**-5.2",
             Node::binary_op(
@@ -4,18 +4,22 @@ use crate::parsers::expression::module::{module_body, module_path};
 
 /// `class` *module_path* [ no ⏎ ] ( `<` *superclass* )? *module_body* `end`
 pub(crate) fn class_definition(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("class"),
-            ws0,
-            module_path,
-            no_lt,
-            opt(tuple((char('<'), ws0, superclass))),
-            module_body,
-            tag("end"),
-        )),
-        |_| Node::Placeholder,
-    )(i)
+    spanned(map(
+        construct(
+            "class",
+            "end",
+            tuple((
+                tag("class"),
+                ws0,
+                module_path,
+                no_lt,
+                opt(tuple((char('<'), ws0, superclass))),
+                module_body,
+                tag("end"),
+            )),
+        ),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *expression*
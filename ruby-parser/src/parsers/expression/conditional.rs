@@ -3,6 +3,7 @@ use crate::lexer::*;
 use crate::parsers::expression::argument::comma;
 use crate::parsers::expression::argument::operator_expression_list;
 use crate::parsers::expression::argument::splatting_argument;
+use crate::parsers::expression::variable::variable_reference;
 use crate::parsers::expression::{expression, operator_expression, range_constructor};
 use crate::parsers::program::separator_list;
 use crate::parsers::program::{compound_statement, separator};
@@ -10,29 +11,37 @@ use std::mem;
 
 /// `if` *expression* *then_clause* *elsif_clause** *else_clause*? `end`
 pub(crate) fn if_expression(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("if"),
-            ws0,
-            expression,
-            then_clause,
-            many0(elsif_clause),
-            opt(else_clause),
-            tag("end"),
-        )),
+    spanned(map(
+        construct(
+            "if",
+            "end",
+            tuple((
+                tag("if"),
+                ws0,
+                expression,
+                then_clause,
+                many0(elsif_clause),
+                opt(else_clause),
+                tag("end"),
+            )),
+        ),
         |t| {
-            let mut parent_node = Node::Conditional(Conditional {
+            let mut parent_node = Node::new(NodeKind::Conditional(Conditional {
                 kind: ConditionalKind::If,
                 cond: Box::new(t.2),
                 then: Box::new(t.3),
-                otherwise: Box::new(t.5.unwrap_or(Node::None)),
-            });
+                otherwise: Box::new(t.5.unwrap_or(Node::new(NodeKind::None))),
+            }));
             let mut n = &mut parent_node;
             for node in t.4 {
                 let mut temp = Box::new(node);
-                if let Node::Conditional(a) = n {
+                if let Node {
+                    kind: NodeKind::Conditional(a),
+                    ..
+                } = n
+                {
                     mem::swap(&mut a.otherwise, &mut temp);
-                    if let Node::Conditional(ref mut b) = &mut *a.otherwise {
+                    if let NodeKind::Conditional(ref mut b) = &mut a.otherwise.kind {
                         mem::swap(&mut b.otherwise, &mut temp);
                     }
                     n = &mut a.otherwise;
@@ -40,80 +49,91 @@ pub(crate) fn if_expression(i: Input) -> NodeResult {
             }
             parent_node
         },
-    )(i)
+    ))(i)
 }
 
 /// *separator* *compound_statement* | *separator*? `then` *compound_statement*
 pub(crate) fn then_clause(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(tuple((separator, compound_statement)), |t| t.1),
         map(
             tuple((opt(separator), ws0, tag("then"), compound_statement)),
             |t| t.3,
         ),
-    ))(i)
+    )))(i)
 }
 
 /// `else` *compound_statement*
 pub(crate) fn else_clause(i: Input) -> NodeResult {
-    map(tuple((tag("else"), compound_statement)), |t| t.1)(i)
+    spanned(map(tuple((tag("else"), compound_statement)), |t| t.1))(i)
 }
 
 /// `elsif` *expression* *then_clause*
 pub(crate) fn elsif_clause(i: Input) -> NodeResult {
-    map(tuple((tag("elsif"), ws0, expression, then_clause)), |t| {
-        Node::Conditional(Conditional {
-            kind: ConditionalKind::Elsif,
-            cond: Box::new(t.2),
-            then: Box::new(t.3),
-            otherwise: Box::new(Node::None),
-        })
-    })(i)
+    spanned(map(
+        tuple((tag("elsif"), ws0, expression, then_clause)),
+        |t| {
+            Node::new(NodeKind::Conditional(Conditional {
+                kind: ConditionalKind::Elsif,
+                cond: Box::new(t.2),
+                then: Box::new(t.3),
+                otherwise: Box::new(Node::new(NodeKind::None)),
+            }))
+        },
+    ))(i)
 }
 
 /// `unless` *expression* *then_clause* *else_clause*? `end`
 pub(crate) fn unless_expression(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("unless"),
-            ws0,
-            expression,
-            then_clause,
-            opt(else_clause),
-            tag("end"),
-        )),
+    spanned(map(
+        construct(
+            "unless",
+            "end",
+            tuple((
+                tag("unless"),
+                ws0,
+                expression,
+                then_clause,
+                opt(else_clause),
+                tag("end"),
+            )),
+        ),
         |t| {
-            Node::Conditional(Conditional {
+            Node::new(NodeKind::Conditional(Conditional {
                 kind: ConditionalKind::Unless,
                 cond: Box::new(t.2),
                 then: Box::new(t.3),
-                otherwise: Box::new(t.4.unwrap_or(Node::None)),
-            })
+                otherwise: Box::new(t.4.unwrap_or(Node::new(NodeKind::None))),
+            }))
         },
-    )(i)
+    ))(i)
 }
 
 /// `case` *expression*? *separator_list*? *when_clause*+ *else_clause*? end
 pub(crate) fn case_expression(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("case"),
-            ws0,
-            opt(expression),
-            ws0,
-            opt(separator_list),
-            many1(when_clause),
-            opt(else_clause),
-            tag("end"),
-        )),
+    spanned(map(
+        construct(
+            "case",
+            "end",
+            tuple((
+                tag("case"),
+                ws0,
+                opt(expression),
+                ws0,
+                opt(separator_list),
+                many1(when_clause),
+                opt(else_clause),
+                tag("end"),
+            )),
+        ),
         |t| {
-            Node::Case(Case {
-                expr: Box::new(t.2.unwrap_or(Node::None)),
+            Node::new(NodeKind::Case(Case {
+                expr: Box::new(t.2.unwrap_or(Node::new(NodeKind::None))),
                 when: t.5,
-                otherwise: Box::new(t.6.unwrap_or(Node::None)),
-            })
+                otherwise: Box::new(t.6.unwrap_or(Node::new(NodeKind::None))),
+            }))
         },
-    )(i)
+    ))(i)
 }
 
 /// `when` *when_argument* *then_clause*
@@ -147,22 +167,26 @@ pub(crate) fn when_argument(i: Input) -> NodeListResult {
 
 /// *range_constructor* | *range_constructor* [ no ⏎ ] `?` *operator_expression* [ no ⏎ ] `:` *operator_expression*
 pub(crate) fn conditional_operator_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             range_constructor,
             opt(recursing_conditional_operator_expression),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
-fn recursing_conditional_operator_expression(i: Input) -> NodeResult {
-    map(
+pub(crate) fn recursing_conditional_operator_expression(i: Input) -> NodeResult {
+    spanned(map(
         tuple((
             no_lt,
             char('?'),
             ws0,
-            operator_expression,
+            alt((
+                terminated(operator_expression, peek(tuple((no_lt, char(':'))))),
+                // `x ? a :b` treats `a` as a variable rather than a method called with `:b`
+                variable_reference,
+            )),
             no_lt,
             char(':'),
             ws0,
@@ -170,15 +194,19 @@ fn recursing_conditional_operator_expression(i: Input) -> NodeResult {
             opt(recursing_conditional_operator_expression),
         )),
         |t| {
-            let node = Node::Conditional(Conditional {
-                cond: Box::new(Node::Placeholder),
-                then: Box::new(t.3),
-                otherwise: Box::new(t.7),
-                kind: ConditionalKind::Ternary,
-            });
+            let span = t.7.span;
+            let node = Node::with_span(
+                NodeKind::Conditional(Conditional {
+                    cond: Box::new(Node::new(NodeKind::Placeholder)),
+                    then: Box::new(t.3),
+                    otherwise: Box::new(t.7),
+                    kind: ConditionalKind::Ternary,
+                }),
+                span,
+            );
             Node::decurse((node, t.8))
         },
-    )(i)
+    ))(i)
 }
 
 #[cfg(test)]
@@ -196,15 +224,15 @@ mod tests {
         assert_ok!(
             "case when 1, 2 + 3 \n 4 end",
             Node::case(
-                Node::None,
+                Node::new(NodeKind::None),
                 vec![WhenClause {
                     when: vec![
                         Node::int(1),
                         Node::binary_op(Node::int(2), BinaryOpKind::Add, Node::int(3))
                     ],
-                    then: Box::new(Node::Block(vec![Node::int(4)]))
+                    then: Box::new(Node::new(NodeKind::Block(vec![Node::int(4)])))
                 }],
-                Node::None
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -214,18 +242,18 @@ mod tests {
                 vec![
                     WhenClause {
                         when: vec![Node::int(2),],
-                        then: Box::new(Node::Block(vec![Node::int(3)]))
+                        then: Box::new(Node::new(NodeKind::Block(vec![Node::int(3)])))
                     },
                     WhenClause {
                         when: vec![Node::int(4), Node::int(5)],
-                        then: Box::new(Node::Block(vec![Node::int(6)]))
+                        then: Box::new(Node::new(NodeKind::Block(vec![Node::int(6)])))
                     },
                     WhenClause {
                         when: vec![Node::int(7),],
-                        then: Box::new(Node::Block(vec![Node::int(8)]))
+                        then: Box::new(Node::new(NodeKind::Block(vec![Node::int(8)])))
                     }
                 ],
-                Node::Block(vec![Node::int(9)])
+                Node::new(NodeKind::Block(vec![Node::int(9)]))
             )
         );
     }
@@ -244,8 +272,8 @@ mod tests {
             Node::conditional(
                 ConditionalKind::If,
                 Node::int(1),
-                Node::Block(vec![Node::int(2)]),
-                Node::None
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -254,7 +282,7 @@ mod tests {
                 ConditionalKind::If,
                 Node::int(1),
                 Node::empty(),
-                Node::Block(vec![Node::int(2)]),
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
             )
         );
         assert_ok!(
@@ -262,8 +290,8 @@ mod tests {
             Node::conditional(
                 ConditionalKind::If,
                 Node::logical_and(Node::int(1), Node::int(2)),
-                Node::Block(vec![Node::int(3)]),
-                Node::None
+                Node::new(NodeKind::Block(vec![Node::int(3)])),
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -271,8 +299,8 @@ mod tests {
             Node::conditional(
                 ConditionalKind::If,
                 Node::int(1),
-                Node::Block(vec![Node::int(2)]),
-                Node::Block(vec![Node::int(3)]),
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
+                Node::new(NodeKind::Block(vec![Node::int(3)])),
             )
         );
         assert_ok!(
@@ -280,16 +308,16 @@ mod tests {
             Node::conditional(
                 ConditionalKind::If,
                 Node::int(1),
-                Node::Block(vec![Node::int(2)]),
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
                 Node::conditional(
                     ConditionalKind::Elsif,
                     Node::int(3),
-                    Node::Block(vec![Node::int(4)]),
+                    Node::new(NodeKind::Block(vec![Node::int(4)])),
                     Node::conditional(
                         ConditionalKind::Elsif,
                         Node::int(5),
-                        Node::Block(vec![Node::int(6)]),
-                        Node::Block(vec![Node::int(7)]),
+                        Node::new(NodeKind::Block(vec![Node::int(6)])),
+                        Node::new(NodeKind::Block(vec![Node::int(7)])),
                     ),
                 ),
             )
@@ -308,8 +336,8 @@ mod tests {
             Node::conditional(
                 ConditionalKind::Unless,
                 Node::int(1),
-                Node::Block(vec![Node::int(2)]),
-                Node::None
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -318,7 +346,7 @@ mod tests {
                 ConditionalKind::Unless,
                 Node::int(1),
                 Node::empty(),
-                Node::Block(vec![Node::int(3)])
+                Node::new(NodeKind::Block(vec![Node::int(3)]))
             )
         );
     }
@@ -344,6 +372,37 @@ mod tests {
         assert_ok!("1 ?2 :3", ok);
         assert_ok!("1 ? 2:3", ok);
         assert_ok!("1?2:3", ok);
+        let chr = |c: &str| Node::new(NodeKind::Literal(Literal::Character(c.to_owned())));
+        let ident = |n: &str| Node::ident(n, IdentifierKind::LocalVariable);
+        assert_ok!(
+            "x ?a :b",
+            Node::conditional(ConditionalKind::Ternary, ident("x"), ident("a"), ident("b"))
+        );
+        assert_ok!(
+            "x ? a : b",
+            Node::conditional(ConditionalKind::Ternary, ident("x"), ident("a"), ident("b"))
+        );
+        assert_ok!(
+            "x ?ab :cd",
+            Node::conditional(
+                ConditionalKind::Ternary,
+                ident("x"),
+                ident("ab"),
+                ident("cd")
+            )
+        );
+        assert_ok!(
+            "x ? ?a : ?b",
+            Node::conditional(ConditionalKind::Ternary, ident("x"), chr("a"), chr("b"))
+        );
+        assert_ok!(
+            "?a ? ?b:?c",
+            Node::conditional(ConditionalKind::Ternary, chr("a"), chr("b"), chr("c"))
+        );
+        assert_ok!(
+            "x ??a :?b",
+            Node::conditional(ConditionalKind::Ternary, ident("x"), chr("a"), chr("b"))
+        );
         assert_ok!(
             "1 ? 2 ? 3 : 4 : 5",
             Node::conditional(
@@ -360,12 +419,7 @@ mod tests {
         );
         assert_ok!(
             "1??2:?3",
-            Node::conditional(
-                ConditionalKind::Ternary,
-                Node::int(1),
-                Node::literal_string("2"),
-                Node::literal_string("3"),
-            )
+            Node::conditional(ConditionalKind::Ternary, Node::int(1), chr("2"), chr("3"),)
         );
     }
 }
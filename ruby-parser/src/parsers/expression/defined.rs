@@ -3,23 +3,23 @@ use crate::parsers::expression::{expression, operator_expression};
 
 /// `defined?` `(` *expression* `)`
 pub(crate) fn defined_with_parenthesis(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((tag("defined?"), char('('), ws0, expression, ws0, char(')'))),
-        |t| Node::Defined(Box::new(t.3)),
-    )(i)
+        |t| Node::new(NodeKind::Defined(Box::new(t.3))),
+    ))(i)
 }
 
 /// `defined?` *operator_expression*
 pub(crate) fn defined_without_parenthesis(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             tag("defined?"),
             peek(not(char('('))),
             ws0,
             operator_expression,
         )),
-        |t| Node::Defined(Box::new(t.3)),
-    )(i)
+        |t| Node::new(NodeKind::Defined(Box::new(t.3))),
+    ))(i)
 }
 
 #[cfg(test)]
@@ -36,15 +36,18 @@ mod tests {
         // Success cases
         assert_ok!(
             "defined?( foo )",
-            Node::Defined(Box::new(Node::ident("foo", IdentifierKind::LocalVariable)))
+            Node::new(NodeKind::Defined(Box::new(Node::ident(
+                "foo",
+                IdentifierKind::LocalVariable
+            ))))
         );
         assert_ok!(
             "defined?(2 + 1)",
-            Node::Defined(Box::new(Node::binary_op(
+            Node::new(NodeKind::Defined(Box::new(Node::binary_op(
                 Node::int(2),
                 BinaryOpKind::Add,
                 Node::int(1)
-            )))
+            ))))
         );
     }
 
@@ -74,11 +77,11 @@ mod tests {
         );
         assert_ok!(
             "defined?\n(2 +\n 1)",
-            Node::defined(Node::Block(vec![Node::binary_op(
+            Node::defined(Node::new(NodeKind::Block(vec![Node::binary_op(
                 Node::int(2),
                 BinaryOpKind::Add,
                 Node::int(1)
-            )]))
+            )])))
         );
     }
 }
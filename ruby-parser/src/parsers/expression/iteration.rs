@@ -8,64 +8,76 @@ use crate::parsers::program::separator;
 
 /// `while` *expression* *do_clause* `end`
 pub(crate) fn while_expression(i: Input) -> NodeResult {
-    map(
-        tuple((tag("while"), ws0, expression, do_clause, tag("end"))),
+    spanned(map(
+        construct(
+            "while",
+            "end",
+            tuple((tag("while"), ws0, expression, do_clause, tag("end"))),
+        ),
         |t| {
-            Node::Loop(Loop {
+            Node::new(NodeKind::Loop(Loop {
                 kind: LoopKind::While,
                 cond: Box::new(t.2),
                 body: Box::new(t.3),
                 bindings: None,
-            })
+            }))
         },
-    )(i)
+    ))(i)
 }
 
 /// *separator* *compound_statement* | [ no ⏎ ] `do` *compound_statement*
 pub(crate) fn do_clause(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(tuple((separator, compound_statement)), |t| t.1),
         map(tuple((no_lt, tag("do"), compound_statement)), |t| t.2),
-    ))(i)
+    )))(i)
 }
 
 /// `until` *expression* *do_clause* `end`
 pub(crate) fn until_expression(i: Input) -> NodeResult {
-    map(
-        tuple((tag("until"), ws0, expression, do_clause, tag("end"))),
+    spanned(map(
+        construct(
+            "until",
+            "end",
+            tuple((tag("until"), ws0, expression, do_clause, tag("end"))),
+        ),
         |t| {
-            Node::Loop(Loop {
+            Node::new(NodeKind::Loop(Loop {
                 kind: LoopKind::Until,
                 cond: Box::new(t.2),
                 body: Box::new(t.3),
                 bindings: None,
-            })
+            }))
         },
-    )(i)
+    ))(i)
 }
 
 /// `for` *for_variable* [ no ⏎ ] `in` *expression* *do_clause* `end`
 pub(crate) fn for_expression(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("for"),
-            ws0,
-            for_variable,
-            no_lt,
-            tag("in"),
-            ws0,
-            expression,
-            ws0,
-            do_clause,
-            tag("end"),
-        )),
-        |_| Node::Placeholder,
-    )(i)
+    spanned(map(
+        construct(
+            "for",
+            "end",
+            tuple((
+                tag("for"),
+                ws0,
+                for_variable,
+                no_lt,
+                tag("in"),
+                ws0,
+                expression,
+                ws0,
+                do_clause,
+                tag("end"),
+            )),
+        ),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *left_hand_side* | *multiple_left_hand_side*
 pub(crate) fn for_variable(i: Input) -> NodeResult {
-    alt((left_hand_side, multiple_left_hand_side))(i)
+    spanned(alt((left_hand_side, multiple_left_hand_side)))(i)
 }
 
 #[cfg(test)]
@@ -84,7 +96,7 @@ mod tests {
             Node::loop_(
                 LoopKind::While,
                 Node::int(1),
-                Node::Block(vec![Node::int(2)]),
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
                 vec![]
             )
         );
@@ -93,7 +105,7 @@ mod tests {
             Node::loop_(
                 LoopKind::While,
                 Node::int(1),
-                Node::Block(vec![Node::int(2), Node::int(3)]),
+                Node::new(NodeKind::Block(vec![Node::int(2), Node::int(3)])),
                 vec![]
             )
         );
@@ -102,7 +114,7 @@ mod tests {
             Node::loop_(
                 LoopKind::While,
                 Node::int(1),
-                Node::Block(vec![Node::int(2), Node::int(3)]),
+                Node::new(NodeKind::Block(vec![Node::int(2), Node::int(3)])),
                 vec![]
             )
         );
@@ -120,7 +132,7 @@ mod tests {
             Node::loop_(
                 LoopKind::Until,
                 Node::int(1),
-                Node::Block(vec![Node::int(2), Node::int(3)]),
+                Node::new(NodeKind::Block(vec![Node::int(2), Node::int(3)])),
                 vec![]
             )
         );
@@ -38,49 +38,49 @@ use crate::parsers::expression::operator_expression;
 
 /// *keyword_not_expression* | *keyword_and_expression* | *keyword_or_expression*
 pub(crate) fn keyword_logical_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         keyword_or_expression,
         keyword_and_expression,
         keyword_not_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *method_invocation_without_parenthesis* | *operator_expression* | `!` *method_invocation_without_parenthesis* | `not` *keyword_not_expression*
 pub(crate) fn keyword_not_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((char('!'), ws0, method_invocation_without_parenthesis)),
             |t| {
-                Node::LogicalNot(LogicalNot {
+                Node::new(NodeKind::LogicalNot(LogicalNot {
                     expr: Box::new(t.2),
-                })
+                }))
             },
         ),
         map(tuple((tag("not"), ws0, keyword_not_expression)), |t| {
-            Node::LogicalNot(LogicalNot {
+            Node::new(NodeKind::LogicalNot(LogicalNot {
                 expr: Box::new(t.2),
-            })
+            }))
         }),
         operator_expression,
         method_invocation_without_parenthesis,
-    ))(i)
+    )))(i)
 }
 
 /// *expression* [ no ⏎ ] `and` *keyword_not_expression*
 /// `A  -> N A1 | O A1`
 pub(crate) fn keyword_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             alt((keyword_not_expression, keyword_or_expression)),
             recursing_keyword_and_expression,
         )),
         |(node, ast)| Node::decurse((node, Some(ast))),
-    )(i)
+    ))(i)
 }
 
 /// `A1 -> a N A1 | ϵ`
 fn recursing_keyword_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             tag("and"),
@@ -89,19 +89,23 @@ fn recursing_keyword_and_expression(i: Input) -> NodeResult {
             opt(recursing_keyword_and_expression),
         )),
         |t| {
-            let node = Node::LogicalAnd(LogicalAnd {
-                first: Box::new(Node::Placeholder),
-                second: Box::new(t.3),
-            });
+            let span = t.3.span;
+            let node = Node::with_span(
+                NodeKind::LogicalAnd(LogicalAnd {
+                    first: Box::new(Node::new(NodeKind::Placeholder)),
+                    second: Box::new(t.3),
+                }),
+                span,
+            );
             Node::decurse((node, t.4))
         },
-    )(i)
+    ))(i)
 }
 
 /// *expression* [ no ⏎ ] `or` *keyword_not_expression*
 /// `O  -> N O1 | N A1 O1`
 pub(crate) fn keyword_or_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             keyword_not_expression,
             opt(keyword_and_expression),
@@ -114,12 +118,12 @@ pub(crate) fn keyword_or_expression(i: Input) -> NodeResult {
                 Node::decurse((node, Some(ast)))
             }
         },
-    )(i)
+    ))(i)
 }
 
 /// `O1 -> A1 O1 | o N O1 | ϵ`
 fn recursing_keyword_or_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 recursing_keyword_and_expression,
@@ -136,29 +140,33 @@ fn recursing_keyword_or_expression(i: Input) -> NodeResult {
                 opt(recursing_keyword_or_expression),
             )),
             |t| {
-                let node = Node::LogicalOr(LogicalOr {
-                    first: Box::new(Node::Placeholder),
-                    second: Box::new(t.3),
-                });
+                let span = t.3.span;
+                let node = Node::with_span(
+                    NodeKind::LogicalOr(LogicalOr {
+                        first: Box::new(Node::new(NodeKind::Placeholder)),
+                        second: Box::new(t.3),
+                    }),
+                    span,
+                );
                 Node::decurse((node, t.4))
             },
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *operator_and_expression* | *operator_or_expression* [ no ⏎ ] `||` *operator_and_expression*
 pub(crate) fn operator_or_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             operator_and_expression,
             opt(recursing_operator_or_expression),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_operator_or_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             tag("||"),
@@ -167,25 +175,29 @@ fn recursing_operator_or_expression(i: Input) -> NodeResult {
             opt(recursing_operator_or_expression),
         )),
         |t| {
-            let node = Node::LogicalOr(LogicalOr {
-                first: Box::new(Node::Placeholder),
-                second: Box::new(t.3),
-            });
+            let span = t.3.span;
+            let node = Node::with_span(
+                NodeKind::LogicalOr(LogicalOr {
+                    first: Box::new(Node::new(NodeKind::Placeholder)),
+                    second: Box::new(t.3),
+                }),
+                span,
+            );
             Node::decurse((node, t.4))
         },
-    )(i)
+    ))(i)
 }
 
 /// *equality_expression* | *operator_and_expression* [ no ⏎ ] `&&` *equality_expression*
 pub(crate) fn operator_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((equality_expression, opt(recursing_operator_and_expression))),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 fn recursing_operator_and_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             tag("&&"),
@@ -194,13 +206,17 @@ fn recursing_operator_and_expression(i: Input) -> NodeResult {
             opt(recursing_operator_and_expression),
         )),
         |t| {
-            let node = Node::LogicalAnd(LogicalAnd {
-                first: Box::new(Node::Placeholder),
-                second: Box::new(t.3),
-            });
+            let span = t.3.span;
+            let node = Node::with_span(
+                NodeKind::LogicalAnd(LogicalAnd {
+                    first: Box::new(Node::new(NodeKind::Placeholder)),
+                    second: Box::new(t.3),
+                }),
+                span,
+            );
             Node::decurse((node, t.4))
         },
-    )(i)
+    ))(i)
 }
 
 #[cfg(test)]
@@ -7,6 +7,7 @@ use crate::parsers::expression::argument::indexing_argument_list;
 use crate::parsers::expression::begin::body_statement;
 use crate::parsers::expression::block::block;
 use crate::parsers::expression::block::do_block;
+use crate::parsers::expression::conditional::recursing_conditional_operator_expression;
 use crate::parsers::expression::jump::{
     break_with_argument, next_with_argument, return_with_argument,
 };
@@ -27,18 +28,22 @@ use crate::parsers::token::operator::operator_method_name;
 
 /// `def` *defined_method_name* [ no ⏎ ] *method_parameter_part* *method_body* `end`
 pub(crate) fn method_definition(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("def"),
-            ws0,
-            defined_method_name,
-            no_lt,
-            method_parameter_part,
-            method_body,
-            tag("end"),
-        )),
-        |_| Node::Placeholder,
-    )(i)
+    spanned(map(
+        construct(
+            "def",
+            "end",
+            tuple((
+                tag("def"),
+                ws0,
+                defined_method_name,
+                no_lt,
+                method_parameter_part,
+                method_body,
+                tag("end"),
+            )),
+        ),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *method_name* | *assignment_like_method_identifier*
@@ -54,20 +59,19 @@ pub(crate) fn method_body(i: Input) -> NodeResult {
     body_statement(i)
 }
 
-/// *primary_expression* [ no ⏎ ] `.` *method_name* ( [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis* )? *block*? | *primary_expression* [ no ⏎ ] `::` *method_name* [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis* *block*? | *primary_expression* [ no ⏎ ] `::` *method_name_except_constant* *block*?
+/// *primary_expression* *call_operator* *method_name* ( [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis* )? *block*? | *primary_expression* [ no ⏎ ] `::` *method_name* [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis* *block*? | *primary_expression* [ no ⏎ ] `::` *method_name_except_constant* *block*?
 pub(crate) fn _primary_method_invocation(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
-                no_lt,
-                char('.'),
+                call_operator,
                 ws0,
                 method_name,
                 opt(argument_with_parenthesis),
                 opt(block),
                 opt(recursing_primary_expression),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -78,7 +82,7 @@ pub(crate) fn _primary_method_invocation(i: Input) -> NodeResult {
                 argument_with_parenthesis,
                 opt(recursing_primary_expression),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -89,51 +93,69 @@ pub(crate) fn _primary_method_invocation(i: Input) -> NodeResult {
                 opt(block),
                 opt(recursing_primary_expression),
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
+}
+
+/// *ws0* ( `&.` | `.` **but not** `..` )
+pub(crate) fn call_operator(i: Input) -> LexResult {
+    // A line that begins with a call operator continues the method chain of the previous line
+    preceded(ws0, alt((tag("&."), terminated(tag("."), not(char('.'))))))(i)
 }
 
 /// *method_only_identifier*
 pub(crate) fn method_only_invocation(i: Input) -> NodeResult {
-    map(method_only_identifier, |_| Node::Placeholder)(i)
+    spanned(map(method_only_identifier, |_| {
+        Node::new(NodeKind::Placeholder)
+    }))(i)
 }
 
 /// *method_identifier* *block*
 pub(crate) fn method_invocation_with_block(i: Input) -> NodeResult {
-    map(tuple((method_identifier, no_lt, block)), |_| {
-        Node::Placeholder
-    })(i)
+    spanned(map(tuple((method_identifier, no_lt, block)), |_| {
+        Node::new(NodeKind::Placeholder)
+    }))(i)
 }
 
 /// *method_identifier* [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis* *block*?
 pub(crate) fn method_invocation_with_parenthesis(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((method_identifier, argument_with_parenthesis, opt(block))),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *method_identifier* *whitespace*+ *argument_without_parenthesis* *block*?
 pub(crate) fn simple_method_invocation_without_parenthesis(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             method_identifier,
             many1(whitespace),
+            // `x ?a :b` is a conditional operator rather than a character literal argument
+            not(recursing_conditional_operator_expression),
             argument_without_parenthesis,
             opt(block),
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *local_variable_identifier* | *constant_identifier* | *method_only_identifier*
+///
+/// Identifiers other than constants are recorded as method identifiers.
 pub(crate) fn method_identifier(i: Input) -> LexResult {
-    recognize(alt((
-        method_only_identifier,
-        local_variable_identifier,
-        constant_identifier,
-    )))(i)
+    recognize(token(
+        |identifier: &Identifier| match identifier.kind {
+            IdentifierKind::Constant => TokenKind::Identifier(IdentifierKind::Constant),
+            _ => TokenKind::Identifier(IdentifierKind::Method),
+        },
+        alt((
+            method_only_identifier,
+            local_variable_identifier,
+            constant_identifier,
+        )),
+    ))(i)
 }
 
 /// *method_identifier* | *operator_method_name* | *keyword*
@@ -143,7 +165,7 @@ pub(crate) fn method_name(i: Input) -> LexResult {
 
 /// *primary_expression* [ no ⏎ ] [ no ⎵ ] `[` *indexing_argument_list*? `]`
 pub(crate) fn _indexing_method_invocation(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             char('['),
             ws0,
@@ -152,8 +174,8 @@ pub(crate) fn _indexing_method_invocation(i: Input) -> NodeResult {
             char(']'),
             opt(recursing_primary_expression),
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *method_name* **but not** *constant_identifier*
@@ -164,7 +186,7 @@ pub(crate) fn method_name_except_constant(i: Input) -> LexResult {
 
 /// *command* | *chained_command_with_do_block* | *chained_command_with_do_block* ( `.` | `::` ) *method_name* *argument_without_parenthesis* | *return_with_argument* | *break_with_argument* | *next_with_argument*
 pub(crate) fn method_invocation_without_parenthesis(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(
             tuple((
                 chained_command_with_do_block,
@@ -172,24 +194,24 @@ pub(crate) fn method_invocation_without_parenthesis(i: Input) -> NodeResult {
                 method_name,
                 argument_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         chained_command_with_do_block,
         command,
         return_with_argument,
         break_with_argument,
         next_with_argument,
-    ))(i)
+    )))(i)
 }
 
 /// *super_with_argument* | *yield_with_argument* | *method_identifier* *argument_without_parenthesis* | *primary_expression* [ no ⏎ ] ( `.` | `::` ) *method_name* *argument_without_parenthesis*
 pub(crate) fn command(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         super_with_argument,
         yield_with_argument,
         map(
             tuple((method_identifier, argument_without_parenthesis)),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -198,38 +220,38 @@ pub(crate) fn command(i: Input) -> NodeResult {
                 method_name,
                 argument_without_parenthesis,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// *command_with_do_block* *chained_method_invocation**
 pub(crate) fn chained_command_with_do_block(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((command_with_do_block, chained_method_invocation)),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// ( `.` | `::` ) *method_name* | ( `.` | `::` ) *method_name* [ no ⏎ ] [ no ⎵ ] *argument_with_parenthesis*
 pub(crate) fn chained_method_invocation(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             alt((tag("."), tag("::"))),
             method_name,
             opt(argument_with_parenthesis),
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *super_with_argument_and_do_block* | *method_identifier* *argument_without_parenthesis* *do_block* | *primary_expression* [ no ⏎ ] ( `.` | `::` ) *method_name* *argument_without_parenthesis* *do_block*
 pub(crate) fn command_with_do_block(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         super_with_argument_and_do_block,
         map(
             tuple((method_identifier, argument_without_parenthesis, do_block)),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
         map(
             tuple((
@@ -239,21 +261,22 @@ pub(crate) fn command_with_do_block(i: Input) -> NodeResult {
                 argument_without_parenthesis,
                 do_block,
             )),
-            |_| Node::Placeholder,
+            |_| Node::new(NodeKind::Placeholder),
         ),
-    ))(i)
+    )))(i)
 }
 
 /// `(` *parameter_list*? `)` | *parameter_list*? *separator*
 pub(crate) fn method_parameter_part(i: Input) -> NodeResult {
-    alt((
-        map(tuple((char('('), opt(parameter_list), char(')'))), |_| {
-            Node::Placeholder
-        }),
+    spanned(alt((
+        map(
+            construct("(", ")", tuple((char('('), opt(parameter_list), char(')')))),
+            |_| Node::new(NodeKind::Placeholder),
+        ),
         map(tuple((opt(parameter_list), separator)), |_| {
-            Node::Placeholder
+            Node::new(NodeKind::Placeholder)
         }),
-    ))(i)
+    )))(i)
 }
 
 /// *mandatory_parameter_list* ( `,` *optional_parameter_list* )? ( `,` *array_parameter* )? ( `,` *proc_parameter* )? |  *optional_parameter_list* ( `,` *array_parameter* )? ( `,` *proc_parameter* )? | *array_parameter* ( `,` *proc_parameter* )? | *proc_parameter*
@@ -395,6 +418,24 @@ mod tests {
     use super::*;
     use crate::ast::BinaryOpKind;
 
+    #[test]
+    fn test_call_operator() {
+        use_parser!(call_operator);
+        // Parse errors
+        assert_err!("");
+        assert_err!("..");
+        assert_err!("\n..");
+        assert_err!("&");
+        assert_err!("::");
+        // Success cases
+        assert_ok!(".");
+        assert_ok!("&.");
+        assert_ok!("  .");
+        assert_ok!("\n  .");
+        assert_ok!("\n  &.");
+        assert_ok!("\n\n  # comment\n  .");
+    }
+
     // #[test]
     // fn test_method_invocation_without_parenthesis() {
     //     use_parser!(method_invocation_without_parenthesis);
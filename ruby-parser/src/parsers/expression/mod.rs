@@ -2,6 +2,8 @@ use crate::lexer::*;
 use crate::parsers::expression::object::{association, range_constructor};
 use crate::parsers::program::compound_statement;
 use crate::parsers::token::literal::literal;
+use crate::parsers::token::literal::string::adjacent_string_literal_part;
+use nom::combinator::map_opt;
 
 pub(crate) mod argument;
 pub(crate) mod assignment;
@@ -25,28 +27,31 @@ pub(crate) mod yield_;
 
 /// *keyword_logical_expression*
 pub(crate) fn expression(i: Input) -> NodeResult {
-    logical::keyword_logical_expression(i)
+    spanned(logical::keyword_logical_expression)(i)
 }
 
 /// *primary_method_invocation* | *indexing_method_invocation* | *scoped_constant_reference* | *simple_primary_expression*
 pub(crate) fn primary_expression(i: Input) -> NodeResult {
-    map(
-        tuple((simple_primary_expression, opt(recursing_primary_expression))),
-        Node::decurse,
+    memoized(
+        "primary_expression",
+        spanned(map(
+            tuple((simple_primary_expression, opt(recursing_primary_expression))),
+            Node::decurse,
+        )),
     )(i)
 }
 
 pub(crate) fn recursing_primary_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         method::_primary_method_invocation,
         method::_indexing_method_invocation,
         variable::_scoped_constant_reference,
-    ))(i)
+    )))(i)
 }
 
 /// *primary_literal_expression* | *primary_definition_expression* | *primary_conditional_expression* | *primary_iteration_expression* | *primary_jump_expression* | *primary_keyword_expression* | *begin_expression* | *grouping_expression* | *primary_method_call_expression* | *variable_reference*
 pub(crate) fn simple_primary_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         primary_literal_expression,
         primary_definition_expression,
         primary_conditional_expression,
@@ -58,90 +63,119 @@ pub(crate) fn simple_primary_expression(i: Input) -> NodeResult {
         primary_method_call_expression,
         variable::variable_reference,
         variable::simple_scoped_constant_reference,
-    ))(i)
+    )))(i)
 }
 
-/// *array_constructor* | *hash_constructor* | *literal*
+/// *array_constructor* | *hash_constructor* | *literal_expression*
 fn primary_literal_expression(i: Input) -> NodeResult {
-    alt((object::array_constructor, object::hash_constructor, literal))(i)
+    spanned(alt((
+        object::array_constructor,
+        object::hash_constructor,
+        literal_expression,
+    )))(i)
+}
+
+/// *literal* ( *whitespace** *adjacent_string_literal_part* )*
+///
+/// Juxtaposed string literals are only looked for once a string literal has been accepted, as
+/// the literal parsers themselves are retried whenever the parser backtracks.
+fn literal_expression(i: Input) -> NodeResult {
+    let (i, first) = literal(i)?;
+    let segments = match first.string_segments() {
+        Some(segments) => segments,
+        None => return Ok((i, first)),
+    };
+    let (i, mut parts) = many0(map_opt(
+        preceded(no_lt, adjacent_string_literal_part),
+        |part| part.string_segments().map(|segments| (part, segments)),
+    ))(i)?;
+    if parts.is_empty() {
+        return Ok((i, first));
+    }
+    parts.insert(0, (first, segments));
+    Ok((i, Node::concatenated(parts)))
 }
 
 /// *defined_with_parenthesis* | *super_with_optional_argument* | *yield_with_optional_argument*
 fn primary_keyword_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         defined::defined_with_parenthesis,
         super_::super_with_optional_argument,
         yield_::yield_with_optional_argument,
-    ))(i)
+    )))(i)
 }
 
 /// *class_definition* | *singleton_class_definition* | *singleton_method_definition* | *module_definition* | *method_definition*
 fn primary_definition_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         class::class_definition,
         singleton::singleton_class_definition,
         singleton::singleton_method_definition,
         module::module_definition,
         method::method_definition,
-    ))(i)
+    )))(i)
 }
 
 /// *return_without_argument* | *break_without_argument* | *next_without_argument* | *redo_expression* | *retry_expression*
 fn primary_jump_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         jump::return_without_argument,
         jump::break_without_argument,
         jump::next_without_argument,
         jump::redo_expression,
         jump::retry_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *if_expression* | *unless_expression* | *case_expression*
 fn primary_conditional_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         conditional::if_expression,
         conditional::unless_expression,
         conditional::case_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *while_expression* | *until_expression* | *for_expression*
 fn primary_iteration_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         iteration::while_expression,
         iteration::until_expression,
         iteration::for_expression,
-    ))(i)
+    )))(i)
 }
 
 /// *method_only_invocation* | *method_invocation_with_block* | *method_invocation_with_parenthesis*
 fn primary_method_call_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         method::simple_method_invocation_without_parenthesis, // Added for Ruby 2.0
         method::method_invocation_with_parenthesis,
         method::method_only_invocation,
         method::method_invocation_with_block,
-    ))(i)
+    )))(i)
 }
 
 /// `(` *compound_statement* `)`
 pub(crate) fn grouping_expression(i: Input) -> NodeResult {
-    map(tuple((char('('), compound_statement, char(')'))), |t| t.1)(i)
+    spanned(map(
+        construct("(", ")", tuple((char('('), compound_statement, char(')')))),
+        |t| t.1,
+    ))(i)
 }
 
 /// *assignment_expression* | *defined_without_parenthesis* | *conditional_operator_expression*
 pub(crate) fn operator_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         assignment::assignment_expression,
         defined::defined_without_parenthesis,
         conditional::conditional_operator_expression,
-    ))(i)
+    )))(i)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::Concatenated;
 
     #[test]
     fn test_primary_expression() {
@@ -155,19 +189,21 @@ mod tests {
         assert_err!("::foo");
         assert_err!("foo[1]()");
         // Success cases
-        assert_ok!("nil", Node::Nil);
+        assert_ok!("nil", Node::new(NodeKind::Nil));
         assert_ok!("42", Node::int(42));
         assert_ok!("24.2", Node::float(24.2));
         assert_ok!("meh", Node::ident("meh", IdentifierKind::LocalVariable));
         assert_ok!("-23e4", Node::float(-230000.0));
         assert_ok!("'hello world'", Node::literal_string("hello world"));
-        assert_ok!("redo", Node::Redo);
-        assert_ok!("retry", Node::Retry);
-        assert_ok!("return", Node::Return(vec![]));
-        assert_ok!("break", Node::Break(vec![]));
-        assert_ok!("next", Node::Next(vec![]));
-        assert_ok!("()", Node::Block(vec![]));
+        assert_ok!("redo", Node::new(NodeKind::Redo));
+        assert_ok!("retry", Node::new(NodeKind::Retry));
+        assert_ok!("return", Node::new(NodeKind::Return(vec![])));
+        assert_ok!("break", Node::new(NodeKind::Break(vec![])));
+        assert_ok!("next", Node::new(NodeKind::Next(vec![])));
+        assert_ok!("()", Node::new(NodeKind::Block(vec![])));
         assert_ok!("foo.bar.baz");
+        assert_ok!("foo&.bar");
+        assert_ok!("users\n  .where(active: true)\n  # sorted\n  &.order(:name)");
         assert_ok!("foo::BAR");
         assert_ok!("::BAR");
         assert_ok!("::BAR::Baz");
@@ -176,11 +212,115 @@ mod tests {
         assert_ok!("foo[1, 2][2].bar().baz[3]");
         assert_ok!("require 1, 2 + 3");
         assert_ok!("require 'bar/blah'");
+        assert_ok!("puts ?a, ?b");
         assert_ok!(
             "((false))",
-            Node::Block(vec![Node::Block(vec![Node::boolean(false)])])
+            Node::new(NodeKind::Block(vec![Node::new(NodeKind::Block(vec![
+                Node::boolean(false)
+            ]))]))
+        );
+        assert_ok!(
+            "(;2\n\t5;;)",
+            Node::new(NodeKind::Block(vec![Node::int(2), Node::int(5)]))
+        );
+        assert_ok!("(;)", Node::new(NodeKind::Block(vec![])));
+    }
+
+    #[test]
+    fn test_literal_expression() {
+        fn s(v: &str) -> Node {
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
+        }
+        fn seg(v: &str) -> Node {
+            Node::new(NodeKind::Segment(Segment::String(v.to_owned())))
+        }
+        fn c(value: Node, parts: Vec<Node>) -> Node {
+            Node::new(NodeKind::Concatenated(Concatenated {
+                value: Box::new(value),
+                parts,
+            }))
+        }
+        use_parser!(literal_expression);
+        // Single literals
+        assert_ok!("'foo'", s("foo"));
+        assert_ok!(":foo", Node::literal_symbol("foo"));
+        // Adjacent literals
+        assert_ok!(
+            "\"meh\"  \"Blah\"",
+            c(s("mehBlah"), vec![s("meh"), s("Blah")])
+        );
+        assert_ok!(
+            "'a' \"b\" %q(c) %Q{d}",
+            c(s("abcd"), vec![s("a"), s("b"), s("c"), s("d")])
+        );
+        assert_ok!(
+            "?a 'b'",
+            c(
+                s("ab"),
+                vec![
+                    Node::new(NodeKind::Literal(Literal::Character("a".to_owned()))),
+                    s("b")
+                ]
+            )
+        );
+        assert_ok!("'a' \\\n 'b'", c(s("ab"), vec![s("a"), s("b")]));
+        assert_ok!(
+            "'a' \"b#{1}c\" 'd'",
+            c(
+                Node::new(NodeKind::Interpolated(Interpolated::String(vec![
+                    seg("ab"),
+                    Node::new(NodeKind::Block(vec![Node::int(1)])),
+                    seg("cd")
+                ]))),
+                vec![
+                    s("a"),
+                    Node::new(NodeKind::Interpolated(Interpolated::String(vec![
+                        seg("b"),
+                        Node::new(NodeKind::Block(vec![Node::int(1)])),
+                        seg("c")
+                    ]))),
+                    s("d")
+                ]
+            )
+        );
+        assert_ok!(
+            "\"\\xE2\\x82\" '\\xAC'",
+            c(
+                Node::new(NodeKind::Literal(Literal::Bytes(vec![
+                    0xE2, 0x82, b'\\', b'x', b'A', b'C'
+                ]))),
+                vec![
+                    Node::new(NodeKind::Literal(Literal::Bytes(vec![0xE2, 0x82]))),
+                    s("\\xAC")
+                ]
+            )
+        );
+        assert_ok!(
+            "\"\\xE2\\x82\" \"\\xAC\"",
+            c(
+                s("€"),
+                vec![
+                    Node::new(NodeKind::Literal(Literal::Bytes(vec![0xE2, 0x82]))),
+                    Node::new(NodeKind::Literal(Literal::Bytes(vec![0xAC])))
+                ]
+            )
+        );
+        // Heredocs and character literals may only begin a sequence
+        assert_partial!("'a' ?b", s("a"));
+        assert_partial!("'a' <<B\nb\nB", s("a"));
+        assert_partial!("'a'\n'b'", s("a"));
+        assert_partial!(
+            "`a` 'b'",
+            Node::new(NodeKind::Literal(Literal::Command("a".to_owned())))
+        );
+        assert_partial!(":a 'b'", Node::literal_symbol("a"));
+        // Heredocs continued on their start line
+        assert_ok!(
+            "<<~MARK \\\nfoobar\nMARK\n\"meh\"  \"Blah\"",
+            c(
+                s("foobar\nmehBlah"),
+                vec![s("foobar\n"), s("meh"), s("Blah")]
+            )
         );
-        assert_ok!("(;2\n\t5;;)", Node::Block(vec![Node::int(2), Node::int(5)]));
-        assert_ok!("(;)", Node::Block(vec![]));
     }
 }
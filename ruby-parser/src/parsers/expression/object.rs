@@ -1,80 +1,138 @@
-use crate::ast::Ranged;
+use crate::ast::{Association, PairSyntax, Ranged, Span};
 use crate::lexer::*;
 use crate::parsers::expression::argument::{comma, indexing_argument_list};
 use crate::parsers::expression::logical::operator_or_expression;
 use crate::parsers::expression::operator_expression;
-use crate::parsers::token::literal::string::{double_quoted_string, single_quoted_string};
+use crate::parsers::program::line_terminator;
+use crate::parsers::token::identifier::{constant_identifier, local_variable_identifier};
+use crate::parsers::token::literal::string::double::valid_double_quoted_string;
+use crate::parsers::token::literal::string::single_quoted_string;
 use crate::parsers::token::literal::symbol::symbol_name;
 
 /// `[` *indexing_argument_list*? `]`
 pub(crate) fn array_constructor(i: Input) -> NodeResult {
-    map(
-        tuple((char('['), ws0, opt(indexing_argument_list), ws0, char(']'))),
-        |t| Node::Array(t.2.unwrap_or(vec![])),
-    )(i)
+    spanned(map(
+        construct(
+            "[",
+            "]",
+            tuple((char('['), ws0, opt(indexing_argument_list), ws0, char(']'))),
+        ),
+        |t| Node::new(NodeKind::Array(t.2.unwrap_or(vec![]))),
+    ))(i)
 }
 
 /// `{` ( *association_list* [ no ⏎ ] `,`? )? `}`
 pub(crate) fn hash_constructor(i: Input) -> NodeResult {
-    map(
-        tuple((
-            char('{'),
-            ws0,
-            opt(map(tuple((association_list, opt(comma), ws0)), |t| t.0)),
-            ws0,
-            char('}'),
-        )),
-        |t| Node::Hash(t.2.unwrap_or(vec![])),
-    )(i)
+    spanned(map(
+        construct(
+            "{",
+            "}",
+            tuple((
+                char('{'),
+                ws0,
+                opt(map(tuple((association_list, opt(comma), ws0)), |t| t.0)),
+                ws0,
+                char('}'),
+            )),
+        ),
+        |t| Node::new(NodeKind::Hash(t.2.unwrap_or(vec![]))),
+    ))(i)
 }
 
 /// *association* ( [ no ⏎ ] `,` *association* )*
-pub(crate) fn association_list(i: Input) -> NodeListResult {
+pub(crate) fn association_list(i: Input) -> Parsed<Vec<Association>> {
     map(
         tuple((
             association,
             many0(map(tuple((no_lt, char(','), ws0, association)), |t| t.3)),
         )),
-        |(mut first, vec)| {
-            first.extend(vec.into_iter().flatten().collect::<Vec<Node>>());
-            first
+        |(first, mut vec)| {
+            vec.insert(0, first);
+            vec
         },
     )(i)
 }
 
-/// *association_key* [ no ⏎ ] `=>` *association_value* | *symbol_name* `:` *association_value* | *single_quoted_string* `:` *association_value* | *double_quoted_string* `:` *association_value*
-pub(crate) fn association(i: Input) -> NodeListResult {
+/// *association_key* [ no ⏎ ] `=>` *association_value* | *symbol_name* `:` *association_value* | *single_quoted_string* `:` *association_value* | *double_quoted_string* `:` *association_value* | *association_shorthand* | *double_splatting_association*
+pub(crate) fn association(i: Input) -> Parsed<Association> {
     alt((
         map(
             tuple((association_key, no_lt, tag("=>"), ws0, association_value)),
-            |t| vec![t.0, t.4],
+            |t| Association::pair(t.0, t.4, PairSyntax::Rocket),
         ),
         map(
             tuple((
-                alt((map(symbol_name, |s| s.to_string()), single_quoted_string)),
+                spanned(map(symbol_name, |s| {
+                    Node::new(NodeKind::Literal(Literal::Symbol(s.to_string())))
+                })),
                 char(':'),
                 ws0,
                 association_value,
             )),
-            |t| vec![Node::Literal(Literal::Symbol(t.0)), t.3],
+            |t| Association::pair(t.0, t.3, PairSyntax::Label),
         ),
         map(
-            tuple((double_quoted_string, char(':'), ws0, association_value)),
-            |t| {
-                vec![
-                    match t.0 {
-                        Interpolatable::String(s) => Node::Literal(Literal::Symbol(s)),
-                        Interpolatable::Interpolated(vec) => {
-                            Node::Interpolated(Interpolated::Symbol(vec))
-                        }
-                    },
-                    t.3,
-                ]
-            },
+            tuple((
+                spanned(map(single_quoted_string, |s| {
+                    Node::new(NodeKind::Literal(Literal::Symbol(s)))
+                })),
+                char(':'),
+                ws0,
+                association_value,
+            )),
+            |t| Association::pair(t.0, t.3, PairSyntax::QuotedLabel),
+        ),
+        map(
+            tuple((
+                spanned(map(valid_double_quoted_string, |s| match s {
+                    Interpolatable::String(s) => Node::new(NodeKind::Literal(Literal::Symbol(s))),
+                    Interpolatable::Bytes(_) => unreachable!(),
+                    Interpolatable::Interpolated(vec) => {
+                        Node::new(NodeKind::Interpolated(Interpolated::Symbol(vec)))
+                    }
+                })),
+                char(':'),
+                ws0,
+                association_value,
+            )),
+            |t| Association::pair(t.0, t.3, PairSyntax::QuotedLabel),
         ),
+        association_shorthand,
+        double_splatting_association,
     ))(i)
 }
 
+/// ( *local_variable_identifier* | *constant_identifier* ) `:` [ followed by `,`, `}`, `)`, `]`, ⏎ or the end of the input ]
+pub(crate) fn association_shorthand(i: Input) -> Parsed<Association> {
+    // Both the key and the value are written as the identifier
+    let start = i.position();
+    let (i, ident) = alt((local_variable_identifier, constant_identifier))(i)?;
+    let span = Span::new(start, i.position());
+    let (i, _) = tuple((
+        char(':'),
+        peek(alt((
+            recognize(tuple((ws0, one_of(",})]")))),
+            recognize(tuple((no_lt, line_terminator))),
+            recognize(tuple((ws0, not(anychar)))),
+        ))),
+    ))(i)?;
+    Ok((
+        i,
+        Association::pair(
+            Node::with_span(NodeKind::Literal(Literal::Symbol(ident.name.clone())), span),
+            Node::with_span(NodeKind::Identifier(ident), span),
+            PairSyntax::Shorthand,
+        ),
+    ))
+}
+
+/// `**` *operator_expression*
+pub(crate) fn double_splatting_association(i: Input) -> Parsed<Association> {
+    map(tuple((tag("**"), ws0, operator_expression)), |t| {
+        Association::double_splat(t.2)
+    })(i)
+}
+
 /// *operator_expression*
 pub(crate) fn association_key(i: Input) -> NodeResult {
     operator_expression(i)
@@ -89,13 +147,17 @@ pub(crate) fn association_value(i: Input) -> NodeResult {
 pub(crate) fn range_constructor(i: Input) -> NodeResult {
     let (i, lhs) = operator_or_expression(i)?;
     if let Ok((j, t)) = tuple((no_lt, range_operator, ws0, operator_or_expression))(i.clone()) {
+        let span = lhs.span.to(&t.3.span);
         Ok((
             j,
-            Node::Ranged(Ranged {
-                from: Box::new(lhs),
-                to: Box::new(t.3),
-                exclusive: *t.1 == "...",
-            }),
+            Node::with_span(
+                NodeKind::Ranged(Ranged {
+                    from: Box::new(lhs),
+                    to: Box::new(t.3),
+                    exclusive: *t.1 == "...",
+                }),
+                span,
+            ),
         ))
     } else {
         Ok((i, lhs))
@@ -121,42 +183,122 @@ mod tests {
         assert_err!("{1: 2}");
         assert_err!("{foo : 2}");
         assert_err!("{1 \n => 2}");
+        assert_err!("{foo?:}");
+        assert_err!("{'foo':}");
+        assert_err!("{**}");
         // Success cases
-        assert_ok!("{}", Node::Hash(vec![]));
-        assert_ok!("{1=>2}", Node::Hash(vec![Node::int(1), Node::int(2)]));
+        assert_ok!("{}", Node::new(NodeKind::Hash(vec![])));
+        assert_ok!(
+            "{1=>2}",
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::int(1),
+                Node::int(2),
+                PairSyntax::Rocket
+            )]))
+        );
         assert_ok!(
             "{'1': 2}",
-            Node::Hash(vec![Node::literal_symbol("1"), Node::int(2)])
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::literal_symbol("1"),
+                Node::int(2),
+                PairSyntax::QuotedLabel
+            )]))
         );
         assert_ok!(
             "{foo: 2}",
-            Node::Hash(vec![Node::literal_symbol("foo"), Node::int(2)])
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::literal_symbol("foo"),
+                Node::int(2),
+                PairSyntax::Label
+            )]))
         );
         assert_ok!(
             "{\"foo\": 2}",
-            Node::Hash(vec![Node::literal_symbol("foo"), Node::int(2)])
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::literal_symbol("foo"),
+                Node::int(2),
+                PairSyntax::QuotedLabel
+            )]))
         );
         assert_ok!(
             "{1 => 2,\n\n 3=>\n{}}",
-            Node::Hash(vec![
-                Node::int(1),
-                Node::int(2),
-                Node::int(3),
-                Node::Hash(vec![])
-            ])
+            Node::new(NodeKind::Hash(vec![
+                Association::pair(Node::int(1), Node::int(2), PairSyntax::Rocket),
+                Association::pair(
+                    Node::int(3),
+                    Node::new(NodeKind::Hash(vec![])),
+                    PairSyntax::Rocket
+                )
+            ]))
         );
         assert_ok!(
             "{\"foo#{1}\": 2}",
-            Node::Hash(vec![
-                Node::Interpolated(Interpolated::Symbol(vec![
-                    Node::Segment(Segment::String("foo".to_owned())),
-                    Node::Block(vec![Node::int(1)])
-                ])),
-                Node::int(2)
-            ])
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::new(NodeKind::Interpolated(Interpolated::Symbol(vec![
+                    Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
+                    Node::new(NodeKind::Block(vec![Node::int(1)]))
+                ]))),
+                Node::int(2),
+                PairSyntax::QuotedLabel
+            )]))
+        );
+        // Value omission
+        assert_ok!(
+            "{x:, Y: }",
+            Node::new(NodeKind::Hash(vec![
+                Association::pair(
+                    Node::literal_symbol("x"),
+                    Node::ident("x", IdentifierKind::LocalVariable),
+                    PairSyntax::Shorthand
+                ),
+                Association::pair(
+                    Node::literal_symbol("Y"),
+                    Node::ident("Y", IdentifierKind::Constant),
+                    PairSyntax::Shorthand
+                )
+            ]))
+        );
+        assert_ok!(
+            "{\n  foo:\n}",
+            Node::new(NodeKind::Hash(vec![Association::pair(
+                Node::literal_symbol("foo"),
+                Node::ident("foo", IdentifierKind::LocalVariable),
+                PairSyntax::Shorthand
+            )]))
+        );
+        // Double splats
+        assert_ok!(
+            "{**opts, a: 1, ** {}}",
+            Node::new(NodeKind::Hash(vec![
+                Association::double_splat(Node::ident("opts", IdentifierKind::LocalVariable)),
+                Association::pair(Node::literal_symbol("a"), Node::int(1), PairSyntax::Label),
+                Association::double_splat(Node::new(NodeKind::Hash(vec![])))
+            ]))
         );
     }
 
+    #[test]
+    fn test_association_shorthand() {
+        use_parser!(association_shorthand);
+        let x = || {
+            Association::pair(
+                Node::literal_symbol("x"),
+                Node::ident("x", IdentifierKind::LocalVariable),
+                PairSyntax::Shorthand,
+            )
+        };
+        // Parse errors
+        assert_err!("x: 1");
+        assert_err!("x:y");
+        assert_err!("x:\\\n");
+        // Success cases
+        assert_ok!("x:", x());
+        assert_remaining!("x:  ", "  ");
+        assert_remaining!("x:\n1", "\n1");
+        assert_remaining!("x:]", "]");
+        assert_remaining!("x: , y:", " , y:");
+    }
+
     #[test]
     fn test_array_constructor() {
         use_parser!(array_constructor);
@@ -172,6 +314,14 @@ mod tests {
                 Node::array(vec![])
             ])
         );
+        assert_ok!(
+            "[x:]",
+            Node::array(vec![Node::hash(vec![Association::pair(
+                Node::literal_symbol("x"),
+                Node::ident("x", IdentifierKind::LocalVariable),
+                PairSyntax::Shorthand
+            )])])
+        );
     }
 
     #[test]
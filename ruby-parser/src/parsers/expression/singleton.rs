@@ -9,19 +9,23 @@ use crate::parsers::program::separator;
 
 /// `class` `<<` *expression* *separator* *singleton_class_body* `end`
 pub(crate) fn singleton_class_definition(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("class"),
-            ws0,
-            tag("<<"),
-            ws0,
-            expression,
-            separator,
-            singleton_class_body,
-            tag("end"),
-        )),
-        |_| Node::Placeholder,
-    )(i)
+    spanned(map(
+        construct(
+            "class",
+            "end",
+            tuple((
+                tag("class"),
+                ws0,
+                tag("<<"),
+                ws0,
+                expression,
+                separator,
+                singleton_class_body,
+                tag("end"),
+            )),
+        ),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *body_statement*
@@ -31,27 +35,31 @@ pub(crate) fn singleton_class_body(i: Input) -> NodeResult {
 
 /// `def` *singleton* ( `.` | `::` ) *defined_method_name* [ no ⏎ ] *method_parameter_part* *method_body* `end`
 pub(crate) fn singleton_method_definition(i: Input) -> NodeResult {
-    map(
-        tuple((
-            tag("def"),
-            ws0,
-            singleton,
-            alt((tag("."), tag("::"))),
-            ws0,
-            defined_method_name,
-            no_lt,
-            method_parameter_part,
-            method_body,
-            tag("end"),
-        )),
-        |_| Node::Placeholder,
-    )(i)
+    spanned(map(
+        construct(
+            "def",
+            "end",
+            tuple((
+                tag("def"),
+                ws0,
+                singleton,
+                alt((tag("."), tag("::"))),
+                ws0,
+                defined_method_name,
+                no_lt,
+                method_parameter_part,
+                method_body,
+                tag("end"),
+            )),
+        ),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// *variable_reference* | `(` *expression* `)`
 pub(crate) fn singleton(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         variable_reference,
         map(tuple((char('('), ws0, expression, ws0, char(')'))), |t| t.2),
-    ))(i)
+    )))(i)
 }
@@ -5,28 +5,35 @@ use crate::parsers::expression::primary_expression;
 
 /// *power_expression* | `-` *power_expression*
 pub(crate) fn unary_minus_expression(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         power_expression,
         map(tuple((char('-'), ws0, power_expression)), |t| {
-            Node::UnaryOp(UnaryOp {
+            Node::new(NodeKind::UnaryOp(UnaryOp {
                 op: Op::from(t.0),
                 rhs: Box::new(t.2),
-            })
+            }))
         }),
-    ))(i)
+    )))(i)
 }
 
 /// *primary_expression* | `~` *unary_expression* | `+` *unary_expression* | `!` *unary_expression*
 pub(crate) fn unary_expression(i: Input) -> NodeResult {
-    alt((
-        map(tuple((one_of("~+!"), ws0, unary_expression)), |t| {
-            Node::UnaryOp(UnaryOp {
-                op: Op::from(t.0),
-                rhs: Box::new(t.2),
-            })
-        }),
+    spanned(alt((
+        map(
+            tuple((
+                token(|_| TokenKind::Operator, one_of("~+!")),
+                ws0,
+                unary_expression,
+            )),
+            |t| {
+                Node::new(NodeKind::UnaryOp(UnaryOp {
+                    op: Op::from(t.0),
+                    rhs: Box::new(t.2),
+                }))
+            },
+        ),
         map(primary_expression, |t| Node::from(t)),
-    ))(i)
+    )))(i)
 }
 
 #[cfg(test)]
@@ -42,9 +49,15 @@ mod tests {
         assert_err!("---42");
         assert_err!("- - 42");
         // Success cases
-        assert_ok!("nil", Node::Nil);
-        assert_ok!("-nil", Node::unary_op(Op::Negative, Node::Nil));
-        assert_ok!("-\n\n  nil", Node::unary_op(Op::Negative, Node::Nil));
+        assert_ok!("nil", Node::new(NodeKind::Nil));
+        assert_ok!(
+            "-nil",
+            Node::unary_op(Op::Negative, Node::new(NodeKind::Nil))
+        );
+        assert_ok!(
+            "-\n\n  nil",
+            Node::unary_op(Op::Negative, Node::new(NodeKind::Nil))
+        );
         assert_ok!("-42", Node::int(-42));
         assert_ok!("- 42", Node::unary_op(Op::Negative, Node::int(42)));
         assert_ok!("--42", Node::unary_op(Op::Negative, Node::int(-42)));
@@ -68,7 +81,7 @@ mod tests {
         assert_err!("('");
         assert_err!("((foo)");
         // Success cases
-        assert_ok!("nil", Node::Nil);
+        assert_ok!("nil", Node::new(NodeKind::Nil));
         assert_ok!("+42", Node::unary_op(Op::Positive, Node::int(42)));
         assert_ok!(
             "!! meh",
@@ -81,6 +94,9 @@ mod tests {
             )
         );
         assert_ok!("-23e4", Node::float(-230000.0));
-        assert_ok!("~(;)", Node::unary_op(Op::BitNot, Node::Block(vec![])));
+        assert_ok!(
+            "~(;)",
+            Node::unary_op(Op::BitNot, Node::new(NodeKind::Block(vec![])))
+        );
     }
 }
@@ -5,7 +5,7 @@ use crate::parsers::token::identifier::*;
 
 /// *pseudo_variable* | *variable*
 pub(crate) fn variable_reference(i: Input) -> NodeResult {
-    alt((pseudo_variable, map(variable, |v| Node::from(v))))(i)
+    spanned(alt((pseudo_variable, map(variable, |v| Node::from(v)))))(i)
 }
 
 /// *constant_identifier* | *global_variable_identifier* | *class_variable_identifier* | *instance_variable_identifier* | *local_variable_identifier*
@@ -19,65 +19,93 @@ pub(crate) fn variable(i: Input) -> IdentifierResult {
     ))(i)
 }
 
-/// *nil_expression* | *true_expression* | *false_expression* | *self_expression*
+/// *nil_expression* | *true_expression* | *false_expression* | *self_expression* | *file_expression* | *line_expression* | *encoding_expression*
 pub(crate) fn pseudo_variable(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         nil_expression,
         true_expression,
         false_expression,
         self_expression,
-    ))(i)
+        file_expression,
+        line_expression,
+        encoding_expression,
+    )))(i)
 }
 
 /// `::` *constant_identifier*
 pub(crate) fn simple_scoped_constant_reference(i: Input) -> NodeResult {
-    map(tuple((tag("::"), ws0, constant_identifier)), |_| {
-        Node::Placeholder
-    })(i)
+    spanned(map(tuple((tag("::"), ws0, constant_identifier)), |_| {
+        Node::new(NodeKind::Placeholder)
+    }))(i)
 }
 
 /// `::` *constant_identifier*
 pub(crate) fn _scoped_constant_reference(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             tag("::"),
             ws0,
             constant_identifier,
             opt(recursing_primary_expression),
         )),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// `nil`
 pub(crate) fn nil_expression(i: Input) -> NodeResult {
-    map(tuple((tag("nil"), not(peek(identifier_character)))), |_| {
-        Node::Nil
-    })(i)
+    spanned(map(
+        tuple((tag("nil"), not(peek(identifier_character)))),
+        |_| Node::new(NodeKind::Nil),
+    ))(i)
 }
 
 /// `true`
 pub(crate) fn true_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((tag("true"), not(peek(identifier_character)))),
-        |_| Node::Literal(Literal::Boolean(true)),
-    )(i)
+        |_| Node::new(NodeKind::Literal(Literal::Boolean(true))),
+    ))(i)
 }
 
 /// `false`
 pub(crate) fn false_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((tag("false"), not(peek(identifier_character)))),
-        |_| Node::Literal(Literal::Boolean(false)),
-    )(i)
+        |_| Node::new(NodeKind::Literal(Literal::Boolean(false))),
+    ))(i)
 }
 
 /// `self`
 pub(crate) fn self_expression(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((tag("self"), not(peek(identifier_character)))),
-        |_| Node::Self_,
-    )(i)
+        |_| Node::new(NodeKind::Self_),
+    ))(i)
+}
+
+/// `__FILE__`
+pub(crate) fn file_expression(i: Input) -> NodeResult {
+    let file = i.metadata.file.map(|f| f.to_owned());
+    spanned(map(tag("__FILE__"), move |_| {
+        Node::new(NodeKind::File(file.clone()))
+    }))(i)
+}
+
+/// `__LINE__`
+pub(crate) fn line_expression(i: Input) -> NodeResult {
+    let line = i.line();
+    spanned(map(tag("__LINE__"), move |_| {
+        Node::new(NodeKind::Line(line))
+    }))(i)
+}
+
+/// `__ENCODING__`
+pub(crate) fn encoding_expression(i: Input) -> NodeResult {
+    let encoding = i.metadata.encoding.name();
+    spanned(map(tag("__ENCODING__"), move |_| {
+        Node::new(NodeKind::Encoding(encoding.to_owned()))
+    }))(i)
 }
 
 #[cfg(test)]
@@ -92,10 +120,10 @@ mod tests {
         assert_err!("nil ");
         assert_err!("bar\n");
         // Success cases
-        assert_ok!("nil", Node::Nil);
+        assert_ok!("nil", Node::new(NodeKind::Nil));
         assert_ok!("true", Node::boolean(true));
         assert_ok!("false", Node::boolean(false));
-        assert_ok!("self", Node::Self_);
+        assert_ok!("self", Node::new(NodeKind::Self_));
         assert_ok!("TRUE", Node::ident("TRUE", IdentifierKind::Constant));
         assert_ok!("False", Node::ident("False", IdentifierKind::Constant));
         assert_ok!("nil_", Node::ident("nil_", IdentifierKind::LocalVariable));
@@ -103,5 +131,33 @@ mod tests {
             "$true",
             Node::ident("$true", IdentifierKind::GlobalVariable)
         );
+        assert_ok!(
+            "__ENCODING__",
+            Node::new(NodeKind::Encoding("UTF-8".to_owned()))
+        );
+        assert_ok!("__LINE__", Node::new(NodeKind::Line(1)));
+        assert_ok!("__FILE__", Node::new(NodeKind::File(None)));
+        assert_ok!(
+            "__FILE__foo",
+            Node::ident("__FILE__foo", IdentifierKind::LocalVariable)
+        );
+    }
+
+    #[test]
+    fn test_pseudo_variable() {
+        // Line numbers are resolved from the input's position
+        assert_eq!(
+            Node::new(NodeKind::Line(3)),
+            pseudo_variable(Input::new_with_pos("__LINE__", 20, 3, 5))
+                .unwrap()
+                .1
+        );
+        // File names are resolved from the input's metadata
+        let mut i: Input = "__FILE__".into();
+        i.metadata.file = Some("lib/foo.rb");
+        assert_eq!(
+            Node::new(NodeKind::File(Some("lib/foo.rb".to_owned()))),
+            pseudo_variable(i).unwrap().1
+        );
     }
 }
@@ -4,31 +4,32 @@ use crate::parsers::expression::argument::argument_without_parenthesis;
 
 /// *yield_with_parenthesis_and_argument* | *yield_with_parenthesis_without_argument* | `yield`
 pub(crate) fn yield_with_optional_argument(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         yield_with_parenthesis_and_argument,
         yield_with_parenthesis_without_argument,
-        map(tag("yield"), |_| Node::Placeholder),
-    ))(i)
+        map(tag("yield"), |_| Node::new(NodeKind::Placeholder)),
+    )))(i)
 }
 
 /// `yield` [ no ⏎ ] [ no ⎵ ] `(` *argument_list `)`
 pub(crate) fn yield_with_parenthesis_and_argument(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((tag("yield("), ws0, argument_list, ws0, char(')'))),
-        |_| Node::Placeholder,
-    )(i)
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
 
 /// `yield` [ no ⏎ ] [ no ⎵ ] `()`
 pub(crate) fn yield_with_parenthesis_without_argument(i: Input) -> NodeResult {
-    map(tuple((tag("yield("), ws0, char(')'))), |_| {
-        Node::Placeholder
-    })(i)
+    spanned(map(tuple((tag("yield("), ws0, char(')'))), |_| {
+        Node::new(NodeKind::Placeholder)
+    }))(i)
 }
 
 /// `yield` *argument_without_parenthesis*
 pub(crate) fn yield_with_argument(i: Input) -> NodeResult {
-    map(tuple((tag("yield"), argument_without_parenthesis)), |_| {
-        Node::Placeholder
-    })(i)
+    spanned(map(
+        tuple((tag("yield"), argument_without_parenthesis)),
+        |_| Node::new(NodeKind::Placeholder),
+    ))(i)
 }
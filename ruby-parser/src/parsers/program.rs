@@ -1,42 +1,185 @@
 //! Provides parsers for program text
+use crate::ast::{MagicComments, Shebang};
 use crate::lexer::*;
-use crate::parsers::comment::comment;
-use crate::parsers::statement::statement;
+use crate::parsers::comment::{comment, leading_comments, line_content};
+use crate::parsers::statement::{begin_block_statement, statement};
 use nom::character::complete::line_ending;
 
-/// *compound_statement*
+/// *byte_order_mark*? *leading_garbage*? *toplevel_compound_statement*
 pub fn program(i: Input) -> ProgramResult {
-    let (i, program) = compound_statement(i)?;
+    let (i, (shebang, magic_comments)) = program_header(i)?;
+    let encoding = i.metadata.encoding;
+    let (i, (begin_blocks, program)) = toplevel_compound_statement(i)?;
     let (i, data) = opt(end_of_program_marker)(i)?;
+    let comments = i.metadata.tokens.map_or(vec![], |tokens| {
+        tokens.borrow().comments.values().cloned().collect()
+    });
     Ok((
         i,
         Program {
             program: program,
-            data: if let Some(Node::EndOfProgram(data)) = data {
-                Some(data)
-            } else {
-                None
+            begin_blocks,
+            shebang,
+            magic_comments,
+            comments,
+            encoding,
+            data: match data {
+                Some(Node {
+                    kind: NodeKind::EndOfProgram(data),
+                    ..
+                }) => Some(data),
+                _ => None,
             },
         },
     ))
 }
 
-/// *statement_list*? *separator_list*?
-pub(crate) fn compound_statement(i: Input) -> NodeResult {
-    map(
-        tuple((ws0, opt(statement_list), opt(separator_list), ws0)),
-        |t| t.1.unwrap_or(Node::Block(vec![])),
+/// *byte_order_mark*? *leading_garbage*? [ the shebang and magic comments ]
+///
+/// The encoding named by a magic comment is recorded in the returned input's metadata.
+pub(crate) fn program_header(i: Input) -> Parsed<(Option<Shebang>, MagicComments)> {
+    let (i, _) = opt(byte_order_mark)(i)?;
+    let (i, _) = if i.metadata.skip_leading_garbage {
+        leading_garbage(i)?
+    } else {
+        (i.clone(), i)
+    };
+    let (i, shebang) = opt(peek(shebang))(i)?;
+    let (mut i, magic_comments) = leading_comments(i)?;
+    // Input decoded from bytes has already had its encoding detected
+    if i.metadata.encoding == Encoding::default() {
+        if let Some(encoding) = magic_comments
+            .encoding
+            .as_deref()
+            .and_then(Encoding::from_name)
+        {
+            i.metadata.encoding = encoding;
+        }
+    }
+    Ok((i, (shebang, magic_comments)))
+}
+
+/// U+FEFF
+fn byte_order_mark(i: Input) -> LexResult {
+    token(|_| TokenKind::Whitespace, lexical::tag("\u{FEFF}"))(i)
+}
+
+/// ( *line_content*? *line_terminator* **but not** *ruby_shebang* )* [ followed by *ruby_shebang* ]
+///
+/// The garbage is recorded as a comment, since it is ignored like one.
+pub(crate) fn leading_garbage(i: Input) -> LexResult {
+    token(
+        |_| TokenKind::Comment,
+        terminated(
+            recognize(many0(preceded(
+                peek(not(ruby_shebang)),
+                tuple((opt(line_content), line_terminator)),
+            ))),
+            peek(ruby_shebang),
+        ),
+    )(i)
+}
+
+/// *shebang* **but** the interpreter mentions `ruby`
+fn ruby_shebang(i: Input) -> LexResult {
+    recognize(verify(shebang, |s: &Shebang| s.line.contains("ruby")))(i)
+}
+
+/// [ beginning of a line ] `#!` *line_content*?
+pub(crate) fn shebang(i: Input) -> Parsed<Shebang> {
+    if !i.beginning_of_line() {
+        return Err(nom::Err::Error((i, nom::error::ErrorKind::Space)));
+    }
+    map(preceded(lexical::tag("#!"), opt(line_content)), |line| {
+        let line = line.map(|s| s.to_string()).unwrap_or_default();
+        let flags = line
+            .split_whitespace()
+            .skip_while(|word| !word.rsplit('/').next().unwrap().contains("ruby"))
+            .skip(1)
+            .map(|word| word.to_owned())
+            .collect();
+        Shebang { line, flags }
+    })(i)
+}
+
+/// Distinguishes `BEGIN` blocks (which are hoisted onto the program) from other top-level statements
+pub(crate) enum ToplevelStatement {
+    BeginBlock(Node),
+    Statement(Node),
+}
+
+/// *toplevel_statement_list*? *separator_list*?
+fn toplevel_compound_statement(i: Input) -> Parsed<(Vec<Node>, Node)> {
+    let start = i.position();
+    let (i, (statements, _)) = statements_of(toplevel_statement)(i)?;
+    let mut begin_blocks = vec![];
+    let mut body = vec![];
+    for statement in statements {
+        match statement {
+            ToplevelStatement::BeginBlock(block) => begin_blocks.push(block),
+            ToplevelStatement::Statement(node) => body.push(node),
+        }
+    }
+    // The program spans everything up to the end of the input or `__END__`
+    let span = Span::new(start, i.position());
+    Ok((
+        i,
+        (begin_blocks, Node::with_span(NodeKind::Block(body), span)),
+    ))
+}
+
+/// ( *toplevel_statement* *separator_list* )*
+///
+/// `parse_with_recovery` parses a program's statements with this, so that the statements before one
+/// that fails to parse are kept while the rest of the program is parsed again.
+pub(crate) fn terminated_toplevel_statements(i: Input) -> Parsed<Vec<ToplevelStatement>> {
+    preceded(
+        tuple((ws0, opt(separator_list))),
+        many0(terminated(toplevel_statement, separator_list)),
     )(i)
 }
 
-/// *statement* ( *separator_list* *statement* )*
-pub(crate) fn statement_list(i: Input) -> NodeResult {
-    let (i, _) = opt(separator_list)(i)?;
-    map(separated_list0(separator_list, statement), |statements| {
-        Node::Block(statements)
+/// ( *begin_block_statement* | *statement* ) **but not** *end_of_program_marker*
+pub(crate) fn toplevel_statement(i: Input) -> Parsed<ToplevelStatement> {
+    let (i, _) = peek(not(end_of_program_marker))(i)?;
+    alt((
+        map(begin_block_statement, ToplevelStatement::BeginBlock),
+        map(statement, ToplevelStatement::Statement),
+    ))(i)
+}
+
+/// *statement_list*? *separator_list*?
+pub(crate) fn compound_statement(i: Input) -> NodeResult {
+    map(statements_of(statement), |(statements, span)| {
+        Node::with_span(NodeKind::Block(statements), span)
     })(i)
 }
 
+/// Parses a *compound_statement* whose statements are parsed by `f`
+///
+/// The statements are returned along with the span of the statement list, which excludes the
+/// separators around it.
+fn statements_of<'a, O, F>(f: F) -> impl FnMut(Input<'a>) -> Parsed<'a, (Vec<O>, Span)>
+where
+    F: FnMut(Input<'a>) -> Parsed<'a, O>,
+{
+    // *statement* ( *separator_list* *statement* )*
+    let mut statement_list = separated_list0(separator_list, f);
+    move |i: Input<'a>| {
+        let (i, _) = ws0(i)?;
+        let (i, _) = opt(separator_list)(i)?;
+        let start = i.position();
+        let (i, statements) = match statement_list(i.clone()) {
+            Ok((j, statements)) => (j, statements),
+            Err(nom::Err::Error(_)) => (i, vec![]),
+            Err(e) => return Err(e),
+        };
+        let span = Span::new(start, i.position());
+        let (i, _) = tuple((opt(separator_list), ws0))(i)?;
+        Ok((i, (statements, span)))
+    }
+}
+
 /// *separator*+
 pub(crate) fn separator_list(i: Input) -> LexResult {
     recognize(many1(separator))(i)
@@ -62,7 +205,10 @@ pub(crate) fn source_character(i: Input) -> CharResult {
 /// 0x09 | 0x0b | 0x0c | 0x0d | 0x20 | *line_terminator_escape_sequence*
 pub(crate) fn whitespace(i: Input) -> LexResult {
     alt((
-        recognize(one_of(" \t\x0b\x0c\r")),
+        token(
+            |_| TokenKind::Whitespace,
+            recognize(one_of(" \t\x0b\x0c\r")),
+        ),
         recognize(comment),
         line_terminator_escape_sequence,
     ))(i)
@@ -70,12 +216,15 @@ pub(crate) fn whitespace(i: Input) -> LexResult {
 
 /// `\r`? `\n`
 pub(crate) fn line_terminator(i: Input) -> LexResult {
-    line_ending(i)
+    token(|_| TokenKind::Newline, line_ending)(i)
 }
 
 /// `\` *line_terminator*
 pub(crate) fn line_terminator_escape_sequence(i: Input) -> LexResult {
-    recognize(tuple((char('\\'), line_terminator)))(i)
+    token(
+        |_| TokenKind::Whitespace,
+        recognize(tuple((lexical::char('\\'), line_terminator))),
+    )(i)
 }
 
 /// [ beginning of a line ] `__END__` ( *line_terminator* | [ end of a program ] )
@@ -83,10 +232,16 @@ pub(crate) fn end_of_program_marker(i: Input) -> NodeResult {
     if !i.beginning_of_line() {
         return Err(nom::Err::Error((i, nom::error::ErrorKind::Space)));
     }
-    let (i, _) = tag("__END__")(i)?;
-    let (i, _) = opt(line_terminator)(i)?;
-    let (i, data) = map(nom::combinator::rest, |s: Input| s.to_string())(i)?;
-    Ok((i, Node::EndOfProgram(data)))
+    token(
+        |_| TokenKind::EndOfProgram,
+        spanned(map(
+            preceded(
+                tuple((tag("__END__"), opt(line_terminator))),
+                nom::combinator::rest,
+            ),
+            |data: Input| Node::new(NodeKind::EndOfProgram(data.to_string())),
+        )),
+    )(i)
 }
 
 /// ( *whitespace* | *line_terminator* | *comment* )*
@@ -102,11 +257,143 @@ pub(crate) fn no_lt(i: Input) -> LexResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ast::MagicComments;
+
+    #[test]
+    fn test_program() {
+        use_parser!(program);
+        // Parse errors
+        assert_err!("BEGIN");
+        assert_err!("BEGIN do end");
+        assert_err!("if true\n BEGIN { 1 }\nend");
+        assert_err!("BEGIN { BEGIN { 1 } }");
+        // Success cases
+        assert_ok!(
+            "1\nBEGIN { 2 }\n3; BEGIN {}\n__END__\ndata",
+            Program {
+                program: Node::new(NodeKind::Block(vec![Node::int(1), Node::int(3)])),
+                begin_blocks: vec![
+                    Node::new(NodeKind::Block(vec![Node::int(2)])),
+                    Node::new(NodeKind::Block(vec![]))
+                ],
+                shebang: None,
+                magic_comments: MagicComments::default(),
+                comments: vec![],
+                encoding: Encoding::Utf8,
+                data: Some("data".to_owned()),
+            }
+        );
+        assert_ok!(
+            "END { 1 }",
+            Program {
+                program: Node::new(NodeKind::Block(vec![Node::new(NodeKind::EndBlock(
+                    Box::new(Node::new(NodeKind::Block(vec![Node::int(1)])))
+                ))])),
+                begin_blocks: vec![],
+                shebang: None,
+                magic_comments: MagicComments::default(),
+                comments: vec![],
+                encoding: Encoding::Utf8,
+                data: None,
+            }
+        );
+        assert_ok!(
+            "# frozen_string_literal: true\n\n'foo'",
+            Program {
+                program: Node::new(NodeKind::Block(vec![Node::literal_string("foo")])),
+                begin_blocks: vec![],
+                shebang: None,
+                magic_comments: MagicComments {
+                    frozen_string_literal: Some(true),
+                    ..MagicComments::default()
+                },
+                comments: vec![],
+                encoding: Encoding::Utf8,
+                data: None,
+            }
+        );
+        assert_ok!(
+            "\u{FEFF}# encoding: us-ascii\n__ENCODING__",
+            Program {
+                program: Node::new(NodeKind::Block(vec![Node::new(NodeKind::Encoding(
+                    "US-ASCII".to_owned()
+                ))])),
+                begin_blocks: vec![],
+                shebang: None,
+                magic_comments: MagicComments {
+                    encoding: Some("us-ascii".to_owned()),
+                    ..MagicComments::default()
+                },
+                comments: vec![],
+                encoding: Encoding::UsAscii,
+                data: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_program_with_leading_garbage() {
+        let mut i: Input =
+            "#!/bin/sh\nexec ruby -x \"$0\"\n#!/usr/bin/env ruby -w\n# encoding: utf-8\n__LINE__"
+                .into();
+        i.metadata.skip_leading_garbage = true;
+        let (_, parsed) = program(i).unwrap();
+        assert_eq!(
+            Node::new(NodeKind::Block(vec![Node::new(NodeKind::Line(5))])),
+            parsed.program
+        );
+        assert_eq!(Some(vec!["-w".to_owned()]), parsed.shebang.map(|s| s.flags));
+        assert_eq!(Some("utf-8".to_owned()), parsed.magic_comments.encoding);
+        // A `#!` line that mentions ruby is required
+        let mut i: Input = "#!/bin/sh\nexec ruby\n".into();
+        i.metadata.skip_leading_garbage = true;
+        assert!(program(i).is_err());
+    }
+
+    #[test]
+    fn test_shebang() {
+        use_parser!(shebang);
+        fn s(line: &str, flags: Vec<&str>) -> Shebang {
+            Shebang {
+                line: line.to_owned(),
+                flags: flags.into_iter().map(|f| f.to_owned()).collect(),
+            }
+        }
+        // Parse errors
+        assert_err!("");
+        assert_err!("# !ruby");
+        assert_err!("#!ruby\n");
+        // Success cases
+        assert_ok!("#!", s("", vec![]));
+        assert_ok!("#!/bin/sh -e", s("/bin/sh -e", vec![]));
+        assert_ok!(
+            "#!/usr/bin/env ruby -w --disable-gems",
+            s(
+                "/usr/bin/env ruby -w --disable-gems",
+                vec!["-w", "--disable-gems"]
+            )
+        );
+        assert_ok!(
+            "#!/opt/ruby1.9/bin/ruby1.9 -Ku",
+            s("/opt/ruby1.9/bin/ruby1.9 -Ku", vec!["-Ku"])
+        );
+        assert_partial!("#!ruby\nfoo", s("ruby", vec![]));
+    }
 
     #[test]
     fn test_compound_statement() {
         use_parser!(compound_statement);
-        assert_ok!("2; 5", Node::Block(vec![Node::int(2), Node::int(5)]));
+        assert_ok!(
+            "2; 5",
+            Node::new(NodeKind::Block(vec![Node::int(2), Node::int(5)]))
+        );
+        assert_ok!(
+            "foo\n  .bar\n\n  # baz\n  &.baz\n5",
+            Node::new(NodeKind::Block(vec![
+                Node::ident("foo", IdentifierKind::LocalVariable),
+                Node::int(5)
+            ]))
+        );
     }
 
     #[test]
@@ -177,12 +464,115 @@ mod tests {
         assert_err!("__end__");
         assert_err!("__END__ing");
         // Success cases
-        assert_ok!("__END__", Node::EndOfProgram("".to_owned()));
-        assert_ok!("__END__\n", Node::EndOfProgram("".to_owned()));
-        assert_ok!("__END__\r\n", Node::EndOfProgram("".to_owned()));
+        assert_ok!("__END__", Node::new(NodeKind::EndOfProgram("".to_owned())));
+        assert_ok!(
+            "__END__\n",
+            Node::new(NodeKind::EndOfProgram("".to_owned()))
+        );
+        assert_ok!(
+            "__END__\r\n",
+            Node::new(NodeKind::EndOfProgram("".to_owned()))
+        );
         assert_ok!(
             "__END__\r\n\nfoo\nbar",
-            Node::EndOfProgram("\nfoo\nbar".to_owned())
+            Node::new(NodeKind::EndOfProgram("\nfoo\nbar".to_owned()))
         );
     }
+
+    #[test]
+    fn test_spans() {
+        let span = |start: (usize, usize, usize), end: (usize, usize, usize)| {
+            Span::new(
+                Position::new(start.0, start.1, start.2),
+                Position::new(end.0, end.1, end.2),
+            )
+        };
+        let statements = |program: &Program| match &program.program.kind {
+            NodeKind::Block(statements) => statements.clone(),
+            _ => unreachable!(),
+        };
+        // Statements and their children
+        let (_, parsed) = program(Input::new("a and b\nc\n")).unwrap();
+        assert_eq!(parsed.program.span, span((0, 1, 1), (10, 3, 1)));
+        let nodes = statements(&parsed);
+        assert_eq!(nodes[0].span, span((0, 1, 1), (7, 1, 8)));
+        if let NodeKind::LogicalAnd(and) = &nodes[0].kind {
+            assert_eq!(and.first.span, span((0, 1, 1), (1, 1, 2)));
+            assert_eq!(and.second.span, span((6, 1, 7), (7, 1, 8)));
+        } else {
+            panic!("expected a logical and");
+        }
+        assert_eq!(nodes[1].span, span((8, 2, 1), (9, 2, 2)));
+        // Binary operators span both of their operands
+        let (_, parsed) = program(Input::new("1 + 2 * 3")).unwrap();
+        assert_eq!(statements(&parsed)[0].span, span((0, 1, 1), (9, 1, 10)));
+        // Statements following a heredoc start after its body
+        let (_, parsed) = program(Input::new("foo <<EOS, 1\nbody\nEOS\ny")).unwrap();
+        let nodes = statements(&parsed);
+        assert_eq!(nodes[1].span, span((22, 4, 1), (23, 4, 2)));
+        // String segments span the source between interpolations
+        let parsed = crate::parse(Input::new("\"a#{b}c\"")).unwrap();
+        let nodes = statements(&parsed)[0]
+            .children()
+            .cloned()
+            .collect::<Vec<Node>>();
+        assert_eq!(nodes[0].span, span((1, 1, 2), (2, 1, 3)));
+        assert_eq!(nodes[1].span, span((2, 1, 3), (6, 1, 7)));
+        assert_eq!(nodes[2].span, span((6, 1, 7), (7, 1, 8)));
+        // Labels and shorthand keys span their names
+        let parsed = crate::parse(Input::new("{a: 1, b:}")).unwrap();
+        let nodes = statements(&parsed)[0]
+            .children()
+            .cloned()
+            .collect::<Vec<Node>>();
+        assert_eq!(nodes[0].span, span((1, 1, 2), (2, 1, 3)));
+        assert_eq!(nodes[2].span, span((7, 1, 8), (8, 1, 9)));
+        assert_eq!(nodes[3].span, span((7, 1, 8), (8, 1, 9)));
+        // Every node that was parsed from source has a span
+        fn assert_spanned(node: &Node, source: &str) {
+            assert!(
+                !node.span.is_unset(),
+                "{:?} has no span in {:?}",
+                node,
+                source
+            );
+            node.children()
+                .for_each(|child| assert_spanned(child, source));
+        }
+        for source in [
+            "a and b",
+            "\"a#{b}c\" 'd' \"#{e}\"",
+            "`ls #{dir}`",
+            "%Q(x #@y z)",
+            "[<<~EOS, <<-'RAW']\n  text #{a}\n    more\nEOS\nraw\n  RAW\n",
+            "{a: 1, 'b': 2, \"c#{d}\": 3, e:, F:}",
+            "{:a => \"#{b}\", **c}",
+        ]
+        .iter()
+        {
+            let parsed = crate::parse(Input::new(source)).unwrap();
+            assert_spanned(&parsed.program, source);
+        }
+    }
+
+    #[test]
+    fn test_backtracking() {
+        // Alternatives that begin with the same expression must not each reparse it, as every
+        // level of nesting would multiply the work of the levels above it
+        fn runs(source: &str) -> usize {
+            let memo = std::cell::RefCell::new(crate::lexer::Memo::default());
+            let mut i = Input::new(source);
+            i.metadata.memo = Some(&memo);
+            assert!(nom::combinator::all_consuming(program)(i).is_ok());
+            let runs = memo.borrow().runs();
+            runs
+        }
+        let nested = |depth| (0..depth).fold("1".to_owned(), |s, _| format!("\"#{{{}}}\"", s));
+        let runs: Vec<usize> = (1..6).map(|depth| runs(&nested(depth))).collect();
+        // Each level of nesting adds the same amount of work
+        for w in runs.windows(3) {
+            assert_eq!(w[1] - w[0], w[2] - w[1], "{:?}", runs);
+        }
+        assert!(crate::parse(Input::new(include_str!("../../../test.rb"))).is_ok());
+    }
 }
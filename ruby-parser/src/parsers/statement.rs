@@ -1,37 +1,63 @@
 use crate::ast::{
-    Alias, Conditional, ConditionalKind, Loop, LoopKind, Rescue, RescueClause, Undef,
+    Conditional, ConditionalKind, Loop, LoopKind, MethodName, Rescue, RescueClause, Undef,
 };
 use crate::lexer::*;
 use crate::parsers::expression::assignment::assignment_statement;
 use crate::parsers::expression::expression;
 use crate::parsers::expression::method::defined_method_name;
+use crate::parsers::program::compound_statement;
+use crate::parsers::token::identifier::global_variable_identifier;
+use crate::parsers::token::literal::symbol::dynamic_symbol;
+use nom::InputTake;
 
 /// *simple_statement* | *expression_modifier_statement* | *rescue_modifier_statement*
 pub(crate) fn statement(i: Input) -> NodeResult {
-    map(
-        tuple((simple_statement, opt(recursing_statement_modifier))),
-        Node::decurse,
-    )(i)
+    recoverable(spanned(alt((
+        skipped_statement,
+        map(
+            tuple((simple_statement, opt(recursing_statement_modifier))),
+            Node::decurse,
+        ),
+    ))))(i)
+}
+
+/// Source that `parse_with_recovery` has determined to be unparsable
+fn skipped_statement(i: Input) -> NodeResult {
+    let end = i
+        .metadata
+        .failures
+        .and_then(|failures| failures.borrow().skipped_from(i.offset()));
+    match end {
+        Some(end) => {
+            // The input stops at the end of the line while a heredoc's body is pending
+            let length = (end - i.offset()).min(i.len());
+            let (j, skipped) = i.take_split(length);
+            Ok((j, Node::new(NodeKind::Error(skipped.to_string()))))
+        }
+        None => Err(nom::Err::Error((i, crate::ErrorKind::Verify))),
+    }
 }
 
-/// *expression_statement* | *alias_statement* | *undef_statement* | *assignment_statement*
+/// *expression_statement* | *alias_statement* | *undef_statement* | *assignment_statement* | *end_block_statement*
 pub(crate) fn simple_statement(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
+        nested_begin_block_statement,
         expression_statement,
         alias_statement,
         undef_statement,
         assignment_statement,
-    ))(i)
+        end_block_statement,
+    )))(i)
 }
 
 pub(crate) fn recursing_statement_modifier(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             alt((_expression_modifier_statement, _rescue_modifier_statement)),
             opt(recursing_statement_modifier),
         )),
         Node::decurse,
-    )(i)
+    ))(i)
 }
 
 /// *expression*
@@ -39,29 +65,82 @@ pub(crate) fn expression_statement(i: Input) -> NodeResult {
     expression(i)
 }
 
-/// `alias` *method_name_or_symbol* *method_name_or_symbol*
-pub(crate) fn alias_statement(i: Input) -> NodeResult {
-    map(
+/// `BEGIN` [ no ⏎ ] `{` *compound_statement* `}`
+pub(crate) fn begin_block_statement(i: Input) -> NodeResult {
+    spanned(map(
         tuple((
-            tag("alias"),
-            ws0,
-            method_name_or_symbol,
-            ws0,
-            method_name_or_symbol,
+            tag("BEGIN"),
+            no_lt,
+            char('{'),
+            compound_statement,
+            char('}'),
         )),
-        |t| Node::Alias(Alias { to: t.2, from: t.4 }),
-    )(i)
+        |t| t.3,
+    ))(i)
+}
+
+/// `END` [ no ⏎ ] `{` *compound_statement* `}`
+pub(crate) fn end_block_statement(i: Input) -> NodeResult {
+    spanned(map(
+        tuple((tag("END"), no_lt, char('{'), compound_statement, char('}'))),
+        |t| Node::new(NodeKind::EndBlock(Box::new(t.3))),
+    ))(i)
+}
+
+/// `BEGIN` blocks are only permitted at the top level of a program
+fn nested_begin_block_statement(i: Input) -> NodeResult {
+    peek(tag("BEGIN"))(i.clone())?;
+    if let Some(failures) = i.metadata.failures {
+        failures
+            .borrow_mut()
+            .reject(i.position(), "`BEGIN` is permitted only at the top level");
+    }
+    Err(nom::Err::Failure((i, crate::ErrorKind::Verify)))
+}
+
+/// `alias` *method_name_or_symbol* *method_name_or_symbol* | `alias` *global_variable_name* *global_variable_name*
+pub(crate) fn alias_statement(i: Input) -> NodeResult {
+    spanned(alt((
+        map(
+            tuple((
+                tag("alias"),
+                ws0,
+                method_name_or_symbol,
+                ws0,
+                method_name_or_symbol,
+            )),
+            |t| Node::alias(t.2, t.4),
+        ),
+        map(
+            tuple((
+                tag("alias"),
+                ws0,
+                global_variable_name,
+                ws0,
+                global_variable_name,
+            )),
+            |t| Node::global_alias(t.2, t.4),
+        ),
+    )))(i)
+}
+
+/// *global_variable_identifier*
+pub(crate) fn global_variable_name(i: Input) -> IdentifierResult {
+    // Numbered match references cannot be aliased to or from
+    verify(global_variable_identifier, |ident| {
+        ident.kind != IdentifierKind::NthReference
+    })(i)
 }
 
 /// `undef` *undef_list*
 pub(crate) fn undef_statement(i: Input) -> NodeResult {
-    map(tuple((tag("undef"), ws0, undef_list)), |t| {
-        Node::Undef(Undef { list: t.2 })
-    })(i)
+    spanned(map(tuple((tag("undef"), ws0, undef_list)), |t| {
+        Node::new(NodeKind::Undef(Undef { list: t.2 }))
+    }))(i)
 }
 
 /// *method_name_or_symbol* ( [ no ⏎ ] `,` *method_name_or_symbol* )*
-pub(crate) fn undef_list(i: Input) -> Parsed<Vec<String>> {
+pub(crate) fn undef_list(i: Input) -> Parsed<Vec<MethodName>> {
     map(
         tuple((
             method_name_or_symbol,
@@ -79,13 +158,21 @@ pub(crate) fn undef_list(i: Input) -> Parsed<Vec<String>> {
 }
 
 /// *defined_method_name* | *symbol*
-pub(crate) fn method_name_or_symbol(i: Input) -> StringResult {
-    preceded(opt(char(':')), defined_method_name)(i)
+pub(crate) fn method_name_or_symbol(i: Input) -> Parsed<MethodName> {
+    alt((
+        map(defined_method_name, MethodName::Bare),
+        map(preceded(char(':'), defined_method_name), MethodName::Symbol),
+        map(dynamic_symbol, |node| match node.kind {
+            NodeKind::Literal(Literal::Symbol(s)) => MethodName::Symbol(s),
+            NodeKind::Interpolated(Interpolated::Symbol(vec)) => MethodName::DynamicSymbol(vec),
+            _ => unreachable!(),
+        }),
+    ))(i)
 }
 
 /// *statement* [ no ⏎ ] ( `if` | `unless` | `while` | `until` ) *expression*
 pub(crate) fn _expression_modifier_statement(i: Input) -> NodeResult {
-    map(
+    spanned(map(
         tuple((
             no_lt,
             alt((tag("if"), tag("unless"), tag("while"), tag("until"))),
@@ -93,54 +180,65 @@ pub(crate) fn _expression_modifier_statement(i: Input) -> NodeResult {
             expression,
         )),
         |(_, kind, _, expr)| match *kind {
-            "if" => Node::Conditional(Conditional {
+            "if" => Node::new(NodeKind::Conditional(Conditional {
                 kind: ConditionalKind::ModifyingIf,
                 cond: Box::new(expr),
-                then: Box::new(Node::Placeholder),
-                otherwise: Box::new(Node::None),
-            }),
-            "unless" => Node::Conditional(Conditional {
+                then: Box::new(Node::new(NodeKind::Placeholder)),
+                otherwise: Box::new(Node::new(NodeKind::None)),
+            })),
+            "unless" => Node::new(NodeKind::Conditional(Conditional {
                 kind: ConditionalKind::ModifyingUnless,
                 cond: Box::new(expr),
-                then: Box::new(Node::Placeholder),
-                otherwise: Box::new(Node::None),
-            }),
-            "while" => Node::Loop(Loop {
+                then: Box::new(Node::new(NodeKind::Placeholder)),
+                otherwise: Box::new(Node::new(NodeKind::None)),
+            })),
+            "while" => Node::new(NodeKind::Loop(Loop {
                 kind: LoopKind::ModifyingWhile,
                 cond: Box::new(expr),
-                body: Box::new(Node::Placeholder),
+                body: Box::new(Node::new(NodeKind::Placeholder)),
                 bindings: None,
-            }),
-            "until" => Node::Loop(Loop {
+            })),
+            "until" => Node::new(NodeKind::Loop(Loop {
                 kind: LoopKind::ModifyingUntil,
                 cond: Box::new(expr),
-                body: Box::new(Node::Placeholder),
+                body: Box::new(Node::new(NodeKind::Placeholder)),
                 bindings: None,
-            }),
+            })),
             _ => unreachable!(),
         },
-    )(i)
+    ))(i)
 }
 
 /// *statement* [ no ⏎ ] `rescue` *fallback_statement*
 pub(crate) fn _rescue_modifier_statement(i: Input) -> NodeResult {
-    map(tuple((no_lt, tag("rescue"), ws0, simple_statement)), |t| {
-        Node::Rescue(Rescue {
-            body: Box::new(Node::Placeholder),
-            rescue: vec![RescueClause {
-                exceptions: vec![],
-                assigned_to: Box::new(Node::None),
-                then: Box::new(t.3),
-            }],
-            otherwise: Box::new(Node::None),
-        })
-    })(i)
+    spanned(map(
+        tuple((no_lt, tag("rescue"), ws0, simple_statement)),
+        |t| {
+            Node::new(NodeKind::Rescue(Rescue {
+                body: Box::new(Node::new(NodeKind::Placeholder)),
+                rescue: vec![RescueClause {
+                    exceptions: vec![],
+                    assigned_to: Box::new(Node::new(NodeKind::None)),
+                    then: Box::new(t.3),
+                }],
+                otherwise: Box::new(Node::new(NodeKind::None)),
+            }))
+        },
+    ))(i)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn bare(name: &str) -> MethodName {
+        MethodName::Bare(name.to_owned())
+    }
+
+    fn sym(name: &str) -> MethodName {
+        MethodName::Symbol(name.to_owned())
+    }
+
     #[test]
     fn test_alias_statement() {
         use_parser!(alias_statement);
@@ -149,18 +247,70 @@ mod tests {
         assert_err!("alias foo");
         assert_err!("alias foo?? bar");
         assert_err!("alias foo? :bar?!");
+        assert_err!("alias $foo bar");
+        assert_err!("alias foo $bar");
+        assert_err!("alias :foo $bar");
+        assert_err!("alias $foo $1");
+        assert_err!("alias $1 $foo");
         // Success cases
+        assert_ok!("alias foo? BAR", Node::alias(bare("foo?"), bare("BAR")));
+        assert_ok!("alias\n\nfoo\t:bar!", Node::alias(bare("foo"), sym("bar!")));
         assert_ok!(
-            "alias foo? BAR",
-            Node::alias("foo?".to_owned(), "BAR".to_owned(),)
+            "alias :sym func_name!",
+            Node::alias(sym("sym"), bare("func_name!"))
         );
         assert_ok!(
-            "alias\n\nfoo\t:bar!",
-            Node::alias("foo".to_owned(), "bar!".to_owned(),)
+            "alias :'foo bar' :\"baz\"",
+            Node::alias(sym("foo bar"), sym("baz"))
         );
         assert_ok!(
-            "alias :sym func_name!",
-            Node::alias("sym".to_owned(), "func_name!".to_owned(),)
+            "alias :\"foo#{x}\" bar",
+            Node::alias(
+                MethodName::DynamicSymbol(vec![
+                    Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
+                    Node::new(NodeKind::Block(vec![Node::ident(
+                        "x",
+                        IdentifierKind::LocalVariable
+                    )]))
+                ]),
+                bare("bar")
+            )
+        );
+        assert_ok!(
+            "alias $LOAD $:",
+            Node::global_alias(
+                Identifier::new("$LOAD".to_owned(), IdentifierKind::GlobalVariable),
+                Identifier::new("$:".to_owned(), IdentifierKind::SpecialVariable)
+            )
+        );
+        assert_ok!(
+            "alias $MATCH $&",
+            Node::global_alias(
+                Identifier::new("$MATCH".to_owned(), IdentifierKind::GlobalVariable),
+                Identifier::new("$&".to_owned(), IdentifierKind::BackReference)
+            )
+        );
+    }
+
+    #[test]
+    fn test_end_block_statement() {
+        use_parser!(end_block_statement);
+        // Parse errors
+        assert_err!("END");
+        assert_err!("END do 1 end");
+        assert_err!("END\n{ 1 }");
+        // Success cases
+        assert_ok!(
+            "END {}",
+            Node::new(NodeKind::EndBlock(Box::new(Node::new(NodeKind::Block(
+                vec![]
+            )))))
+        );
+        assert_ok!(
+            "END { 1; 2 }",
+            Node::new(NodeKind::EndBlock(Box::new(Node::new(NodeKind::Block(
+                vec![Node::int(1), Node::int(2)]
+            )))))
         );
     }
 
@@ -172,11 +322,22 @@ mod tests {
         assert_err!("undef");
         assert_err!("undef foo?? bar");
         assert_err!("undef foo? :bar?!");
+        assert_err!("undef $foo");
         // Success cases
-        assert_ok!("undef foo?", Node::undef(vec!["foo?".to_owned()]));
+        assert_ok!("undef foo?", Node::undef(vec![bare("foo?")]));
         assert_ok!(
             "undef \n:bar   , BAZ\t,\n foo!",
-            Node::undef(vec!["bar".to_owned(), "BAZ".to_owned(), "foo!".to_owned()])
+            Node::undef(vec![sym("bar"), bare("BAZ"), bare("foo!")])
+        );
+        assert_ok!(
+            "undef :\"#{x}\", :\"y\"",
+            Node::undef(vec![
+                MethodName::DynamicSymbol(vec![Node::new(NodeKind::Block(vec![Node::ident(
+                    "x",
+                    IdentifierKind::LocalVariable
+                )]))]),
+                sym("y")
+            ])
         );
     }
 
@@ -186,6 +347,13 @@ mod tests {
         // Parse errors
         assert_err!("");
         assert_err!("2 if");
+        assert_eq!(
+            statement("BEGIN { 1 }".into()),
+            Err(nom::Err::Failure((
+                "BEGIN { 1 }".into(),
+                crate::ErrorKind::Verify
+            )))
+        );
         // Success cases
         assert_ok!(
             "2 if true",
@@ -193,7 +361,7 @@ mod tests {
                 ConditionalKind::ModifyingIf,
                 Node::boolean(true),
                 Node::int(2),
-                Node::None
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -205,9 +373,9 @@ mod tests {
                     ConditionalKind::ModifyingIf,
                     Node::boolean(true),
                     Node::int(2),
-                    Node::None
+                    Node::new(NodeKind::None)
                 ),
-                Node::None
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -231,8 +399,8 @@ mod tests {
                 Node::conditional(
                     ConditionalKind::ModifyingIf,
                     Node::boolean(true),
-                    Node::undef(vec!["hi".to_owned()]),
-                    Node::None
+                    Node::undef(vec![sym("hi")]),
+                    Node::new(NodeKind::None)
                 ),
                 Node::int(3)
             )
@@ -242,8 +410,8 @@ mod tests {
             Node::conditional(
                 ConditionalKind::ModifyingIf,
                 Node::boolean(false),
-                Node::rescued_statement(Node::undef(vec!["hi".to_owned()]), Node::int(3)),
-                Node::None
+                Node::rescued_statement(Node::undef(vec![sym("hi")]), Node::int(3)),
+                Node::new(NodeKind::None)
             )
         );
         assert_ok!(
@@ -261,13 +429,47 @@ mod tests {
                             ConditionalKind::ModifyingIf,
                             Node::int(2),
                             Node::int(1),
-                            Node::None
+                            Node::new(NodeKind::None)
                         ),
-                        Node::None
+                        Node::new(NodeKind::None)
                     ),
                     vec![]
                 ),
-                Node::None
+                Node::new(NodeKind::None)
+            )
+        );
+        assert_ok!(
+            "begin; end while 1",
+            Node::loop_(
+                LoopKind::PostConditionWhile,
+                Node::int(1),
+                Node::new(NodeKind::Begin(Box::new(Node::new(NodeKind::Placeholder)))),
+                vec![]
+            )
+        );
+        assert_ok!(
+            "begin\n2\nend until 1 while 3",
+            Node::loop_(
+                LoopKind::ModifyingWhile,
+                Node::int(3),
+                Node::loop_(
+                    LoopKind::PostConditionUntil,
+                    Node::int(1),
+                    Node::new(NodeKind::Begin(Box::new(Node::new(NodeKind::Placeholder)))),
+                    vec![]
+                ),
+                vec![]
+            )
+        );
+        assert_ok!(
+            "(begin; end) while 1",
+            Node::loop_(
+                LoopKind::ModifyingWhile,
+                Node::int(1),
+                Node::new(NodeKind::Block(vec![Node::new(NodeKind::Begin(Box::new(
+                    Node::new(NodeKind::Placeholder)
+                )))])),
+                vec![]
             )
         );
     }
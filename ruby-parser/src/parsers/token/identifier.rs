@@ -1,5 +1,7 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::token::keyword::keyword;
+use crate::parsers::token::literal::numeric::{decimal_digit, decimal_digit_except_zero};
 
 /// *local_variable_identifier* | *global_variable_identifier* | *class_variable_identifier* | *instance_variable_identifier* | *constant_identifier* | *method_only_identifier* | *assignment_like_method_identifier*
 pub(crate) fn identifier(i: Input) -> IdentifierResult {
@@ -18,7 +20,7 @@ pub(crate) fn identifier(i: Input) -> IdentifierResult {
 /// ( *lowercase_character* | `_` ) *identifier_character**
 pub(crate) fn local_variable_identifier(i: Input) -> IdentifierResult {
     use crate::nom::InputLength;
-    map(
+    identifier_token(map(
         verify(
             recognize(tuple((
                 alt((lowercase_character, char('_'))),
@@ -31,49 +33,88 @@ pub(crate) fn local_variable_identifier(i: Input) -> IdentifierResult {
             },
         ),
         |s| Identifier::new(s.to_string(), IdentifierKind::LocalVariable),
-    )(i)
+    ))(i)
 }
 
-/// `$` *identifier_start_character* *identifier_character**
+/// *special_variable_identifier* | *nth_reference_identifier* | *back_reference_identifier* | `$` *identifier_start_character* *identifier_character**
 pub(crate) fn global_variable_identifier(i: Input) -> IdentifierResult {
+    identifier_token(alt((
+        special_variable_identifier,
+        nth_reference_identifier,
+        back_reference_identifier,
+        map(
+            recognize(tuple((
+                char('$'),
+                identifier_start_character,
+                many0(identifier_character),
+            ))),
+            |s| Identifier::new(s.to_string(), IdentifierKind::GlobalVariable),
+        ),
+    )))(i)
+}
+
+/// `$` ( `~` | `*` | `$` | `?` | `!` | `@` | `/` | `\` | `;` | `,` | `.` | `=` | `:` | `<` | `>` | `"` | `0` ) | `$_` | `$-` *identifier_character*
+pub(crate) fn special_variable_identifier(i: Input) -> IdentifierResult {
+    map(
+        recognize(preceded(
+            char('$'),
+            alt((
+                recognize(one_of("~*$?!@/\\;,.=:<>\"0")),
+                terminated(tag("_"), not(identifier_character)),
+                recognize(tuple((char('-'), identifier_character))),
+            )),
+        )),
+        |s| Identifier::new(s.to_string(), IdentifierKind::SpecialVariable),
+    )(i)
+}
+
+/// `$` *decimal_digit_except_zero* *decimal_digit**
+pub(crate) fn nth_reference_identifier(i: Input) -> IdentifierResult {
     map(
         recognize(tuple((
             char('$'),
-            identifier_start_character,
-            many0(identifier_character),
+            decimal_digit_except_zero,
+            many0(decimal_digit),
         ))),
-        |s| Identifier::new(s.to_string(), IdentifierKind::GlobalVariable),
+        |s| Identifier::new(s.to_string(), IdentifierKind::NthReference),
     )(i)
 }
 
+/// `$` ( `&` | `` ` `` | `'` | `+` )
+pub(crate) fn back_reference_identifier(i: Input) -> IdentifierResult {
+    map(recognize(tuple((char('$'), one_of("&`'+")))), |s: Input| {
+        Identifier::new(s.to_string(), IdentifierKind::BackReference)
+    })(i)
+}
+
 /// `@@` *identifier_start_character* *identifier_character**
 pub(crate) fn class_variable_identifier(i: Input) -> IdentifierResult {
-    map(
+    identifier_token(map(
         recognize(tuple((
             tag("@@"),
             identifier_start_character,
             many0(identifier_character),
         ))),
         |s| Identifier::new(s.to_string(), IdentifierKind::ClassVariable),
-    )(i)
+    ))(i)
 }
 
 /// `@` *identifier_start_character* *identifier_character**
 pub(crate) fn instance_variable_identifier(i: Input) -> IdentifierResult {
-    map(
+    identifier_token(map(
         recognize(tuple((
             char('@'),
             identifier_start_character,
             many0(identifier_character),
         ))),
         |s| Identifier::new(s.to_string(), IdentifierKind::InstanceVariable),
-    )(i)
+    ))(i)
 }
 
 /// *uppercase_character* *identifier_character**
 pub(crate) fn constant_identifier(i: Input) -> IdentifierResult {
     use crate::nom::InputLength;
-    map(
+    identifier_token(map(
         verify(
             recognize(tuple((uppercase_character, many0(identifier_character)))),
             // Ensure that we didn't match a known keyword
@@ -83,7 +124,7 @@ pub(crate) fn constant_identifier(i: Input) -> IdentifierResult {
             },
         ),
         |s| Identifier::new(s.to_string(), IdentifierKind::Constant),
-    )(i)
+    ))(i)
 }
 
 /// Returns any UTF-8 upper case character
@@ -102,24 +143,35 @@ fn lowercase_character(i: Input) -> CharResult {
 
 /// ( *constant_identifier* | *local_variable_identifier* ) ( `!` | `?` )
 pub(crate) fn method_only_identifier(i: Input) -> IdentifierResult {
-    map(
+    identifier_token(map(
         recognize(tuple((
             alt((constant_identifier, local_variable_identifier)),
             one_of("!?"),
         ))),
         |s| Identifier::new(s.to_string(), IdentifierKind::Method),
-    )(i)
+    ))(i)
 }
 
 /// ( *constant_identifier* | *local_variable_identifier* ) `=`
 pub(crate) fn assignment_like_method_identifier(i: Input) -> IdentifierResult {
-    map(
+    identifier_token(map(
         recognize(tuple((
             alt((constant_identifier, local_variable_identifier)),
             char('='),
         ))),
         |s| Identifier::new(s.to_string(), IdentifierKind::AssignmentMethod),
-    )(i)
+    ))(i)
+}
+
+/// Records an identifier as a token of its kind
+fn identifier_token<'a, F>(f: F) -> impl FnMut(Input<'a>) -> IdentifierResult<'a>
+where
+    F: FnMut(Input<'a>) -> IdentifierResult<'a>,
+{
+    token(
+        |identifier: &Identifier| TokenKind::Identifier(identifier.kind.clone()),
+        f,
+    )
 }
 
 /// *lowercase_character* | *uppercase_character* | *decimal_digit* | `_`
@@ -242,18 +294,43 @@ mod tests {
 
     #[test]
     fn test_global_variable_identifier() {
+        use crate::ast::IdentifierKind::*;
         use_parser!(global_variable_identifier);
         // Parse errors
         assert_err!("var");
-        assert_err!("$$var");
-        assert_err!("$$");
-        assert_err!("$?");
-        assert_err!("$!");
+        assert_err!("$");
+        assert_err!("$-");
+        assert_err!("$%");
+        assert_err!("$^");
+        assert_remaining!("$$var", "var");
+        assert_remaining!("$0x", "x");
+        assert_remaining!("$-ww", "w");
+        assert_remaining!("$&&", "&");
+        assert_remaining!("$1a", "a");
         // Success cases
-        assert_ok!("$_");
-        assert_ok!("$var");
-        assert_ok!("$VAR_");
-        assert_ok!("$東");
+        assert_ok!("$var", i("$var", GlobalVariable));
+        assert_ok!("$VAR_", i("$VAR_", GlobalVariable));
+        assert_ok!("$東", i("$東", GlobalVariable));
+        assert_ok!("$stdout", i("$stdout", GlobalVariable));
+        assert_ok!("$_foo", i("$_foo", GlobalVariable));
+        assert_ok!("$_", i("$_", SpecialVariable));
+        assert_ok!("$~", i("$~", SpecialVariable));
+        assert_ok!("$!", i("$!", SpecialVariable));
+        assert_ok!("$?", i("$?", SpecialVariable));
+        assert_ok!("$$", i("$$", SpecialVariable));
+        assert_ok!("$:", i("$:", SpecialVariable));
+        assert_ok!("$;", i("$;", SpecialVariable));
+        assert_ok!("$\\", i("$\\", SpecialVariable));
+        assert_ok!("$\"", i("$\"", SpecialVariable));
+        assert_ok!("$0", i("$0", SpecialVariable));
+        assert_ok!("$-w", i("$-w", SpecialVariable));
+        assert_ok!("$-0", i("$-0", SpecialVariable));
+        assert_ok!("$1", i("$1", NthReference));
+        assert_ok!("$10", i("$10", NthReference));
+        assert_ok!("$&", i("$&", BackReference));
+        assert_ok!("$`", i("$`", BackReference));
+        assert_ok!("$'", i("$'", BackReference));
+        assert_ok!("$+", i("$+", BackReference));
     }
 
     #[test]
@@ -14,11 +14,11 @@ pub(crate) use symbol::symbol;
 
 /// *numeric_literal* | *string_literal* | *array_literal* | *regular_expression_literal* | *symbol*
 pub(crate) fn literal(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         numeric_literal,
         string_literal,
         array_literal,
         regular_expression_literal,
         symbol,
-    ))(i)
+    )))(i)
 }
@@ -1,3 +1,4 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::{line_terminator, whitespace};
 use crate::parsers::token::literal::string::double::double_escape_sequence;
@@ -10,39 +11,47 @@ use crate::parsers::token::literal::string::quoted::wrap_quote_delimiter;
 
 /// *quoted_non_expanded_array_constructor* | *quoted_expanded_array_constructor*
 pub(crate) fn array_literal(i: Input) -> NodeResult {
-    alt((
-        map(quoted_non_expanded_array_constructor, |vec| {
-            Node::Array(
-                vec.into_iter()
-                    .map(|s| Node::Literal(Literal::String(s)))
-                    .collect(),
-            )
-        }),
-        map(quoted_expanded_array_constructor, |vec| {
-            Node::Array(
-                vec.into_iter()
-                    .map(|v| match v {
-                        Interpolatable::String(s) => Node::Literal(Literal::String(s)),
-                        Interpolatable::Interpolated(vec) => {
-                            Node::Interpolated(Interpolated::String(vec))
-                        }
-                    })
-                    .collect(),
-            )
-        }),
-    ))(i)
+    token(
+        |_| TokenKind::Literal(LiteralKind::Array),
+        spanned(alt((
+            map(quoted_non_expanded_array_constructor, |vec| {
+                Node::new(NodeKind::Array(
+                    vec.into_iter()
+                        .map(|s| Node::new(NodeKind::Literal(Literal::String(s))))
+                        .collect(),
+                ))
+            }),
+            map(quoted_expanded_array_constructor, |vec| {
+                Node::new(NodeKind::Array(
+                    vec.into_iter()
+                        .map(|v| match v {
+                            Interpolatable::String(s) => {
+                                Node::new(NodeKind::Literal(Literal::String(s)))
+                            }
+                            Interpolatable::Bytes(b) => {
+                                Node::new(NodeKind::Literal(Literal::Bytes(b)))
+                            }
+                            Interpolatable::Interpolated(vec) => {
+                                Node::new(NodeKind::Interpolated(Interpolated::String(vec)))
+                            }
+                        })
+                        .collect(),
+                ))
+            }),
+        ))),
+    )(i)
 }
 
 /// `%w` *literal_beginning_delimiter* *non_expanded_array_content* *literal_ending_delimiter*
 pub(crate) fn quoted_non_expanded_array_constructor(i: Input) -> Parsed<Vec<String>> {
-    preceded(
+    percent_construct(preceded(
         tag("%W"),
         wrap_quote_delimiter(delimited(
             literal_beginning_delimiter,
             non_expanded_array_content,
             literal_ending_delimiter,
         )),
-    )(i)
+    ))(i)
 }
 
 /// *quoted_array_item_separator_list*? *non_expanded_array_item_list*? *quoted_array_item_separator_list*?
@@ -117,14 +126,14 @@ pub(crate) fn non_expanded_array_escape_sequence(i: Input) -> StringResult {
 
 /// `%W` *literal_beginning_delimiter* *expanded_array_content* *literal_ending_delimiter*
 pub(crate) fn quoted_expanded_array_constructor(i: Input) -> Parsed<Vec<Interpolatable>> {
-    preceded(
+    percent_construct(preceded(
         tag("%W"),
         wrap_quote_delimiter(delimited(
             literal_beginning_delimiter,
             expanded_array_content,
             literal_ending_delimiter,
         )),
-    )(i)
+    ))(i)
 }
 
 /// *quoted_array_item_separator_list*? *expanded_array_item_list*? *quoted_array_item_separator_list*?
@@ -158,16 +167,16 @@ pub(crate) fn expanded_array_item_list(i: Input) -> Parsed<Vec<Interpolatable>>
 
 /// *expanded_array_item_character*+
 pub(crate) fn expanded_array_item(i: Input) -> InterpolatableResult {
-    map(many1(expanded_array_item_character), |contents| {
+    interpolatable(map(many1(expanded_array_item_character), |contents| {
         Interpolatable::from(contents)
-    })(i)
+    }))(i)
 }
 
 /// *non_escaped_array_item_character* | `#` **not** ( `$` | `@` | `{` ) | *expanded_array_escape_sequence* | *interpolated_character_sequence*
 pub(crate) fn expanded_array_item_character(i: Input) -> SegmentResult {
     alt((
         map(non_escaped_array_item_character, |c| Segment::Char(c)),
-        map(expanded_array_escape_sequence, |s| Segment::String(s)),
+        expanded_array_escape_sequence,
         map(interpolated_character_sequence, |e| Segment::expr(e)),
         map(char('#'), |c| Segment::Char(c)),
     ))(i)
@@ -180,11 +189,11 @@ pub(crate) fn non_escaped_array_item_character(i: Input) -> CharResult {
 }
 
 /// *double_escape_sequence* | `\` *quoted_array_item_separator*
-pub(crate) fn expanded_array_escape_sequence(i: Input) -> StringResult {
+pub(crate) fn expanded_array_escape_sequence(i: Input) -> SegmentResult {
     alt((
         double_escape_sequence,
         map(preceded(char('\\'), quoted_array_item_separator), |s| {
-            s.into()
+            Segment::String(s.into())
         }),
     ))(i)
 }
@@ -1,3 +1,4 @@
+use crate::lexer::lexical::char;
 use crate::lexer::*;
 
 /**
@@ -13,17 +14,17 @@ tuple of the remaining input and a `Node::Integer` or `Node::Float` value when s
 
 ```
 use ruby_parser::parse;
-use ruby_parser::lexer::Node;
+use ruby_parser::lexer::{Node, NodeKind};
 use ruby_parser::ast::Literal;
 
 let input = "12_345";
-let (remaining, parsed) = parse(input.into()).unwrap();
-assert_eq!("", *remaining);
-assert_eq!(Node::Block(vec![Node::Literal(Literal::Integer(12345))]), parsed.program);
+let parsed = parse(input.into()).unwrap();
+assert_eq!(Node::new(NodeKind::Block(vec![Node::new(NodeKind::Literal(Literal::Integer(12345)))])), parsed.program);
+assert_eq!(6, parsed.program.span.end.offset);
 
 let input = "-12.34e+4";
-let (remaining, parsed) = parse(input.into()).unwrap();
-assert_eq!(Node::Block(vec![Node::Literal(Literal::Float(-123400.0))]), parsed.program);
+let parsed = parse(input.into()).unwrap();
+assert_eq!(Node::new(NodeKind::Block(vec![Node::new(NodeKind::Literal(Literal::Float(-123400.0)))])), parsed.program);
 ```
 
 ## ISO Spec
@@ -33,9 +34,15 @@ assert_eq!(Node::Block(vec![Node::Literal(Literal::Float(-123400.0))]), parsed.p
 */
 pub(crate) fn numeric_literal(i: Input) -> NodeResult {
     // Ordered to match the largest production first
-    let (i, num) = alt((signed_number, unsigned_number))(i)?;
-    let token = Node::Literal(num);
-    Ok((i, token))
+    token(
+        |node: &Node| match node.kind {
+            NodeKind::Literal(Literal::Float(_)) => TokenKind::Literal(LiteralKind::Float),
+            _ => TokenKind::Literal(LiteralKind::Integer),
+        },
+        spanned(map(alt((signed_number, unsigned_number)), |num| {
+            Node::new(NodeKind::Literal(num))
+        })),
+    )(i)
 }
 
 /// ( `+` | `-` ) *unsigned_number*
@@ -45,6 +52,8 @@ pub(crate) fn signed_number(i: Input) -> LiteralResult {
     if sign == Some('-') {
         let token = match token {
             Literal::Integer(v) => Literal::Integer(v * -1),
+            // The magnitude of `isize::MIN` is beyond the range of an `isize`
+            Literal::BigInteger(v) => integer(&format!("-{}", v), 10),
             Literal::Float(v) => Literal::Float(v * -1f64),
             v => v,
         };
@@ -78,7 +87,7 @@ pub(crate) fn decimal_integer_literal(i: Input) -> LiteralResult {
             prefixed_decimal_integer_literal,
             unprefixed_decimal_integer_literal,
         )),
-        |s| Literal::Integer(isize::from_str_radix(&s, 10).unwrap()),
+        |s| integer(&s, 10),
     )(i)
 }
 
@@ -109,30 +118,21 @@ pub(crate) fn digit_decimal_part(i: Input) -> StringResult {
 pub(crate) fn binary_integer_literal(i: Input) -> LiteralResult {
     let (i, digit) = preceded(char('0'), preceded(one_of("bB"), binary_digit))(i)?;
     let (i, rest) = many0(preceded(opt(char('_')), binary_digit))(i)?;
-    Ok((
-        i,
-        Literal::Integer(isize::from_str_radix(&concat(digit, rest), 2).unwrap()),
-    ))
+    Ok((i, integer(&concat(digit, rest), 2)))
 }
 
 /// `0` ( `_` | `o` | `O` )? *octal_digit* ( `_`? *octal_digit* )*
 pub(crate) fn octal_integer_literal(i: Input) -> LiteralResult {
     let (i, digit) = preceded(char('0'), preceded(opt(one_of("_oO")), octal_digit))(i)?;
     let (i, rest) = many0(preceded(opt(char('_')), octal_digit))(i)?;
-    Ok((
-        i,
-        Literal::Integer(isize::from_str_radix(&concat(digit, rest), 8).unwrap()),
-    ))
+    Ok((i, integer(&concat(digit, rest), 8)))
 }
 
 /// `0` ( `x` | `X` ) *hexadecimal_digit* ( `_`? *hexadecimal_digit* )*
 pub(crate) fn hexadecimal_integer_literal(i: Input) -> LiteralResult {
     let (i, digit) = preceded(char('0'), preceded(one_of("xX"), hexadecimal_digit))(i)?;
     let (i, rest) = many0(preceded(opt(char('_')), hexadecimal_digit))(i)?;
-    Ok((
-        i,
-        Literal::Integer(isize::from_str_radix(&concat(digit, rest), 16).unwrap()),
-    ))
+    Ok((i, integer(&concat(digit, rest), 16)))
 }
 
 /// *float_literal_with_exponent* | *float_literal_without_exponent*
@@ -213,6 +213,36 @@ pub(crate) fn decimal_digit(i: Input) -> CharResult {
     verify(anychar, |c: &char| c.is_ascii_digit())(i)
 }
 
+/// Constructs an integer from its digits in the given radix (and an optional leading `-`)
+fn integer(digits: &str, radix: u32) -> Literal {
+    if let Ok(v) = isize::from_str_radix(digits, radix) {
+        return Literal::Integer(v);
+    }
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
+    // Little-endian limbs of nine decimal digits each
+    let mut limbs: Vec<u64> = vec![0];
+    for digit in digits.chars().filter_map(|c| c.to_digit(radix)) {
+        let mut carry = digit as u64;
+        for limb in limbs.iter_mut() {
+            let v = *limb * radix as u64 + carry;
+            *limb = v % 1_000_000_000;
+            carry = v / 1_000_000_000;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut string = sign.to_owned();
+    string.push_str(&limbs.last().unwrap().to_string());
+    for limb in limbs.iter().rev().skip(1) {
+        string.push_str(&format!("{:09}", limb));
+    }
+    Literal::BigInteger(string)
+}
+
 /// Constructs a string from characters
 fn concat(chr: char, rest: Vec<char>) -> String {
     let mut string = String::with_capacity(chr.len_utf8() + rest.len());
@@ -238,6 +268,10 @@ mod tests {
         assert_ok!("0b0110", Literal::Integer(6));
         assert_ok!("0B0000_1111", Literal::Integer(15));
         assert_ok!("0b1111111101", Literal::Integer(1021));
+        assert_ok!(
+            "0b1_0000000000_0000000000_0000000000_0000000000_0000000000_0000000000_000",
+            Literal::BigInteger("9223372036854775808".to_owned())
+        );
         // Non-exhaustive cases
         assert_err!("0b1111 foobar");
         assert_err!("0b1251");
@@ -259,6 +293,10 @@ mod tests {
         assert_ok!("01234", Literal::Integer(668));
         assert_ok!("0_755", Literal::Integer(493));
         assert_ok!("0_00_10", Literal::Integer(8));
+        assert_ok!(
+            "01_000_000_000_000_000_000_000",
+            Literal::BigInteger("9223372036854775808".to_owned())
+        );
         // Non-exhaustive cases
         assert_err!(
             "0_1__0",
@@ -286,6 +324,10 @@ mod tests {
         assert_ok!("0XF", Literal::Integer(15));
         assert_ok!("0xAB_CD_EF", Literal::Integer(11259375));
         assert_ok!("0x10", Literal::Integer(16));
+        assert_ok!(
+            "0x7fffffffffffffffff",
+            Literal::BigInteger("2361183241434822606847".to_owned())
+        );
         // Non-exhaustive cases
         assert_err!("0x14 ");
         assert_err!("0xAC foobar");
@@ -304,6 +346,15 @@ mod tests {
         assert_ok!("0D52", Literal::Integer(52));
         assert_ok!("5_923_032", Literal::Integer(5923032));
         assert_ok!("0d12_000", Literal::Integer(12000));
+        assert_ok!("9223372036854775807", Literal::Integer(isize::MAX));
+        assert_ok!(
+            "99999999999999999999999",
+            Literal::BigInteger("99999999999999999999999".to_owned())
+        );
+        assert_ok!(
+            "1_000000000_000000000_000000000",
+            Literal::BigInteger("1000000000000000000000000000".to_owned())
+        );
         // Non-exhaustive cases
         assert_err!("42_");
         assert_err!("0b0");
@@ -344,6 +395,16 @@ mod tests {
         assert_ok!("-0x0000_0000F", Literal::Integer(-15));
         assert_ok!("-0d20", Literal::Integer(-20));
         assert_ok!("-0755", Literal::Integer(-493));
+        // Beyond the range of an isize
+        assert_ok!("-9223372036854775808", Literal::Integer(isize::MIN));
+        assert_ok!(
+            "-9223372036854775809",
+            Literal::BigInteger("-9223372036854775809".to_owned())
+        );
+        assert_ok!(
+            "-0x10000000000000000",
+            Literal::BigInteger("-18446744073709551616".to_owned())
+        );
         // Floats
         assert_ok!("0.0", Literal::Float(0.0));
         assert_ok!("+0.0", Literal::Float(0.0));
@@ -1,3 +1,4 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::line_terminator_escape_sequence;
 use crate::parsers::token::literal::string::double::interpolated_character_sequence;
@@ -7,34 +8,41 @@ use crate::parsers::token::literal::string::quoted::literal_ending_delimiter;
 
 /// `/` *regular_expression_body* `/` *regular_expression_option** | `%r` *literal_beginning_delimiter* *expanded_literal_string** *literal_ending_delimiter* *regular_expression_option**
 pub(crate) fn regular_expression_literal(i: Input) -> NodeResult {
-    alt((
-        map(
-            tuple((
-                char('/'),
-                regular_expression_body,
-                char('/'),
-                many0(regular_expression_option),
-            )),
-            |_| Node::Placeholder,
-        ),
-        map(
-            tuple((
-                tag("%r"),
-                literal_beginning_delimiter,
-                many0(expanded_literal_string),
-                literal_ending_delimiter,
-                many0(regular_expression_option),
-            )),
-            |_| Node::Placeholder,
-        ),
-    ))(i)
+    token(
+        |_| TokenKind::Literal(LiteralKind::Regex),
+        spanned(alt((
+            map(
+                tuple((
+                    construct(
+                        "/",
+                        "/",
+                        tuple((char('/'), regular_expression_body, char('/'))),
+                    ),
+                    many0(regular_expression_option),
+                )),
+                |_| Node::new(NodeKind::Placeholder),
+            ),
+            map(
+                tuple((
+                    percent_construct(tuple((
+                        tag("%r"),
+                        literal_beginning_delimiter,
+                        many0(expanded_literal_string),
+                        literal_ending_delimiter,
+                    ))),
+                    many0(regular_expression_option),
+                )),
+                |_| Node::new(NodeKind::Placeholder),
+            ),
+        ))),
+    )(i)
 }
 
 /// *regular_expression_character**
 pub(crate) fn regular_expression_body(i: Input) -> InterpolatableResult {
-    map(many0(regular_expression_character), |contents| {
+    interpolatable(map(many0(regular_expression_character), |contents| {
         Interpolatable::from(contents)
-    })(i)
+    }))(i)
 }
 
 /// *source_character* **but not** ( `/` | `#` | `\` ) | `#` **not** ( `$` | `@` | `{` ) | *regular_expression_unescaped_sequence* | *regular_expression_escape_sequence* | *line_terminator_escape_sequence* | *interpolated_character_sequence*
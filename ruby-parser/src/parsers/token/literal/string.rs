@@ -19,26 +19,99 @@ pub(crate) use quoted::quoted_expanded_literal_string;
 pub(crate) use quoted::quoted_non_expanded_literal_string;
 pub(crate) use single::single_quoted_string;
 
-/// *single_quoted_string* | *double_quoted_string* | *quoted_non_expanded_literal_string* | *quoted_expanded_literal_string* | *here_document* | *external_command_execution*
+/// *adjacent_string_literal_part* | *here_document* | *character_literal* | *external_command_execution*
+///
+/// Juxtaposed string literals are merged by the literal expression that contains them.
 pub(crate) fn string_literal(i: Input) -> NodeResult {
-    alt((
-        map(single_quoted_string, |s| Node::Literal(Literal::String(s))),
-        map(double_quoted_string, |s| match s {
-            Interpolatable::String(s) => Node::Literal(Literal::String(s)),
-            Interpolatable::Interpolated(i) => Node::Interpolated(Interpolated::String(i)),
-        }),
-        map(quoted_non_expanded_literal_string, |s| {
-            Node::Literal(Literal::String(s))
-        }),
-        map(quoted_expanded_literal_string, |s| match s {
-            Interpolatable::String(s) => Node::Literal(Literal::String(s)),
-            Interpolatable::Interpolated(i) => Node::Interpolated(Interpolated::String(i)),
-        }),
+    spanned(alt((
+        adjacent_string_literal_part,
         here_document,
-        map(external_command_execution, |s| match s {
-            Interpolatable::String(s) => Node::Literal(Literal::Command(s)),
-            Interpolatable::Interpolated(i) => Node::Interpolated(Interpolated::Command(i)),
-        }),
-        map(character_literal, |s| Node::Literal(Literal::String(s))),
-    ))(i)
+        token(
+            |_| TokenKind::Literal(LiteralKind::Character),
+            map(character_literal, |s| match s {
+                Segment::Bytes(b) => Node::new(NodeKind::Literal(Literal::CharacterBytes(b))),
+                Segment::String(s) => Node::new(NodeKind::Literal(Literal::Character(s))),
+                _ => unreachable!(),
+            }),
+        ),
+        token(
+            |_| TokenKind::Literal(LiteralKind::Command),
+            map(external_command_execution, |s| match s {
+                Interpolatable::String(s) => Node::new(NodeKind::Literal(Literal::Command(s))),
+                Interpolatable::Bytes(b) => Node::new(NodeKind::Literal(Literal::CommandBytes(b))),
+                Interpolatable::Interpolated(i) => {
+                    Node::new(NodeKind::Interpolated(Interpolated::Command(i)))
+                }
+            }),
+        ),
+    )))(i)
+}
+
+/// *single_quoted_string* | *double_quoted_string* | *quoted_non_expanded_literal_string* | *quoted_expanded_literal_string*
+pub(crate) fn adjacent_string_literal_part(i: Input) -> NodeResult {
+    token(
+        |_| TokenKind::Literal(LiteralKind::String),
+        spanned(alt((
+            map(single_quoted_string, |s| {
+                Node::new(NodeKind::Literal(Literal::String(s)))
+            }),
+            map(double_quoted_string, |s| match s {
+                Interpolatable::String(s) => Node::new(NodeKind::Literal(Literal::String(s))),
+                Interpolatable::Bytes(b) => Node::new(NodeKind::Literal(Literal::Bytes(b))),
+                Interpolatable::Interpolated(i) => {
+                    Node::new(NodeKind::Interpolated(Interpolated::String(i)))
+                }
+            }),
+            map(quoted_non_expanded_literal_string, |s| {
+                Node::new(NodeKind::Literal(Literal::String(s)))
+            }),
+            map(quoted_expanded_literal_string, |s| match s {
+                Interpolatable::String(s) => Node::new(NodeKind::Literal(Literal::String(s))),
+                Interpolatable::Bytes(b) => Node::new(NodeKind::Literal(Literal::Bytes(b))),
+                Interpolatable::Interpolated(i) => {
+                    Node::new(NodeKind::Interpolated(Interpolated::String(i)))
+                }
+            }),
+        ))),
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_literal() {
+        fn s(v: &str) -> Node {
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
+        }
+        use_parser!(string_literal);
+        assert_ok!("'foo'", s("foo"));
+        assert_ok!(
+            "`ls`",
+            Node::new(NodeKind::Literal(Literal::Command("ls".to_owned())))
+        );
+        assert_ok!(
+            "?a",
+            Node::new(NodeKind::Literal(Literal::Character("a".to_owned())))
+        );
+        assert_ok!(
+            "?\\xff",
+            Node::new(NodeKind::Literal(Literal::CharacterBytes(vec![0xFF])))
+        );
+        assert_ok!(
+            "\"\\xff\"",
+            Node::new(NodeKind::Literal(Literal::Bytes(vec![0xFF])))
+        );
+        assert_ok!(
+            "`\\xff`",
+            Node::new(NodeKind::Literal(Literal::CommandBytes(vec![0xFF])))
+        );
+        assert_ok!(
+            "%x(a\\xfe)",
+            Node::new(NodeKind::Literal(Literal::CommandBytes(vec![b'a', 0xFE])))
+        );
+        // Adjacent literals are merged by the enclosing expression
+        assert_partial!("\"meh\"  \"Blah\"", s("meh"));
+    }
 }
@@ -1,19 +1,27 @@
 use super::double::double_escape_sequence;
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::*;
+use crate::parsers::token::identifier::identifier_character;
 
 /// `?` ( *double_escape_sequence* | *source_character* **but not** ( *whitespace* | `\` ) )
-pub(crate) fn character_literal(i: Input) -> StringResult {
+///
+/// As in MRI, a `?` followed by two or more identifier characters (`?ab`) is not a character literal
+/// but a conditional operator.
+pub(crate) fn character_literal(i: Input) -> SegmentResult {
     map(
         tuple((
             char('?'),
             alt((
                 // An escaped newline should not be treated as a line continuation in this context
-                map(tag("\\\n"), |_| "\n".to_owned()),
+                map(tag("\\\n"), |_| Segment::String("\n".to_owned())),
                 double_escape_sequence,
                 map(
-                    preceded(peek(not(alt((whitespace, tag("\\"))))), source_character),
-                    |c| c.to_string(),
+                    preceded(
+                        peek(not(alt((whitespace, tag("\\"), ascii_identifier_prefix)))),
+                        source_character,
+                    ),
+                    |c| Segment::String(c.to_string()),
                 ),
             )),
         )),
@@ -21,12 +29,23 @@ pub(crate) fn character_literal(i: Input) -> StringResult {
     )(i)
 }
 
+/// An ASCII alphanumeric character or `_` that is followed by an *identifier_character*
+fn ascii_identifier_prefix(i: Input) -> LexResult {
+    recognize(tuple((
+        verify(anychar, |c: &char| c.is_ascii_alphanumeric() || *c == '_'),
+        identifier_character,
+    )))(i)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_character_literal() {
+        fn s(v: &str) -> Segment {
+            Segment::String(v.to_owned())
+        }
         use_parser!(character_literal);
         // Parse errors
         assert_err!("");
@@ -36,22 +55,29 @@ mod tests {
         assert_err!("?\t");
         assert_err!("a");
         assert_err!("?ab");
+        assert_err!("?a_");
+        assert_err!("?_1");
         assert_err!("?a ");
         assert_err!("?\\u");
         assert_err!("?1234");
         // Success cases
-        assert_ok!("?2", "2");
-        assert_ok!("?a", "a");
-        assert_ok!("?:", ":");
-        assert_ok!("?😄", "😄");
-        assert_ok!("?東", "東"); // U+6771: 'CJK Unified Ideograph-6771' "East"
-        assert_ok!("?\\k", "k");
-        assert_ok!("?\\ ", " ");
-        assert_ok!("?\\\\", "\\");
-        assert_ok!("?\\n", "\n");
-        assert_ok!("?\\\n", "\n");
-        assert_ok!("?\\\t", "\t");
-        assert_ok!("?\\123", "S");
-        assert_ok!("?\\u{0000 0002}", "\u{0}\u{2}");
+        assert_ok!("?2", s("2"));
+        assert_ok!("?a", s("a"));
+        assert_ok!("?:", s(":"));
+        assert_ok!("?😄", s("😄"));
+        assert_remaining!("?東a", "a");
+        assert_remaining!("?a?", "?");
+        assert_ok!("?東", s("東")); // U+6771: 'CJK Unified Ideograph-6771' "East"
+        assert_ok!("?\\k", s("k"));
+        assert_ok!("?\\ ", s(" "));
+        assert_ok!("?\\\\", s("\\"));
+        assert_ok!("?\\n", s("\n"));
+        assert_ok!("?\\\n", s("\n"));
+        assert_ok!("?\\\t", s("\t"));
+        assert_ok!("?\\123", s("S"));
+        assert_ok!("?\\u{0000 0002}", s("\u{0}\u{2}"));
+        // Raw bytes
+        assert_ok!("?\\xff", Segment::Bytes(vec![0xFF]));
+        assert_ok!("?\\M-a", Segment::Bytes(vec![0xE1]));
     }
 }
@@ -1,6 +1,7 @@
 use super::double::double_escape_sequence;
 use super::double::interpolated_character_sequence;
 use super::quoted::expanded_delimited_string;
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::source_character;
 
@@ -14,13 +15,17 @@ pub(crate) fn external_command_execution(i: Input) -> InterpolatableResult {
 
 /// ``` *backquoted_external_command_execution_character** ```
 pub(crate) fn backquoted_external_command_execution(i: Input) -> InterpolatableResult {
-    map(
+    construct(
+        "`",
+        "`",
         delimited(
             char('`'),
-            many0(backquoted_external_command_execution_character),
+            interpolatable(map(
+                many0(backquoted_external_command_execution_character),
+                Interpolatable::from,
+            )),
             char('`'),
         ),
-        |vecs| Interpolatable::from(vecs),
     )(i)
 }
 
@@ -30,7 +35,7 @@ pub(crate) fn backquoted_external_command_execution_character(i: Input) -> Segme
         map(preceded(peek(none_of("`#\\")), source_character), |c| {
             Segment::Char(c)
         }),
-        map(double_escape_sequence, |s| Segment::String(s)),
+        double_escape_sequence,
         map(interpolated_character_sequence, |e| Segment::expr(e)),
         map(terminated(char('#'), peek(none_of("$@{"))), |c| {
             Segment::Char(c)
@@ -40,7 +45,7 @@ pub(crate) fn backquoted_external_command_execution_character(i: Input) -> Segme
 
 /// `%x` *expanded_delimited_string*
 pub(crate) fn quoted_external_command_execution(i: Input) -> InterpolatableResult {
-    preceded(tag("%x"), expanded_delimited_string)(i)
+    percent_construct(preceded(tag("%x"), expanded_delimited_string))(i)
 }
 
 #[cfg(test)]
@@ -73,11 +78,11 @@ mod tests {
         assert_interpolated!(
             "%x!foo#@hi [bar] [#{%Q((hello))}]!",
             vec![
-                Node::Segment(Segment::String("foo".to_owned())),
+                Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
                 Node::ident("@hi", IdentifierKind::InstanceVariable),
-                Node::Segment(Segment::String(" [bar] [".to_owned())),
-                Node::Block(vec![Node::literal_string("(hello)")]),
-                Node::Segment(Segment::String("]".to_owned())),
+                Node::new(NodeKind::Segment(Segment::String(" [bar] [".to_owned()))),
+                Node::new(NodeKind::Block(vec![Node::literal_string("(hello)")])),
+                Node::new(NodeKind::Segment(Segment::String("]".to_owned()))),
             ]
         );
     }
@@ -1,3 +1,4 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::program::*;
 use crate::parsers::token::identifier::*;
@@ -6,35 +7,85 @@ use std::convert::TryFrom;
 
 /// `"` *double_quoted_string_character** `"`
 pub(crate) fn double_quoted_string(i: Input) -> InterpolatableResult {
-    let (i, _) = char('"')(i)?;
-    let (i, contents) = many0(double_quoted_string_character)(i)?;
-    let (i, _) = char('"')(i)?;
+    construct(
+        "\"",
+        "\"",
+        delimited(
+            char('"'),
+            interpolatable(map(
+                many0(double_quoted_string_character),
+                Interpolatable::from,
+            )),
+            char('"'),
+        ),
+    )(i)
+}
 
-    Ok((i, Interpolatable::from(contents)))
+/// *double_quoted_string* whose content is valid UTF-8, as required by symbols
+pub(crate) fn valid_double_quoted_string(i: Input) -> InterpolatableResult {
+    verify(double_quoted_string, |s| {
+        !matches!(s, Interpolatable::Bytes(_))
+    })(i)
 }
 
 /// *source_character* **but not** ( `"` | `#` | `\` ) | `#` **not** ( `$` | `@` | `{` ) | *double_escape_sequence* | *interpolated_character_sequence*
 pub(crate) fn double_quoted_string_character(i: Input) -> SegmentResult {
     alt((
         map(none_of("\"#\\"), |c| Segment::Char(c)),
-        map(double_escape_sequence, |s| Segment::String(s)),
+        double_escape_sequence,
         map(interpolated_character_sequence, |e| Segment::expr(e)),
         map(char('#'), |c| Segment::Char(c)),
     ))(i)
 }
 
 /// *simple_escape_sequence* | *non_escaped_sequence* | *line_terminator_escape_sequence* | *octal_escape_sequence* | *hexadecimal_escape_sequence* | *control_escape_sequence*
-pub(crate) fn double_escape_sequence(i: Input) -> StringResult {
-    // Should be evaluated
+pub(crate) fn double_escape_sequence(i: Input) -> SegmentResult {
+    let encoding = i.metadata.encoding;
+    if encoding == Encoding::Utf8 {
+        return escape_sequence(i);
+    }
+    // The raw bytes of escapes are in the source's encoding, so consecutive escapes are
+    // transcoded together the same way as the source was
+    alt((
+        map(many1(byte_escape_sequence), move |bytes| {
+            match encoding.decode(&bytes) {
+                Ok(s) => Segment::String(s),
+                Err(_) => Segment::Bytes(bytes),
+            }
+        }),
+        escape_sequence,
+    ))(i)
+}
+
+/// A single *double_escape_sequence*
+fn escape_sequence(i: Input) -> SegmentResult {
+    // Octal, hexadecimal and meta escapes represent raw bytes rather than code points
     alt((
-        map(simple_escape_sequence, |c| c.to_string()),
-        map(line_terminator_escape_sequence, |_s| String::new()),
-        map(octal_escape_sequence, |c| c.to_string()),
-        map(hexadecimal_escape_sequence, |c| c.to_string()),
-        map(single_unicode_escape_sequence, |c| c.to_string()),
-        multiple_unicode_escape_sequence,
+        map(simple_escape_sequence, |c| Segment::String(c.to_string())),
+        map(line_terminator_escape_sequence, |_s| {
+            Segment::String(String::new())
+        }),
+        map(octal_escape_sequence, Segment::byte),
+        map(hexadecimal_escape_sequence, Segment::byte),
+        map(single_unicode_escape_sequence, |c| {
+            Segment::String(c.to_string())
+        }),
+        map(multiple_unicode_escape_sequence, Segment::String),
         control_escape_sequence,
-        map(non_escaped_sequence, |c| c.to_string()),
+        map(non_escaped_sequence, |c| Segment::String(c.to_string())),
+    ))(i)
+}
+
+/// *octal_escape_sequence* | *hexadecimal_escape_sequence* | *control_escape_sequence*
+fn byte_escape_sequence(i: Input) -> ByteResult {
+    alt((
+        octal_escape_sequence,
+        hexadecimal_escape_sequence,
+        map(control_escape_sequence, |s| match s {
+            Segment::String(s) => s.as_bytes()[0],
+            Segment::Bytes(b) => b[0],
+            _ => unreachable!(),
+        }),
     ))(i)
 }
 
@@ -75,18 +126,19 @@ pub(crate) fn non_escaped_double_quoted_string_char(i: Input) -> CharResult {
 }
 
 /// `\` *octal_digit* *octal_digit*? *octal_digit*?
-pub(crate) fn octal_escape_sequence(i: Input) -> CharResult {
+pub(crate) fn octal_escape_sequence(i: Input) -> ByteResult {
     map(
         tuple((char('\\'), recognize(many_m_n(1, 3, octal_digit)))),
-        |t| char_from_radix(*t.1, 8),
+        // MRI truncates values above `\377` to the last byte
+        |t| (u32::from_str_radix(*t.1, 8).unwrap() & 0xFF) as u8,
     )(i)
 }
 
 /// `\` `x` *hexadecimal_digit* *hexadecimal_digit*?
-pub(crate) fn hexadecimal_escape_sequence(i: Input) -> CharResult {
+pub(crate) fn hexadecimal_escape_sequence(i: Input) -> ByteResult {
     map(
         tuple((tag("\\x"), recognize(many_m_n(1, 2, hexadecimal_digit)))),
-        |t| char_from_radix(*t.1, 16),
+        |t| u8::from_str_radix(*t.1, 16).unwrap(),
     )(i)
 }
 
@@ -123,8 +175,8 @@ pub(crate) fn unicode_hex_digits(i: Input) -> LexResult {
 }
 
 /// ( `\` ( `C-` | `c` ) **and/or** `\M-` ) *control_escaped_character*
-pub(crate) fn control_escape_sequence(i: Input) -> StringResult {
-    let (i, ((ctrl, meta), escape)) = tuple((
+pub(crate) fn control_escape_sequence(i: Input) -> SegmentResult {
+    let (i, ((ctrl, meta), chr)) = tuple((
         alt((
             map(
                 alt((
@@ -140,34 +192,38 @@ pub(crate) fn control_escape_sequence(i: Input) -> StringResult {
         )),
         control_escaped_character,
     ))(i)?;
-    let chr: u8 = escape.chars().next().unwrap() as u8;
-    match (ctrl, meta, chr) {
-        (true, false, 0x3F) => Ok((i, "\x7F".to_owned())),
-        (true, false, _) => Ok((
-            i,
-            ((if chr < 0x20 { chr } else { chr & 0x9F }) as char).to_string(),
-        )),
-        (true, true, _) => Ok((
-            i,
-            (((if chr < 0x20 { chr } else { chr & 0x9F }) | 0x80) as char).to_string(),
-        )),
-        (false, true, _) => Ok((i, (((chr & 0xFF) | 0x80) as char).to_string())),
+    let byte = match (ctrl, meta, chr) {
+        (true, false, 0x3F) => 0x7F,
+        (true, false, _) => {
+            if chr < 0x20 {
+                chr
+            } else {
+                chr & 0x9F
+            }
+        }
+        (true, true, _) => (if chr < 0x20 { chr } else { chr & 0x9F }) | 0x80,
+        (false, true, _) => chr | 0x80,
         (false, false, _) => unreachable!(),
-    }
+    };
+    Ok((i, Segment::byte(byte)))
 }
 
 /// *double_escape_sequence* | `?` | *source_character* **but not** ( `\` | `?` )
-pub(crate) fn control_escaped_character(i: Input) -> StringResult {
+pub(crate) fn control_escaped_character(i: Input) -> ByteResult {
     alt((
-        double_escape_sequence,
-        map(tag("?"), |s: Input| s.to_string()),
-        map(none_of("\\?"), |c: char| c.to_string()),
+        map(escape_sequence, |s| match s {
+            Segment::String(s) => s.chars().next().map_or(0, |c| c as u8),
+            Segment::Bytes(b) => b[0],
+            _ => unreachable!(),
+        }),
+        map(tag("?"), |_| b'?'),
+        map(none_of("\\?"), |c: char| c as u8),
     ))(i)
 }
 
 /// `#` *global_variable_identifier* | `#` *class_variable_identifier* | `#` *instance_variable_identifier* | `#` `{` *compound_statement* `}`
 pub(crate) fn interpolated_character_sequence(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(preceded(char('#'), global_variable_identifier), |v| {
             Node::from(v)
         }),
@@ -177,8 +233,11 @@ pub(crate) fn interpolated_character_sequence(i: Input) -> NodeResult {
         map(preceded(char('#'), instance_variable_identifier), |v| {
             Node::from(v)
         }),
-        map(tuple((tag("#{"), compound_statement, char('}'))), |t| t.1),
-    ))(i)
+        // The block of statements spans the braces that delimit it
+        map(tuple((tag("#{"), compound_statement, char('}'))), |t| {
+            Node::new(t.1.kind)
+        }),
+    )))(i)
 }
 
 // Converts the value of an escape sequence into a character
@@ -197,6 +256,18 @@ mod tests {
         };
     }
 
+    macro_rules! assert_segment {
+        ($a:expr, $b:expr) => {
+            assert_ok!($a, Segment::String($b.to_owned()))
+        };
+    }
+
+    macro_rules! assert_bytes {
+        ($a:expr, $b:expr) => {
+            assert_ok!($a, Segment::Bytes($b))
+        };
+    }
+
     #[test]
     fn test_double_quoted_string() {
         use_parser!(double_quoted_string);
@@ -204,7 +275,7 @@ mod tests {
             Interpolatable::String(i.to_owned())
         }
         fn seg(i: &str) -> Node {
-            Node::Segment(Segment::String(i.to_owned()))
+            Node::new(NodeKind::Segment(Segment::String(i.to_owned())))
         }
         fn is(i: Vec<Node>) -> Interpolatable {
             Interpolatable::Interpolated(i)
@@ -216,6 +287,18 @@ mod tests {
         // Success cases
         assert_ok!("\"\"", ds(""));
         assert_ok!("\"foo\\\nbar\"", ds("foobar"));
+        // Raw bytes
+        assert_ok!("\"\\xC3\\xA9\"", ds("é"));
+        assert_ok!("\"\\303\\251\"", ds("é"));
+        assert_ok!("\"\\xff\\xfe\"", Interpolatable::Bytes(vec![0xFF, 0xFE]));
+        assert_ok!(
+            "\"a\\377#{1}é\"",
+            is(vec![
+                Node::new(NodeKind::Segment(Segment::Bytes(vec![b'a', 0xFF]))),
+                Node::new(NodeKind::Block(vec![Node::int(1)])),
+                seg("é")
+            ])
+        );
         assert_ok!(
             "\"some #thing\\n#$hi\"",
             is(vec![
@@ -227,7 +310,7 @@ mod tests {
             "\"#@@VAR#{2; 3.5} \"",
             is(vec![
                 Node::ident("@@VAR", IdentifierKind::ClassVariable),
-                Node::Block(vec![Node::int(2), Node::float(3.5)]),
+                Node::new(NodeKind::Block(vec![Node::int(2), Node::float(3.5)])),
                 seg(" ")
             ])
         );
@@ -247,7 +330,7 @@ mod tests {
         assert_ok!("#", Segment::Char('#'));
         assert_ok!("\\\"", Segment::String("\"".to_owned()));
         assert_ok!("\\u0000", Segment::String("\0".to_owned()));
-        assert_ok!("#{}", Segment::expr(Node::Block(vec![])));
+        assert_ok!("#{}", Segment::expr(Node::new(NodeKind::Block(vec![]))));
         assert_ok!(
             "#@@foo",
             Segment::expr(Node::ident("@@foo", IdentifierKind::ClassVariable))
@@ -260,26 +343,34 @@ mod tests {
             "#$glob",
             Segment::expr(Node::ident("$glob", IdentifierKind::GlobalVariable))
         );
+        assert_ok!(
+            "#$1",
+            Segment::expr(Node::ident("$1", IdentifierKind::NthReference))
+        );
         assert_ok!(
             "#{foobar}",
-            Segment::expr(Node::Block(vec![Node::ident(
+            Segment::expr(Node::new(NodeKind::Block(vec![Node::ident(
                 "foobar",
                 IdentifierKind::LocalVariable
-            )]))
+            )])))
         );
         assert_ok!(
             "#{\"foo#{2bar\"}",
-            Segment::expr(Node::Block(vec![Node::literal_string("foo#{2bar")]))
+            Segment::expr(Node::new(NodeKind::Block(vec![Node::literal_string(
+                "foo#{2bar"
+            )])))
         );
         assert_ok!(
             "#{\"foo#{2}bar\"}",
-            Segment::expr(Node::Block(vec![Node::Interpolated(Interpolated::String(
-                vec![
-                    Node::Segment(Segment::String("foo".to_owned())),
-                    Node::Block(vec![Node::Literal(Literal::Integer(2))]),
-                    Node::Segment(Segment::String("bar".to_owned()))
-                ]
-            ))]))
+            Segment::expr(Node::new(NodeKind::Block(vec![Node::new(
+                NodeKind::Interpolated(Interpolated::String(vec![
+                    Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
+                    Node::new(NodeKind::Block(vec![Node::new(NodeKind::Literal(
+                        Literal::Integer(2)
+                    ))])),
+                    Node::new(NodeKind::Segment(Segment::String("bar".to_owned())))
+                ]))
+            )])))
         );
     }
 
@@ -291,16 +382,18 @@ mod tests {
         assert_err!("\\");
         assert_err!("\r");
         // Success cases
-        assert_result!("\\ ", " ");
-        assert_result!("\\\\", "\\");
-        assert_result!("\\\n", "");
-        assert_result!("\\000", "\0");
-        assert_result!("\\x7", "\u{07}");
-        assert_result!("\\r", "\r");
-        assert_result!("\\z", "z");
-        assert_result!("\\M-B", "\u{C2}");
-        assert_result!("\\uaBcD", "\u{ABCD}");
-        assert_result!("\\u{1234 aBCD}", "\u{1234}\u{ABCD}");
+        assert_segment!("\\ ", " ");
+        assert_segment!("\\\\", "\\");
+        assert_segment!("\\\n", "");
+        assert_segment!("\\000", "\0");
+        assert_segment!("\\x7", "\u{07}");
+        assert_segment!("\\r", "\r");
+        assert_segment!("\\z", "z");
+        assert_bytes!("\\M-B", vec![0xC2]);
+        assert_bytes!("\\xff", vec![0xFF]);
+        assert_bytes!("\\377", vec![0xFF]);
+        assert_segment!("\\uaBcD", "\u{ABCD}");
+        assert_segment!("\\u{1234 aBCD}", "\u{1234}\u{ABCD}");
     }
 
     #[test]
@@ -358,15 +451,15 @@ mod tests {
         assert_err!("\\1234");
         assert_err!("\\x0");
         // Success cases
-        assert_ok!("\\0", '\0');
-        assert_ok!("\\000", '\0');
-        assert_ok!("\\7", '\u{7}');
-        assert_ok!("\\40", ' ');
-        assert_ok!("\\040", ' ');
-        assert_ok!("\\77", '?');
-        assert_ok!("\\150", 'h');
-        assert_ok!("\\374", '\u{FC}');
-        assert_ok!("\\776", '\u{1FE}'); // MRI truncates to just the last byte (\xFE)
+        assert_ok!("\\0", 0);
+        assert_ok!("\\000", 0);
+        assert_ok!("\\7", 0x07);
+        assert_ok!("\\40", b' ');
+        assert_ok!("\\040", b' ');
+        assert_ok!("\\77", b'?');
+        assert_ok!("\\150", b'h');
+        assert_ok!("\\374", 0xFC);
+        assert_ok!("\\776", 0xFE); // MRI truncates to just the last byte
     }
 
     #[test]
@@ -379,15 +472,15 @@ mod tests {
         assert_err!("\\xFFa");
         assert_err!("\\XFF");
         // Success cases
-        assert_ok!("\\x0", '\0');
-        assert_ok!("\\x00", '\0');
-        assert_ok!("\\x7", '\u{07}');
-        assert_ok!("\\x20", ' ');
-        assert_ok!("\\x0A", '\n');
-        assert_ok!("\\x36", '6');
-        assert_ok!("\\x72", '\x72');
-        assert_ok!("\\xfa", '\u{FA}');
-        assert_ok!("\\xFF", '\u{FF}');
+        assert_ok!("\\x0", 0);
+        assert_ok!("\\x00", 0);
+        assert_ok!("\\x7", 0x07);
+        assert_ok!("\\x20", b' ');
+        assert_ok!("\\x0A", b'\n');
+        assert_ok!("\\x36", b'6');
+        assert_ok!("\\x72", 0x72);
+        assert_ok!("\\xfa", 0xFA);
+        assert_ok!("\\xFF", 0xFF);
     }
 
     #[test]
@@ -436,32 +529,32 @@ mod tests {
         assert_err!("\\c-a");
         assert_err!("a");
         // Success cases
-        assert_result!("\\C- ", "\0");
-        assert_result!("\\C-5", "\u{15}");
-        assert_result!("\\cA", "\u{01}");
-        assert_result!("\\C-A", "\u{01}");
-        assert_result!("\\M- ", "\u{A0}");
-        assert_result!("\\M-b", "\u{E2}");
-        assert_result!("\\M-B", "\u{C2}");
-        assert_result!("\\M-\\C-c", "\u{83}");
-        assert_result!("\\M-\\C-C", "\u{83}");
-        assert_result!("\\c\\M-D", "\u{84}");
-        assert_result!("\\M-?", "\u{BF}");
-        assert_result!("\\M-\\C- ", "\u{80}");
-        assert_result!("\\M-\\C-?", "\u{9F}");
-        assert_result!("\\c?", "\u{7F}");
-        assert_result!("\\C-?", "\u{7F}");
+        assert_segment!("\\C- ", "\0");
+        assert_segment!("\\C-5", "\u{15}");
+        assert_segment!("\\cA", "\u{01}");
+        assert_segment!("\\C-A", "\u{01}");
+        assert_bytes!("\\M- ", vec![0xA0]);
+        assert_bytes!("\\M-b", vec![0xE2]);
+        assert_bytes!("\\M-B", vec![0xC2]);
+        assert_bytes!("\\M-\\C-c", vec![0x83]);
+        assert_bytes!("\\M-\\C-C", vec![0x83]);
+        assert_bytes!("\\c\\M-D", vec![0x84]);
+        assert_bytes!("\\M-?", vec![0xBF]);
+        assert_bytes!("\\M-\\C- ", vec![0x80]);
+        assert_bytes!("\\M-\\C-?", vec![0x9F]);
+        assert_segment!("\\c?", "\u{7F}");
+        assert_segment!("\\C-?", "\u{7F}");
         // Multibytes should only look at the first byte
-        assert_result!("\\M-\\C-東", "\u{91}");
-        assert_result!("\\M-😅", "\u{85}");
+        assert_bytes!("\\M-\\C-東", vec![0x91]);
+        assert_bytes!("\\M-😅", vec![0x85]);
         // Escape sequences
-        assert_result!("\\C-\\\\", "\u{1C}");
-        assert_result!("\\C-\\M-\\\\", "\u{9C}");
-        assert_result!("\\C-\n", "\u{0A}");
-        assert_result!("\\C-\\n", "\u{0A}");
-        assert_result!("\\M-\\C-\\n", "\u{8A}");
-        assert_result!("\\C-\\t", "\t");
-        assert_result!("\\C-\\z", "\u{1A}");
-        assert_result!("\\C-\\C-\\n", "\u{0A}");
+        assert_segment!("\\C-\\\\", "\u{1C}");
+        assert_bytes!("\\C-\\M-\\\\", vec![0x9C]);
+        assert_segment!("\\C-\n", "\u{0A}");
+        assert_segment!("\\C-\\n", "\u{0A}");
+        assert_bytes!("\\M-\\C-\\n", vec![0x8A]);
+        assert_segment!("\\C-\\t", "\t");
+        assert_segment!("\\C-\\z", "\u{1A}");
+        assert_segment!("\\C-\\C-\\n", "\u{0A}");
     }
 }
@@ -1,24 +1,56 @@
 use super::double::{double_escape_sequence, interpolated_character_sequence};
 use crate::ast::Literal;
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::comment::line_content;
 use crate::parsers::program::{line_terminator, source_character, whitespace};
 use crate::parsers::token::identifier::identifier_character;
 
-// TODO: ensure nested interpolated heredocs work
-// TODO: ensure sequenced heredocs work
-
 /// *heredoc_start_line* *heredoc_body* *heredoc_end_line*
 pub(crate) fn here_document(i: Input) -> NodeResult {
-    wrap_heredoc(_here_document)(i)
+    spanned(wrap_heredoc(_here_document))(i)
 }
 
 // When dealing with heredocs, the parser has to make a jump in the input.
 // First, the heredoc is parsed, and parsing continues using the rest of the heredoc's start line.
 // Only then does parsing resume after the heredoc's ending identifier.
+//
+// Pending jumps form a queue: any heredoc that starts on the rest of that line reads its body
+// from where the previous one ended, and queues its own ending identifier in its place.
 fn _here_document(i: Input) -> NodeResult {
+    let start = i.clone();
     let (i, mut line) = heredoc_start_line(i)?;
-    let (remaining, token) = terminated(heredoc_body, heredoc_end_line)(i)?;
+    // The heredoc remains open until its ending identifier is found
+    let failures = i.metadata.failures;
+    if let Some(failures) = failures {
+        let identifier = i.metadata.heredoc.as_deref().unwrap().identifier;
+        // When the signifier ends its line, the rest of the line has already jumped past the
+        // bodies of the heredocs that were queued on it
+        let length = line.offset() - start.offset();
+        let signifier = &start[..length.min(start.len())];
+        failures.borrow_mut().open(
+            signifier.to_owned(),
+            identifier.unwrap_or_default().to_owned(),
+            start.position(),
+        );
+    }
+    let res = terminated(heredoc_body, heredoc_end_line)(i.clone());
+    if let Some(failures) = failures {
+        failures.borrow_mut().close();
+    }
+    let (remaining, token) = res?;
+    // The body is jumped over by the parser, so it is recorded apart from the other tokens
+    if let Some(tokens) = i.metadata.tokens {
+        tokens
+            .borrow_mut()
+            .heredoc(start.offset(), i.offset(), remaining.offset());
+    }
+    if line.is_empty() {
+        // The ending identifier's line terminator also terminates the start line, unless it is
+        // the last line of the input and there is nothing left for it to separate
+        let (remaining, _) = opt(terminated(line_terminator, at_eof))(remaining)?;
+        return Ok((remaining, token));
+    }
     line.remaining_input = Some(Box::new(remaining));
     Ok((line, token))
 }
@@ -30,12 +62,23 @@ fn heredoc_start_line(i: Input) -> LexResult {
 
 /// `<<` *heredoc_quote_type_specifier*
 fn heredoc_signifier(i: Input) -> LexResult {
-    preceded(tag("<<"), heredoc_quote_type_specifier)(i)
+    token(
+        |_| TokenKind::Literal(LiteralKind::Heredoc),
+        preceded(tag("<<"), heredoc_quote_type_specifier),
+    )(i)
 }
 
 /// *line_content*? *line_terminator*
 fn rest_of_line(i: Input) -> LexResult {
-    terminated(recognize(opt(line_content)), line_terminator)(i)
+    // When a heredoc has already been started on this line, the line's terminator is the one
+    // that follows the queued heredoc's ending identifier
+    match i.jump() {
+        Some(queued) if !i.contains('\n') => {
+            let (body, _) = line_terminator(queued)?;
+            Ok((body, i))
+        }
+        _ => terminated(recognize(opt(line_content)), line_terminator)(i),
+    }
 }
 
 /// *comment_line** **but not** *heredoc_end_line*
@@ -45,24 +88,27 @@ fn heredoc_body(i: Input) -> NodeResult {
         _ => double_quoted_character,
     };
     let indentation = i.metadata.heredoc.as_deref().unwrap().indentation;
-    let (i, contents) = map(
+    let (i, contents) = interpolatable(map(
         many0(preceded(peek(not(heredoc_end_line)), heredoc_contents)),
-        |vec| {
-            let val = Interpolatable::from(vec.into_iter().collect::<Vec<Segment>>());
-            match indentation {
-                Some(HeredocIndentation::FullyIndented) => val.to_unindented(),
-                _ => val,
-            }
+        |vec| match indentation {
+            Some(HeredocIndentation::FullyIndented) => Interpolatable::from_unindented(vec),
+            _ => Interpolatable::from(vec),
         },
-    )(i)?;
+    ))(i)?;
     let token = match i.metadata.heredoc.as_deref().unwrap().quote_type {
         Some(HeredocQuoteType::CommandQuoted) => match contents {
-            Interpolatable::String(v) => Node::Literal(Literal::Command(v)),
-            Interpolatable::Interpolated(v) => Node::Interpolated(Interpolated::Command(v)),
+            Interpolatable::String(v) => Node::new(NodeKind::Literal(Literal::Command(v))),
+            Interpolatable::Bytes(v) => Node::new(NodeKind::Literal(Literal::CommandBytes(v))),
+            Interpolatable::Interpolated(v) => {
+                Node::new(NodeKind::Interpolated(Interpolated::Command(v)))
+            }
         },
         _ => match contents {
-            Interpolatable::String(v) => Node::Literal(Literal::String(v)),
-            Interpolatable::Interpolated(v) => Node::Interpolated(Interpolated::String(v)),
+            Interpolatable::String(v) => Node::new(NodeKind::Literal(Literal::String(v))),
+            Interpolatable::Bytes(v) => Node::new(NodeKind::Literal(Literal::Bytes(v))),
+            Interpolatable::Interpolated(v) => {
+                Node::new(NodeKind::Interpolated(Interpolated::String(v)))
+            }
         },
     };
     Ok((i, token))
@@ -75,7 +121,7 @@ fn single_quoted_character(i: Input) -> SegmentResult {
 fn double_quoted_character(i: Input) -> SegmentResult {
     alt((
         map(none_of("#\\"), |c| Segment::Char(c)),
-        map(double_escape_sequence, |s| Segment::String(s)),
+        double_escape_sequence,
         map(interpolated_character_sequence, |e| Segment::expr(e)),
         map(char('#'), |c| Segment::Char(c)),
     ))(i)
@@ -168,6 +214,8 @@ fn heredoc_end_line(i: Input) -> LexResult {
 }
 
 /// [ beginning of a line ] *whitespace** *heredoc_quote_type_identifier* *line_terminator*
+///
+/// The line terminator is not consumed, as it also terminates the heredoc's start line.
 fn indented_heredoc_end_line(i: Input) -> LexResult {
     if !i.beginning_of_line() {
         return Err(nom::Err::Error((i, crate::ErrorKind::Space)));
@@ -175,7 +223,7 @@ fn indented_heredoc_end_line(i: Input) -> LexResult {
     delimited(
         many0(whitespace),
         heredoc_quote_type_identifier,
-        alt((line_terminator, at_eof)),
+        peek(alt((line_terminator, at_eof))),
     )(i)
 }
 
@@ -185,13 +233,15 @@ fn at_eof(i: Input) -> LexResult {
 }
 
 /// [ beginning of a line ] *heredoc_quote_type_identifier* *line_terminator*
+///
+/// The line terminator is not consumed, as it also terminates the heredoc's start line.
 fn non_indented_heredoc_end_line(i: Input) -> LexResult {
     if !i.beginning_of_line() {
         return Err(nom::Err::Error((i, crate::ErrorKind::Space)));
     }
     terminated(
         heredoc_quote_type_identifier,
-        alt((line_terminator, at_eof)),
+        peek(alt((line_terminator, at_eof))),
     )(i)
 }
 
@@ -301,16 +351,16 @@ mod tests {
     #[test]
     fn test_here_document() {
         fn s(v: &str) -> Node {
-            Node::Literal(Literal::String(v.to_owned()))
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
         }
         fn i(v: Vec<Node>) -> Node {
-            Node::Interpolated(Interpolated::String(v))
+            Node::new(NodeKind::Interpolated(Interpolated::String(v)))
         }
         fn cs(v: &str) -> Node {
-            Node::Literal(Literal::Command(v.to_owned()))
+            Node::new(NodeKind::Literal(Literal::Command(v.to_owned())))
         }
         fn ci(v: Vec<Node>) -> Node {
-            Node::Interpolated(Interpolated::Command(v))
+            Node::new(NodeKind::Interpolated(Interpolated::Command(v)))
         }
         use_parser!(here_document);
         // Synax errors
@@ -321,6 +371,10 @@ mod tests {
         assert_partial!("<<foo + rest * of * line\nbar\nfoo\n", s("bar\n"));
         assert_ok!("<<foo\n  meh\n  bar\n\nfoo", s("  meh\n  bar\n\n"));
         assert_ok!("<<-`foo`\nbar\n foot\nfoo", cs("bar\n foot\n"));
+        assert_ok!(
+            "<<`foo`\n\\xff\nfoo",
+            Node::new(NodeKind::Literal(Literal::CommandBytes(vec![0xFF, b'\n'])))
+        );
         assert_err!("<<foo\nbar\n  foo\n");
         // Indented marker heredocs
         assert_ok!("<<-foo\n  bar\nfoo\n", s("  bar\n"));
@@ -329,17 +383,17 @@ mod tests {
         assert_ok!(
             "<<-foo\nbar#{2.4}\nfoo",
             i(vec![
-                Node::Segment(Segment::String("bar".to_owned())),
-                Node::Block(vec![Node::float(2.4)]),
-                Node::Segment(Segment::String("\n".to_owned()))
+                Node::new(NodeKind::Segment(Segment::String("bar".to_owned()))),
+                Node::new(NodeKind::Block(vec![Node::float(2.4)])),
+                Node::new(NodeKind::Segment(Segment::String("\n".to_owned())))
             ])
         );
         assert_ok!(
             "<<-`foo`\nbar#{2.4}\nfoo",
             ci(vec![
-                Node::Segment(Segment::String("bar".to_owned())),
-                Node::Block(vec![Node::float(2.4)]),
-                Node::Segment(Segment::String("\n".to_owned()))
+                Node::new(NodeKind::Segment(Segment::String("bar".to_owned()))),
+                Node::new(NodeKind::Block(vec![Node::float(2.4)])),
+                Node::new(NodeKind::Segment(Segment::String("\n".to_owned())))
             ])
         );
         // Literal heredocs
@@ -351,8 +405,8 @@ mod tests {
         assert_ok!(
             "<<~foo\n#{2}  bar\nfoo",
             i(vec![
-                Node::Block(vec![Node::int(2)]),
-                Node::Segment(Segment::String("  bar\n".to_owned()))
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
+                Node::new(NodeKind::Segment(Segment::String("  bar\n".to_owned())))
             ])
         );
         // Squiggly heredocs with indented content
@@ -360,13 +414,166 @@ mod tests {
         assert_ok!(
             "<<~foo\n    bar#{\n2\n} stuff\n\t\n     \n  3\nfoo",
             i(vec![
-                Node::Segment(Segment::String("  bar".to_owned())),
-                Node::Block(vec![Node::int(2)]),
-                Node::Segment(Segment::String(" stuff\n\n   \n3\n".to_owned()))
+                Node::new(NodeKind::Segment(Segment::String("  bar".to_owned()))),
+                Node::new(NodeKind::Block(vec![Node::int(2)])),
+                Node::new(NodeKind::Segment(Segment::String(
+                    " stuff\n\t\n   \n3\n".to_owned()
+                )))
             ])
         );
     }
 
+    #[test]
+    fn test_squiggly_heredoc_dedent() {
+        fn s(v: &str) -> Node {
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
+        }
+        fn i(v: Vec<Node>) -> Node {
+            Node::new(NodeKind::Interpolated(Interpolated::String(v)))
+        }
+        fn seg(v: &str) -> Node {
+            Node::new(NodeKind::Segment(Segment::String(v.to_owned())))
+        }
+        use_parser!(here_document);
+        // Conformance with MRI's dedenting rules
+        let cases = vec![
+            // Least-indented line
+            ("<<~E\n  a\n    b\nE", s("a\n  b\n")),
+            ("<<~E\n    a\n  b\n\nE", s("  a\nb\n\n")),
+            // Tabs advance to the next multiple of 8
+            ("<<~E\n\ta\n        b\nE", s("a\nb\n")),
+            ("<<~E\n    \ta\n        b\nE", s("a\nb\n")),
+            ("<<~E\n  \ta\n  b\nE", s("\ta\nb\n")),
+            ("<<~E\n\t  a\n            b\nE", s("a\n  b\n")),
+            // Whitespace-only lines are ignored, but still dedented
+            ("<<~E\n    a\n  \n\n    b\nE", s("a\n\n\nb\n")),
+            ("<<~E\n  a\n      \nE", s("a\n    \n")),
+            ("<<~E\n   \n\t\nE", s("\n\n")),
+            // Escaped whitespace is content
+            ("<<~E\n  \\ta\n    b\nE", s("\ta\n  b\n")),
+            ("<<~E\n  \\  a\n    b\nE", s("  a\n  b\n")),
+            // Literal heredocs
+            ("<<~'E'\n  \\ta\n    b\nE", s("\\ta\n  b\n")),
+            // Interpolation at the start of a line counts as content
+            (
+                "<<~E\n#{1}\n  a\nE",
+                i(vec![
+                    Node::new(NodeKind::Block(vec![Node::int(1)])),
+                    seg("\n  a\n"),
+                ]),
+            ),
+            // Text following an interpolation is not dedented
+            (
+                "<<~E\n  #{1}  a\n  b\nE",
+                i(vec![
+                    Node::new(NodeKind::Block(vec![Node::int(1)])),
+                    seg("  a\nb\n"),
+                ]),
+            ),
+            (
+                "<<~INDENT\n    foo\n    bar#{\n2+8\n} stuff\n  3\nINDENT",
+                i(vec![
+                    seg("  foo\n  bar"),
+                    Node::new(NodeKind::Block(vec![Node::binary_op(
+                        Node::int(2),
+                        crate::ast::BinaryOpKind::Add,
+                        Node::int(8),
+                    )])),
+                    seg(" stuff\n3\n"),
+                ]),
+            ),
+            (
+                "<<~STUFF\n  #{<<-foo * 2} bar\n  meh\n  foo\nSTUFF",
+                i(vec![
+                    Node::new(NodeKind::Block(vec![Node::binary_op(
+                        s("  meh\n"),
+                        crate::ast::BinaryOpKind::Multiply,
+                        Node::int(2),
+                    )])),
+                    seg(" bar\n"),
+                ]),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_ok!(input, expected);
+        }
+    }
+
+    #[test]
+    fn test_sequenced_here_documents() {
+        fn s(v: &str) -> Node {
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
+        }
+        fn sequence(i: Input) -> nom::IResult<Input, Vec<Node>, (Input, crate::ErrorKind)> {
+            terminated(
+                separated_list1(tag(", "), here_document),
+                tuple((char(')'), line_terminator)),
+            )(i)
+        }
+        let (i, result) =
+            sequence("<<~A, <<-B, <<C)\n  a\nA\n  b\n  B\nc\nC\nrest".into()).unwrap();
+        assert_eq!(vec![s("a\n"), s("  b\n"), s("c\n")], result);
+        assert_eq!("rest", *i);
+        assert_eq!(8, i.line());
+        // The final heredoc on a line resumes directly after its ending identifier
+        let (i, result) = sequence("<<A, <<B)\na\nA\nb\nB\n".into()).unwrap();
+        assert_eq!(vec![s("a\n"), s("b\n")], result);
+        assert_eq!("", *i);
+        assert_eq!(6, i.line());
+        // Heredocs ending the start line
+        let (i, result) =
+            separated_list1(tag(", "), here_document)("<<A, <<B\na\nA\nb\nB\nrest".into()).unwrap();
+        assert_eq!(vec![s("a\n"), s("b\n")], result);
+        assert_eq!("\nrest", *i);
+        assert_eq!(5, i.line());
+        // Heredocs that end the line of a queued heredoc
+        for source in [
+            "foo <<A, <<B\na\nA\nb\nB\n",
+            "foo <<~A, <<-'B'\n  a\nA\nb\n  B\n",
+        ]
+        .iter()
+        {
+            assert!(crate::parse(Input::new(source)).is_ok(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_nested_here_documents() {
+        fn s(v: &str) -> Node {
+            Node::new(NodeKind::Literal(Literal::String(v.to_owned())))
+        }
+        fn seg(v: &str) -> Node {
+            Node::new(NodeKind::Segment(Segment::String(v.to_owned())))
+        }
+        use_parser!(here_document);
+        assert_partial!(
+            "<<-A\n#{<<~B} x\n    b\n  B\na\n  A\n",
+            Node::new(NodeKind::Interpolated(Interpolated::String(vec![
+                Node::new(NodeKind::Block(vec![s("b\n")])),
+                seg(" x\na\n")
+            ])))
+        );
+        assert_partial!("<<A + \"#{<<B}\"\na\nA\nb\nB\n", s("a\n"));
+        assert_remaining!("<<A + \"#{<<B}\"\na\nA\nb\nB\n", " + \"#{<<B}\"");
+        let (i, _) = tuple((
+            here_document,
+            tag(" + "),
+            crate::parsers::token::literal::string::string_literal,
+        ))("<<A + \"#{<<B}\"\na\nA\nb\nB\nrest".into())
+        .unwrap();
+        assert_eq!("\nrest", *i);
+        assert_eq!(5, i.line());
+        // Heredocs that end the line of a queued heredoc
+        for source in [
+            "foo <<A, <<B\na\nA\nb\nB\n",
+            "foo <<~A, <<-'B'\n  a\nA\nb\n  B\n",
+        ]
+        .iter()
+        {
+            assert!(crate::parse(Input::new(source)).is_ok(), "{:?}", source);
+        }
+    }
+
     #[test]
     fn test_heredoc_signifier() {
         // This unit test uses a wrapped testing harness that intentionally leaks the
@@ -1,15 +1,19 @@
 use super::double::double_escape_sequence;
 use super::double::interpolated_character_sequence;
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 
 /// `%q` *non_expanded_delimited_string*
 pub(crate) fn quoted_non_expanded_literal_string(i: Input) -> StringResult {
-    preceded(tag("%q"), non_expanded_delimited_string)(i)
+    percent_construct(preceded(tag("%q"), non_expanded_delimited_string))(i)
 }
 
 /// `%` `Q`? *expanded_delimited_string*
 pub(crate) fn quoted_expanded_literal_string(i: Input) -> InterpolatableResult {
-    preceded(alt((tag("%Q"), tag("%"))), expanded_delimited_string)(i)
+    percent_construct(preceded(
+        alt((tag("%Q"), tag("%"))),
+        expanded_delimited_string,
+    ))(i)
 }
 
 /// *literal_beginning_delimiter* *non_expanded_literal_string** *literal_ending_delimiter*
@@ -73,14 +77,13 @@ fn _non_expanded_delimited_string(i: Input) -> StringResult {
 
 /// *literal_beginning_delimiter* *expanded_literal_string** *literal_ending_delimiter*
 pub(crate) fn expanded_delimited_string(i: Input) -> InterpolatableResult {
-    map(
-        wrap_quote_delimiter(delimited(
-            literal_beginning_delimiter,
-            many0(expanded_literal_string),
-            literal_ending_delimiter,
-        )),
-        |vecs| Interpolatable::from(vecs.into_iter().flatten().collect::<Vec<Segment>>()),
-    )(i)
+    wrap_quote_delimiter(delimited(
+        literal_beginning_delimiter,
+        interpolatable(map(many0(expanded_literal_string), |vecs| {
+            Interpolatable::from(vecs.into_iter().flatten().collect::<Vec<Segment>>())
+        })),
+        literal_ending_delimiter,
+    ))(i)
 }
 
 /// *literal_beginning_delimiter* *expanded_literal_string** *literal_ending_delimiter*
@@ -130,7 +133,7 @@ pub(crate) fn expanded_literal_character(i: Input) -> SegmentResult {
             preceded(peek(not(char('#'))), non_escaped_literal_character),
             |c| Segment::Char(c),
         ),
-        map(double_escape_sequence, |s| Segment::String(s)),
+        double_escape_sequence,
         map(interpolated_character_sequence, |e| Segment::expr(e)),
         map(terminated(char('#'), peek(none_of("$@{"))), |c| {
             Segment::Char(c)
@@ -256,11 +259,11 @@ mod tests {
         assert_interpolated!(
             "%[foo#@hi [bar] [#{%Q((hello))}]]",
             vec![
-                Node::Segment(Segment::String("foo".to_owned())),
+                Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
                 Node::ident("@hi", IdentifierKind::InstanceVariable),
-                Node::Segment(Segment::String(" [bar] [".to_owned())),
-                Node::Block(vec![Node::literal_string("(hello)")]),
-                Node::Segment(Segment::String("]".to_owned())),
+                Node::new(NodeKind::Segment(Segment::String(" [bar] [".to_owned()))),
+                Node::new(NodeKind::Block(vec![Node::literal_string("(hello)")])),
+                Node::new(NodeKind::Segment(Segment::String("]".to_owned()))),
             ]
         );
     }
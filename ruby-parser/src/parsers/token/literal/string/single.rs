@@ -1,15 +1,20 @@
+use crate::lexer::lexical::char;
 use crate::lexer::*;
 
 /// `'` *single_quoted_string_character** `'`
 pub(crate) fn single_quoted_string(i: Input) -> StringResult {
-    let (i, _) = char('\'')(i)?;
-    let (i, contents) = many0(single_quoted_string_character)(i)?;
-    let (i, _) = char('\'')(i)?;
-    let mut string = String::new();
-    for s in contents {
-        string.push_str(&s);
-    }
-    Ok((i, string))
+    map(
+        construct(
+            "'",
+            "'",
+            delimited(
+                char('\''),
+                many0(single_quoted_string_character),
+                char('\''),
+            ),
+        ),
+        |contents| contents.concat(),
+    )(i)
 }
 
 /// *single_quoted_string_non_escaped_character* | *single_quoted_escape_sequence*
@@ -1,37 +1,48 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 use crate::parsers::token::identifier::identifier;
 use crate::parsers::token::keyword::keyword;
-use crate::parsers::token::literal::string::double::double_quoted_string;
+use crate::parsers::token::literal::string::double::valid_double_quoted_string;
 use crate::parsers::token::literal::string::quoted::non_expanded_delimited_string;
 use crate::parsers::token::literal::string::single::single_quoted_string;
 use crate::parsers::token::operator::operator;
 
 /// *symbol_literal* | *dynamic_symbol*
 pub(crate) fn symbol(i: Input) -> NodeResult {
-    alt((symbol_literal, dynamic_symbol))(i)
+    token(
+        |_| TokenKind::Literal(LiteralKind::Symbol),
+        spanned(alt((symbol_literal, dynamic_symbol))),
+    )(i)
 }
 
 /// `:` *symbol_name*
 pub(crate) fn symbol_literal(i: Input) -> NodeResult {
-    map(preceded(char(':'), symbol_name), |s| {
-        Node::Literal(Literal::Symbol(s.to_string()))
-    })(i)
+    spanned(map(preceded(char(':'), symbol_name), |s| {
+        Node::new(NodeKind::Literal(Literal::Symbol(s.to_string())))
+    }))(i)
 }
 
 /// `:` *single_quoted_string*  | `:` *double_quoted_string* | `%s` *literal_beginning_delimiter* *non_expanded_literal_string** *literal_ending_delimiter*
 pub(crate) fn dynamic_symbol(i: Input) -> NodeResult {
-    alt((
+    spanned(alt((
         map(tuple((char(':'), single_quoted_string)), |t| {
-            Node::Literal(Literal::Symbol(t.1))
+            Node::new(NodeKind::Literal(Literal::Symbol(t.1)))
         }),
-        map(tuple((char(':'), double_quoted_string)), |t| match t.1 {
-            Interpolatable::String(s) => Node::Literal(Literal::Symbol(s)),
-            Interpolatable::Interpolated(vec) => Node::Interpolated(Interpolated::Symbol(vec)),
-        }),
-        map(tuple((tag("%s"), non_expanded_delimited_string)), |t| {
-            Node::Literal(Literal::Symbol(t.1))
-        }),
-    ))(i)
+        map(
+            tuple((char(':'), valid_double_quoted_string)),
+            |t| match t.1 {
+                Interpolatable::String(s) => Node::new(NodeKind::Literal(Literal::Symbol(s))),
+                Interpolatable::Bytes(_) => unreachable!(),
+                Interpolatable::Interpolated(vec) => {
+                    Node::new(NodeKind::Interpolated(Interpolated::Symbol(vec)))
+                }
+            },
+        ),
+        map(
+            percent_construct(tuple((tag("%s"), non_expanded_delimited_string))),
+            |t| Node::new(NodeKind::Literal(Literal::Symbol(t.1))),
+        ),
+    )))(i)
 }
 
 /// *identifier* | *operator* | *keyword*
@@ -45,12 +56,18 @@ mod tests {
 
     macro_rules! assert_symbol {
         ($a:expr, $b:expr) => {
-            assert_ok!($a, Node::Literal(Literal::Symbol($b.to_owned())))
+            assert_ok!(
+                $a,
+                Node::new(NodeKind::Literal(Literal::Symbol($b.to_owned())))
+            )
         };
     }
     macro_rules! assert_interpolated {
         ($a:expr, $b:expr) => {
-            assert_ok!($a, Node::Interpolated(Interpolated::Symbol($b)))
+            assert_ok!(
+                $a,
+                Node::new(NodeKind::Interpolated(Interpolated::Symbol($b)))
+            )
         };
     }
 
@@ -91,6 +108,7 @@ mod tests {
         assert_err!(":'");
         assert_err!(":'\"");
         assert_err!(":'foo bar''");
+        assert_err!(":\"\\xff\"");
         // Success cases
         assert_symbol!(":''", "");
         assert_symbol!(":\"\"", "");
@@ -102,7 +120,7 @@ mod tests {
         assert_interpolated!(
             ":\"foo#$bar\"",
             vec![
-                Node::Segment(Segment::String("foo".to_owned())),
+                Node::new(NodeKind::Segment(Segment::String("foo".to_owned()))),
                 Node::ident("$bar", IdentifierKind::GlobalVariable)
             ]
         );
@@ -1,56 +1,66 @@
+use crate::lexer::lexical::{char, tag};
 use crate::lexer::*;
 
 /// `!` | `!=` | `!~` | `&&` | `||` | *operator_method_name* | `=` | *assignment_operator*
 pub(crate) fn operator(i: Input) -> LexResult {
-    recognize(alt((
-        assignment_operator,
-        operator_method_name,
-        tag("="),
-        tag("||"),
-        tag("&&"),
-        tag("!~"),
-        tag("!="),
-        tag("!"),
-    )))(i)
+    token(
+        |_| TokenKind::Operator,
+        recognize(alt((
+            assignment_operator,
+            operator_method_name,
+            tag("="),
+            tag("||"),
+            tag("&&"),
+            tag("!~"),
+            tag("!="),
+            tag("!"),
+        ))),
+    )(i)
 }
 
 /// `^` | `&` | `|` | `<=>` | `==` | `===` | `=~` | `>` | `>=` | `<` | `<=` | `<<` | `>>` | `+` | `-` | `*` | `/` | `%` | `**` | `~` | `+@` | `-@` | `[]` | `[]=`
 pub(crate) fn operator_method_name(i: Input) -> LexResult {
-    recognize(alt((
-        alt((
-            tag("<=>"),
-            tag("==="),
-            tag("[]="),
-            tag("=="),
-            tag("=~"),
-            tag(">="),
-            tag(">>"),
-            tag("<="),
-            tag("<<"),
-            tag("**"),
-            tag(">"),
-            tag("<"),
-        )),
-        alt((
-            tag("^"),
-            tag("&"),
-            tag("|"),
-            tag("+"),
-            tag("-"),
-            tag("*"),
-            tag("/"),
-            tag("%"),
-            tag("~"),
-            tag("+@"),
-            tag("-@"),
-            tag("[]"),
-        )),
-    )))(i)
+    token(
+        |_| TokenKind::Operator,
+        recognize(alt((
+            alt((
+                tag("<=>"),
+                tag("==="),
+                tag("[]="),
+                tag("=="),
+                tag("=~"),
+                tag(">="),
+                tag(">>"),
+                tag("<="),
+                tag("<<"),
+                tag("**"),
+                tag(">"),
+                tag("<"),
+            )),
+            alt((
+                tag("^"),
+                tag("&"),
+                tag("|"),
+                tag("+"),
+                tag("-"),
+                tag("*"),
+                tag("/"),
+                tag("%"),
+                tag("~"),
+                tag("+@"),
+                tag("-@"),
+                tag("[]"),
+            )),
+        ))),
+    )(i)
 }
 
 /// *assignment_operator_name* `=`
 pub(crate) fn assignment_operator(i: Input) -> LexResult {
-    recognize(tuple((assignment_operator_name, char('='))))(i)
+    token(
+        |_| TokenKind::Operator,
+        recognize(tuple((assignment_operator_name, char('=')))),
+    )(i)
 }
 
 /// `&&` | `||` | `^` | `&` | `|` | `<<` | `>>` | `+` | `-` | `%` | `/` | `**`
@@ -0,0 +1,174 @@
+//! Parses programs while recovering from syntax errors
+use crate::ast::{MagicComments, Node, NodeKind, Program, Shebang, Span};
+use crate::error::{self, Failures, ParseError};
+use crate::lexer::*;
+use crate::parsers::program::{
+    end_of_program_marker, program_header, separator_list, terminated_toplevel_statements,
+    toplevel_statement, ws0, ToplevelStatement,
+};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ops::Range;
+
+/// The parts of a program that parsing has gotten past
+///
+/// Parsing resumes from the first top-level statement that isn't kept, so the statements before it
+/// are only parsed once.
+#[derive(Default)]
+struct Recovered {
+    header: Option<(Option<Shebang>, MagicComments)>,
+    begin_blocks: Vec<Node>,
+    body: Vec<Node>,
+}
+
+impl Recovered {
+    fn keep(&mut self, statement: ToplevelStatement) {
+        match statement {
+            ToplevelStatement::BeginBlock(block) => self.begin_blocks.push(block),
+            ToplevelStatement::Statement(node) => self.body.push(node),
+        }
+    }
+}
+
+/// Parses a ruby program, skipping the statements that can't be parsed
+///
+/// Each attempt parses the program from where the previous one resumed. When an attempt fails, the
+/// region of source around the error is skipped by the next attempt.
+pub(crate) fn parse_with_recovery(i: Input) -> (Program, Vec<ParseError>) {
+    let (source, file, partial) = (*i, i.metadata.file, i.metadata.partial);
+    let failures = RefCell::new(Failures::default());
+    let tokens = RefCell::new(TokenLog::default());
+    let memo = RefCell::new(Memo::default());
+    let metadata = Metadata {
+        file,
+        skip_leading_garbage: i.metadata.skip_leading_garbage,
+        partial,
+        encoding: i.metadata.encoding,
+        failures: Some(&failures),
+        tokens: Some(&tokens),
+        memo: Some(&memo),
+        ..Metadata::default()
+    };
+    let input = Input::new_with_pos_and_meta(source, i.offset(), i.line(), i.char(), metadata);
+    let end = nom::InputTake::take_split(&input, source.len())
+        .0
+        .position();
+    let mut start = input.position();
+    let mut resume = input;
+    let mut recovered = Recovered::default();
+    let mut skipped: Vec<Range<usize>> = vec![];
+    let mut errors: Vec<ParseError> = vec![];
+    let (end, data) = loop {
+        *failures.borrow_mut() = Failures::skipping(skipped.clone());
+        *memo.borrow_mut() = Memo::default();
+        let position = match parse_from(&mut resume, &mut start, &mut recovered) {
+            Ok((_, parsed)) => break parsed,
+            Err(e) => error::failure_position(&resume, e),
+        };
+        // Comments after where parsing resumes will be parsed again
+        tokens
+            .borrow_mut()
+            .comments
+            .retain(|offset, _| *offset < resume.offset());
+        let failed = failures.replace(Failures::default());
+        let statements = failed.failed_statements();
+        let e = ParseError::new(source, file, partial, failed, position);
+        let (offset, from) = (e.position.offset, resume.offset());
+        let floor = from.max(skipped.last().map_or(0, |region| region.end));
+        let resynchronized = |(start, closer): (usize, Option<Cow<str>>)| {
+            error::resynchronize(source, start, offset, closer.as_deref()).map(|end| start..end)
+        };
+        match skipped
+            .last_mut()
+            .filter(|region| offset <= region.end && region.end >= from)
+        {
+            // Skipping the last region didn't get parsing past it, so the statement that contains
+            // it is skipped instead
+            Some(region) => {
+                let outer = statements
+                    .into_iter()
+                    .filter(|(start, _)| (from..region.start).contains(start))
+                    .find_map(resynchronized);
+                match outer {
+                    Some(outer) => *region = outer.start..outer.end.max(region.end),
+                    None if region.end < source.len() => region.end = source.len(),
+                    // Nothing more could be recovered
+                    None => {
+                        let span = Span::new(resume.position(), end);
+                        let error = Node::with_span(NodeKind::Error((*resume).to_owned()), span);
+                        recovered.body.push(error);
+                        skipped.push(from..source.len());
+                        break (end, None);
+                    }
+                }
+            }
+            // The innermost statement that can be skipped is, up to where parsing can resume
+            None => {
+                let region = statements
+                    .into_iter()
+                    .filter(|(start, _)| *start >= floor)
+                    .filter_map(resynchronized)
+                    .find(|region| !region.is_empty())
+                    .unwrap_or(floor..source.len());
+                skipped.push(region);
+                errors.push(e);
+            }
+        }
+    };
+    // Constructs outside of the skipped regions were closed after all
+    for e in errors.iter_mut() {
+        e.closed(source, |position| {
+            !skipped
+                .iter()
+                .any(|region| region.contains(&position.offset))
+        });
+    }
+    let encoding = resume.metadata.encoding;
+    let (shebang, magic_comments) = recovered.header.unwrap_or_default();
+    let program = Program {
+        program: Node::with_span(NodeKind::Block(recovered.body), Span::new(start, end)),
+        begin_blocks: recovered.begin_blocks,
+        shebang,
+        magic_comments,
+        comments: std::mem::take(&mut tokens.borrow_mut().comments)
+            .into_values()
+            .collect(),
+        encoding,
+        data,
+    };
+    (program, errors)
+}
+
+/// Parses the rest of a program from `i`, returning where its statements end and its data
+///
+/// The header and the top-level statements that are followed by a separator are kept as they are
+/// parsed, and `i` is advanced past them. `start` is set to where the program's statements begin.
+fn parse_from<'a>(
+    i: &mut Input<'a>,
+    start: &mut Position,
+    recovered: &mut Recovered,
+) -> Parsed<'a, (Position, Option<String>)> {
+    if recovered.header.is_none() {
+        let (j, header) = program_header(i.clone())?;
+        recovered.header = Some(header);
+        *start = j.position();
+        *i = j;
+    }
+    let (j, statements) = terminated_toplevel_statements(i.clone())?;
+    statements
+        .into_iter()
+        .for_each(|statement| recovered.keep(statement));
+    *i = j;
+    let (j, last) = opt(toplevel_statement)(i.clone())?;
+    let (j, _) = tuple((opt(separator_list), ws0))(j)?;
+    let end = j.position();
+    let (j, data) = nom::combinator::all_consuming(opt(end_of_program_marker))(j)?;
+    if let Some(statement) = last {
+        recovered.keep(statement);
+    }
+    let data = data.and_then(|data| match data.kind {
+        NodeKind::EndOfProgram(data) => Some(data),
+        _ => None,
+    });
+    Ok((j, (end, data)))
+}
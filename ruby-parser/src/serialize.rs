@@ -0,0 +1,299 @@
+//! Provides stable encodings of the syntax tree for caching and for tools written in other languages
+//!
+//! Requires the `serde` feature, which also implements `Serialize` and `Deserialize` for every
+//! type of the syntax tree.
+//!
+//! ## JSON schema
+//!
+//! Every type is serialized under the names of its Rust fields and variants:
+//!
+//! * A program is an object with the fields of `ast::Program` (`program`, `begin_blocks`,
+//!   `shebang`, `magic_comments`, `comments`, `encoding` and `data`).
+//! * A node is an object with a `kind` and a `span`. The span holds a `start` and an (exclusive)
+//!   `end` position, each of which is an object with an `offset` (in bytes), a `line` and a `char`
+//!   (both starting at 1). Synthesized nodes have every position set to 0.
+//! * Enums are externally tagged: a variant without data is a string (`"Nil"`), and any other
+//!   variant is an object whose only key names the variant (`{"Block": [...]}`,
+//!   `{"Literal": {"Integer": 1}}`, `{"BinaryOp": {"op": "Add", "lhs": {...}, "rhs": {...}}}`).
+//! * Integers are numbers, except those beyond ±(2^53 - 1) (which can't be represented exactly by
+//!   a double) are decimal strings. Integer literals beyond the range of 64 bits are
+//!   `BigInteger`s, whose value is always a string of decimal digits
+//!   (`{"Literal": {"BigInteger": "-99999999999999999999"}}`).
+//! * Floats are numbers, except `NaN`, `Infinity` and `-Infinity` are written as those strings.
+//! * Byte strings (`Bytes`, `CharacterBytes`, `CommandBytes` and `Segment::Bytes`) are arrays of
+//!   numbers.
+//!
+//! ## Binary encoding
+//!
+//! The binary encoding begins with the bytes `RBAST` and the little-endian `u16` format version,
+//! followed by the program encoded by bincode. Decoding rejects any other format version, so
+//! cached trees are invalidated whenever the format changes.
+//!
+//! Changes to either encoding are accompanied by an increment of `FORMAT_VERSION`.
+
+use crate::ast::Program;
+use std::fmt;
+
+/// The version of the syntax tree's encodings
+pub const FORMAT_VERSION: u16 = 3;
+
+/// Identifies the binary encoding
+const MAGIC: &[u8] = b"RBAST";
+
+/// Serializes a program as JSON
+pub fn to_json(program: &Program) -> serde_json::Result<String> {
+    serde_json::to_string(program)
+}
+
+/// Deserializes a program from JSON
+pub fn from_json(json: &str) -> serde_json::Result<Program> {
+    serde_json::from_str(json)
+}
+
+/// Serializes a program with the versioned binary encoding
+pub fn to_bytes(program: &Program) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    // Every type of the tree has a known length, so serializing into a vector can't fail
+    bincode::serialize_into(&mut bytes, program).expect("syntax trees are always serializable");
+    bytes
+}
+
+/// Deserializes a program from the versioned binary encoding
+pub fn from_bytes(bytes: &[u8]) -> Result<Program, DecodeError> {
+    let bytes = bytes
+        .strip_prefix(MAGIC)
+        .ok_or(DecodeError::InvalidHeader)?;
+    if bytes.len() < 2 {
+        return Err(DecodeError::InvalidHeader);
+    }
+    let version = u16::from_le_bytes([bytes[0], bytes[1]]);
+    if version != FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    bincode::deserialize(&bytes[2..]).map_err(DecodeError::Malformed)
+}
+
+/// Describes why a binary encoded program could not be decoded
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The input does not begin with the encoding's header
+    InvalidHeader,
+    /// The input was encoded with a different version of the format
+    UnsupportedVersion(u16),
+    /// The encoded program is corrupt
+    Malformed(bincode::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "not an encoded syntax tree"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {} (expected {})",
+                version, FORMAT_VERSION
+            ),
+            Self::Malformed(e) => write!(f, "malformed syntax tree: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Serializes integers, writing those that a double can't represent exactly as strings in
+/// human-readable formats
+pub(crate) mod integer {
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::Serializer;
+    use std::convert::TryFrom;
+    use std::fmt;
+
+    /// The largest magnitude up to which every integer is exactly representable by a double
+    const MAX_SAFE: i64 = (1 << 53) - 1;
+
+    pub(crate) fn serialize<S: Serializer>(v: &isize, s: S) -> Result<S::Ok, S::Error> {
+        let v = *v as i64;
+        if s.is_human_readable() && !(-MAX_SAFE..=MAX_SAFE).contains(&v) {
+            s.serialize_str(&v.to_string())
+        } else {
+            s.serialize_i64(v)
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<isize, D::Error> {
+        if d.is_human_readable() {
+            d.deserialize_any(IntegerVisitor)
+        } else {
+            d.deserialize_i64(IntegerVisitor)
+        }
+    }
+
+    struct IntegerVisitor;
+
+    impl<'de> Visitor<'de> for IntegerVisitor {
+        type Value = isize;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "an integer or a string of decimal digits")
+        }
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<isize, E> {
+            isize::try_from(v).map_err(|_| E::custom(format!("integer {} is out of range", v)))
+        }
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<isize, E> {
+            isize::try_from(v).map_err(|_| E::custom(format!("integer {} is out of range", v)))
+        }
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<isize, E> {
+            v.parse()
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
+
+/// Serializes floats, writing the non-finite values as strings in human-readable formats
+pub(crate) mod float {
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::Serializer;
+    use std::fmt;
+
+    pub(crate) fn serialize<S: Serializer>(v: &f64, s: S) -> Result<S::Ok, S::Error> {
+        if !s.is_human_readable() || v.is_finite() {
+            s.serialize_f64(*v)
+        } else if v.is_nan() {
+            s.serialize_str("NaN")
+        } else if *v > 0.0 {
+            s.serialize_str("Infinity")
+        } else {
+            s.serialize_str("-Infinity")
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+        if d.is_human_readable() {
+            d.deserialize_any(FloatVisitor)
+        } else {
+            d.deserialize_f64(FloatVisitor)
+        }
+    }
+
+    struct FloatVisitor;
+
+    impl<'de> Visitor<'de> for FloatVisitor {
+        type Value = f64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a number, \"NaN\", \"Infinity\" or \"-Infinity\"")
+        }
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+            Ok(v)
+        }
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+            match v {
+                "NaN" => Ok(f64::NAN),
+                "Infinity" => Ok(f64::INFINITY),
+                "-Infinity" => Ok(f64::NEG_INFINITY),
+                _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::*;
+    use crate::lexer::Input;
+
+    fn program(nodes: Vec<Node>) -> Program {
+        let mut program = crate::parse(Input::new("")).unwrap();
+        program.program = Node::new(NodeKind::Block(nodes));
+        program
+    }
+
+    #[test]
+    fn test_json() {
+        let parsed = crate::parse(Input::new("1 + a")).unwrap();
+        let json = to_json(&parsed).unwrap();
+        assert!(json.starts_with(
+            r#"{"program":{"kind":{"Block":[{"kind":{"BinaryOp":{"op":"Add","lhs":{"kind":{"Literal":{"Integer":1}},"span":{"start":{"offset":0,"line":1,"char":1},"end":{"offset":1,"line":1,"char":2}}},"#
+        ));
+        let decoded = from_json(&json).unwrap();
+        assert_eq!(decoded, parsed);
+        assert_eq!(decoded.program.span, parsed.program.span);
+    }
+
+    #[test]
+    fn test_lossless_numbers() {
+        let numbers = program(vec![
+            Node::float(f64::INFINITY),
+            Node::float(f64::NEG_INFINITY),
+            Node::float(-0.1),
+            Node::int(isize::MAX),
+            Node::int(-(1 << 53)),
+            Node::int((1 << 53) - 1),
+        ]);
+        let json = to_json(&numbers).unwrap();
+        assert!(json.contains(r#"{"Float":"Infinity"}"#));
+        assert!(json.contains(r#"{"Float":"-Infinity"}"#));
+        assert!(json.contains(r#"{"Integer":"9223372036854775807"}"#));
+        assert!(json.contains(r#"{"Integer":"-9007199254740992"}"#));
+        assert!(json.contains(r#"{"Integer":9007199254740991}"#));
+        assert_eq!(from_json(&json).unwrap(), numbers);
+        assert_eq!(from_bytes(&to_bytes(&numbers)).unwrap(), numbers);
+        // Integers beyond the range of an isize keep every digit
+        let parsed = crate::parse(Input::new(
+            "[99999999999999999999999, -0x7fffffffffffffffff]",
+        ))
+        .unwrap();
+        let json = to_json(&parsed).unwrap();
+        assert!(json.contains(r#"{"BigInteger":"99999999999999999999999"}"#));
+        assert!(json.contains(r#"{"BigInteger":"-2361183241434822606847"}"#));
+        assert_eq!(from_json(&json).unwrap(), parsed);
+        assert_eq!(from_bytes(&to_bytes(&parsed)).unwrap(), parsed);
+        // NaN never compares equal, so it's checked separately
+        for decoded in [
+            from_json(&to_json(&program(vec![Node::float(f64::NAN)])).unwrap()).unwrap(),
+            from_bytes(&to_bytes(&program(vec![Node::float(f64::NAN)]))).unwrap(),
+        ]
+        .iter()
+        {
+            match &decoded.program.kind {
+                NodeKind::Block(v) => {
+                    assert!(matches!(v[0].kind, NodeKind::Literal(Literal::Float(f)) if f.is_nan()))
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        let parsed = crate::parse(Input::new("\"\\xff\" if a")).unwrap();
+        let bytes = to_bytes(&parsed);
+        assert!(bytes.starts_with(b"RBAST\x03\x00"));
+        let decoded = from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, parsed);
+        assert_eq!(decoded.program.span, parsed.program.span);
+        // Headers and versions are verified
+        assert!(matches!(
+            from_bytes(b"RBAS"),
+            Err(DecodeError::InvalidHeader)
+        ));
+        let mut outdated = bytes.clone();
+        outdated[5] = 0;
+        assert!(matches!(
+            from_bytes(&outdated),
+            Err(DecodeError::UnsupportedVersion(0))
+        ));
+        assert!(matches!(
+            from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Malformed(_))
+        ));
+    }
+}
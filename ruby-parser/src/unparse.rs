@@ -0,0 +1,1320 @@
+//! Provides a printer that renders syntax trees back to Ruby source
+//!
+//! The printed source re-parses to an equal tree: for any program `p` produced by `parse`,
+//! `parse(unparse(p))` has the same `program` and `begin_blocks`. This doesn't hold for:
+//!
+//! - `NodeKind::Placeholder` nodes, which are only printed by `program_with_source` (as the source
+//!   they were parsed from). The parser currently produces them for constructs it doesn't build
+//!   nodes for yet (such as method calls, assignments and definitions).
+//! - `__LINE__` nodes that are printed on a different line than the one they hold, which are
+//!   printed as that line's number.
+//! - Non-finite floats, which have no literal and are printed as `Float::INFINITY`,
+//!   `-Float::INFINITY` or `Float::NAN`.
+//!
+//! Parentheses are inserted wherever a node binds more loosely than its position requires (such
+//! as a binary operation beneath `!`, or a modifier conditional within an expression). Since
+//! parentheses are parsed as `NodeKind::Block` nodes, trees that were built by hand may gain blocks
+//! when re-parsed, after which printing and parsing reaches a fixed point. The parser accepts
+//! chained equality operators (`a == b == c`), which Ruby does not, so those are printed with
+//! parentheses.
+//!
+//! Layout is not preserved: bodies are indented by two spaces, heredocs and single-quoted strings
+//! are printed as double-quoted strings, and ordinary comments are omitted (magic comments and the
+//! shebang line are kept). Placeholders keep the layout of their source, and the bodies of the
+//! heredocs they start follow the line they're printed on.
+
+use crate::ast::*;
+use crate::lexer::{Input, LiteralKind, Memo, Segment, Token, TokenKind, Tokens};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Describes a node that has no representation in Ruby source
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnparseError {
+    /// The node that could not be printed
+    pub node: Box<Node>,
+    pub reason: &'static str,
+}
+
+impl UnparseError {
+    fn new(node: &Node, reason: &'static str) -> Self {
+        Self {
+            node: Box::new(node.clone()),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for UnparseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot unparse node: {}", self.reason)
+    }
+}
+
+impl std::error::Error for UnparseError {}
+
+type Printed = Result<(), UnparseError>;
+
+/// Renders a program, including its shebang line, magic comments, `BEGIN` blocks and data
+pub fn program(program: &Program) -> Result<String, UnparseError> {
+    Printer::default().program(program)
+}
+
+/// Renders a program that was parsed from `source`, printing its placeholder nodes as the source
+/// they were parsed from
+pub fn program_with_source(program: &Program, source: &str) -> Result<String, UnparseError> {
+    let memo = RefCell::new(Memo::default());
+    let mut input = Input::new(source);
+    input.metadata.memo = Some(&memo);
+    let p = Printer {
+        source: Some(source),
+        tokens: SourceToken::lex(Tokens::new(input)),
+        ..Printer::default()
+    };
+    p.program(program)
+}
+
+/// A token of the source that placeholder nodes are printed from
+struct SourceToken<'a> {
+    token: Token<'a>,
+    /// Where the heredoc that a heredoc body belongs to was started
+    heredoc: Option<usize>,
+}
+
+impl<'a> SourceToken<'a> {
+    /// Collects the tokens of a program, matching heredoc bodies with the heredocs they belong to
+    fn lex(tokens: Tokens<'a>) -> Vec<Self> {
+        let mut started = VecDeque::new();
+        let mut bodies = false;
+        tokens
+            .map(|token| {
+                let mut heredoc = None;
+                match token.kind {
+                    TokenKind::Literal(LiteralKind::Heredoc) => {
+                        started.push_back(token.span.start.offset)
+                    }
+                    TokenKind::HeredocBody => {
+                        heredoc = started.pop_front();
+                        bodies = true;
+                    }
+                    TokenKind::Newline => {}
+                    // The bodies of the heredocs started on a line all follow it
+                    _ if bodies => {
+                        started.clear();
+                        bodies = false;
+                    }
+                    _ => {}
+                }
+                Self { token, heredoc }
+            })
+            .collect()
+    }
+}
+
+/// Renders a single node as a statement
+pub fn node(node: &Node) -> Result<String, UnparseError> {
+    let mut p = Printer::default();
+    p.statement(node)?;
+    Ok(p.out)
+}
+
+/// How tightly a construct binds, from loosest to tightest
+#[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
+enum Precedence {
+    /// Modifier conditionals and loops, and `rescue` modifiers
+    Statement,
+    /// `alias`, `undef` and `END { }`
+    SimpleStatement,
+    /// `and` | `or`
+    Keyword,
+    /// `not`, and jumps that are given arguments
+    Not,
+    /// `? :`
+    Ternary,
+    /// `..` | `...`
+    Range,
+    /// `||`
+    Or,
+    /// `&&`
+    And,
+    /// `<=>` | `==` | `===` | `!=` | `=~` | `!~`
+    Equality,
+    /// `>` | `>=` | `<` | `<=`
+    Relational,
+    /// `|` | `^`
+    BitOr,
+    /// `&`
+    BitAnd,
+    /// `<<` | `>>`
+    Shift,
+    /// `+` | `-`
+    Additive,
+    /// `*` | `/` | `%`
+    Multiplicative,
+    /// unary `-`
+    Negative,
+    /// `**`
+    Power,
+    /// `!` | `~` | unary `+`
+    Unary,
+    Primary,
+}
+
+impl Precedence {
+    /// Returns the precedence of a binary operator
+    fn of_binary_op(op: &BinaryOpKind) -> Self {
+        match op {
+            BinaryOpKind::Compare
+            | BinaryOpKind::Equal
+            | BinaryOpKind::CaseEqual
+            | BinaryOpKind::NotEqual
+            | BinaryOpKind::RegexMatch
+            | BinaryOpKind::NotRegexMatch => Self::Equality,
+            BinaryOpKind::GreaterThan
+            | BinaryOpKind::GreaterEqual
+            | BinaryOpKind::LessThan
+            | BinaryOpKind::LessEqual => Self::Relational,
+            BinaryOpKind::BitOr | BinaryOpKind::BitXor => Self::BitOr,
+            BinaryOpKind::BitAnd => Self::BitAnd,
+            BinaryOpKind::ShiftLeft | BinaryOpKind::ShiftRight => Self::Shift,
+            BinaryOpKind::Add | BinaryOpKind::Subtract => Self::Additive,
+            BinaryOpKind::Multiply | BinaryOpKind::Divide | BinaryOpKind::Modulus => {
+                Self::Multiplicative
+            }
+            BinaryOpKind::Power => Self::Power,
+        }
+    }
+
+    /// Returns the next tighter level of a left-associative binary operator
+    fn tighter(self) -> Self {
+        match self {
+            Self::Or => Self::And,
+            Self::And => Self::Equality,
+            Self::Equality => Self::Relational,
+            Self::Relational => Self::BitOr,
+            Self::BitOr => Self::BitAnd,
+            Self::BitAnd => Self::Shift,
+            Self::Shift => Self::Additive,
+            Self::Additive => Self::Multiplicative,
+            Self::Multiplicative => Self::Negative,
+            _ => Self::Primary,
+        }
+    }
+
+    /// Returns the precedence of the construct a node will be printed as
+    fn of(node: &Node) -> Self {
+        match &node.kind {
+            NodeKind::Conditional(v) => match v.kind {
+                ConditionalKind::ModifyingIf | ConditionalKind::ModifyingUnless => Self::Statement,
+                ConditionalKind::Ternary => Self::Ternary,
+                _ => Self::Primary,
+            },
+            NodeKind::Loop(v) => match v.kind {
+                LoopKind::For | LoopKind::While | LoopKind::Until => Self::Primary,
+                _ => Self::Statement,
+            },
+            NodeKind::Rescue(v) if is_rescue_modifier(v) => Self::Statement,
+            NodeKind::Alias(_) | NodeKind::Undef(_) | NodeKind::EndBlock(_) => {
+                Self::SimpleStatement
+            }
+            NodeKind::LogicalAnd(v) if !symbolic(&v.first, &v.second, Self::And) => Self::Keyword,
+            NodeKind::LogicalAnd(_) => Self::And,
+            NodeKind::LogicalOr(v) if !symbolic(&v.first, &v.second, Self::Or) => Self::Keyword,
+            NodeKind::LogicalOr(_) => Self::Or,
+            NodeKind::LogicalNot(_) => Self::Not,
+            NodeKind::Return(v) | NodeKind::Break(v) | NodeKind::Next(v) if !v.is_empty() => {
+                Self::Not
+            }
+            NodeKind::Ranged(_) => Self::Range,
+            NodeKind::BinaryOp(v) => Self::of_binary_op(&v.op),
+            NodeKind::UnaryOp(UnaryOp {
+                op: UnaryOpKind::Negative,
+                ..
+            }) => Self::Negative,
+            NodeKind::UnaryOp(_) => Self::Unary,
+            _ => Self::Primary,
+        }
+    }
+}
+
+/// Whether a logical operation's operands permit the symbolic (`&&` / `||`) operator
+fn symbolic(first: &Node, second: &Node, level: Precedence) -> bool {
+    Precedence::of(first) >= level && Precedence::of(second) >= level.tighter()
+}
+
+/// Whether a rescue can be written as a `rescue` modifier
+fn is_rescue_modifier(v: &Rescue) -> bool {
+    match v.rescue.as_slice() {
+        [clause] => {
+            clause.exceptions.is_empty()
+                && clause.assigned_to.kind == NodeKind::None
+                && v.otherwise.kind == NodeKind::None
+        }
+        _ => false,
+    }
+}
+
+/// Returns the statements of a body (which is a block unless it was built by hand)
+fn statements(body: &Node) -> Vec<&Node> {
+    match &body.kind {
+        NodeKind::Block(v) => v.iter().collect(),
+        _ => vec![body],
+    }
+}
+
+#[derive(Default)]
+struct Printer<'a> {
+    out: String,
+    indent: usize,
+    /// The source the tree was parsed from, which placeholder nodes are printed from
+    source: Option<&'a str>,
+    tokens: Vec<SourceToken<'a>>,
+    /// The bodies of the heredocs started on the line being printed
+    heredocs: Vec<&'a str>,
+}
+
+impl<'a> Printer<'a> {
+    /// Prints a program, including its shebang line, magic comments, `BEGIN` blocks and data
+    fn program(mut self, program: &Program) -> Result<String, UnparseError> {
+        if let Some(shebang) = &program.shebang {
+            self.out.push_str(&format!("#!{}\n", shebang.line));
+        }
+        let magic = &program.magic_comments;
+        if let Some(encoding) = &magic.encoding {
+            self.out.push_str(&format!("# encoding: {}\n", encoding));
+        }
+        if let Some(flag) = magic.frozen_string_literal {
+            self.out
+                .push_str(&format!("# frozen_string_literal: {}\n", flag));
+        }
+        if let Some(flag) = magic.warn_indent {
+            self.out.push_str(&format!("# warn_indent: {}\n", flag));
+        }
+        if let Some(value) = magic.shareable_constant_value {
+            let value = match value {
+                ShareableConstantValue::None => "none",
+                ShareableConstantValue::Literal => "literal",
+                ShareableConstantValue::ExperimentalEverything => "experimental_everything",
+                ShareableConstantValue::ExperimentalCopy => "experimental_copy",
+            };
+            self.out
+                .push_str(&format!("# shareable_constant_value: {}\n", value));
+        }
+        for block in &program.begin_blocks {
+            self.out.push_str("BEGIN {");
+            self.body(block)?;
+            self.out.push_str("\n}\n");
+        }
+        for statement in statements(&program.program) {
+            self.statement(statement)?;
+            self.end_line();
+        }
+        if let Some(data) = &program.data {
+            self.out.push_str("__END__\n");
+            self.out.push_str(data);
+        }
+        Ok(self.out)
+    }
+
+    /// Returns the line that printing has reached
+    fn line(&self) -> usize {
+        self.out.matches('\n').count() + 1
+    }
+
+    /// Ends the line being printed, following it with the bodies of the heredocs started on it
+    fn end_line(&mut self) {
+        self.out.push('\n');
+        for body in std::mem::take(&mut self.heredocs) {
+            self.out.push_str(body);
+            self.out.push('\n');
+        }
+    }
+
+    fn newline(&mut self) {
+        self.end_line();
+        self.out.push_str(&"  ".repeat(self.indent));
+    }
+
+    /// Prints the statements of a body on their own (indented) lines
+    fn body(&mut self, body: &Node) -> Printed {
+        self.indent += 1;
+        for statement in statements(body) {
+            self.newline();
+            self.statement(statement)?;
+        }
+        self.indent -= 1;
+        Ok(())
+    }
+
+    fn statement(&mut self, node: &Node) -> Printed {
+        self.expr(node, Precedence::Statement)
+    }
+
+    /// Prints a node, parenthesizing it when it binds more loosely than `level`
+    fn expr(&mut self, node: &Node, level: Precedence) -> Printed {
+        if Precedence::of(node) < level {
+            self.out.push('(');
+            self.statement(node)?;
+            self.out.push(')');
+            Ok(())
+        } else {
+            self.node(node)
+        }
+    }
+
+    /// Prints a comma-separated list
+    fn list(&mut self, nodes: &[Node], level: Precedence) -> Printed {
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(node, level)?;
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, node: &Node) -> Printed {
+        match &node.kind {
+            NodeKind::None => return Err(UnparseError::new(node, "absent nodes have no source")),
+            NodeKind::Conditional(v) => self.conditional(v)?,
+            NodeKind::LogicalAnd(v) => self.logical(&v.first, &v.second, Precedence::And)?,
+            NodeKind::LogicalOr(v) => self.logical(&v.first, &v.second, Precedence::Or)?,
+            NodeKind::LogicalNot(v) => {
+                self.out.push_str("not ");
+                self.expr(&v.expr, Precedence::Not)?;
+            }
+            NodeKind::Literal(v) => self.literal(node, v)?,
+            NodeKind::Identifier(v) => self.out.push_str(&v.name),
+            NodeKind::Interpolated(v) => match v {
+                Interpolated::String(parts) => self.interpolated(parts, '"', "\"")?,
+                Interpolated::Symbol(parts) => self.interpolated(parts, '"', ":\"")?,
+                Interpolated::Command(parts) => self.interpolated(parts, '`', "`")?,
+            },
+            NodeKind::Concatenated(v) if v.parts.is_empty() => self.node(&v.value)?,
+            NodeKind::Concatenated(v) => {
+                for (i, part) in v.parts.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(' ');
+                    }
+                    self.node(part)?;
+                }
+            }
+            NodeKind::BinaryOp(v) => self.binary_op(v)?,
+            NodeKind::UnaryOp(v) => self.unary_op(v)?,
+            NodeKind::Block(v) => {
+                self.out.push('(');
+                for (i, statement) in v.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str("; ");
+                    }
+                    self.statement(statement)?;
+                }
+                self.out.push(')');
+            }
+            NodeKind::BlockArg(v) => {
+                self.out.push('&');
+                self.expr(v, Precedence::Ternary)?;
+            }
+            NodeKind::Segment(_) => self.interpolated(std::slice::from_ref(node), '"', "\"")?,
+            NodeKind::Comment(_) => {
+                return Err(UnparseError::new(
+                    node,
+                    "comments are only printed as part of a program",
+                ))
+            }
+            NodeKind::Ranged(v) => {
+                if v.from.kind != NodeKind::None {
+                    self.expr(&v.from, Precedence::Or)?;
+                }
+                self.out.push_str(if v.exclusive { "..." } else { ".." });
+                if v.to.kind != NodeKind::None {
+                    self.expr(&v.to, Precedence::Or)?;
+                }
+            }
+            NodeKind::Defined(v) => {
+                self.out.push_str("defined?(");
+                self.expr(v, Precedence::Keyword)?;
+                self.out.push(')');
+            }
+            NodeKind::Splat(v) => {
+                self.out.push('*');
+                self.expr(v, Precedence::Ternary)?;
+            }
+            NodeKind::Array(v) => {
+                self.out.push('[');
+                self.list(v, Precedence::Ternary)?;
+                self.out.push(']');
+            }
+            NodeKind::Hash(v) => {
+                self.out.push('{');
+                self.associations(v)?;
+                self.out.push('}');
+            }
+            NodeKind::Alias(Alias::Method { to, from }) => {
+                self.out.push_str("alias ");
+                self.method_name(to)?;
+                self.out.push(' ');
+                self.method_name(from)?;
+            }
+            NodeKind::Alias(Alias::GlobalVariable { to, from }) => {
+                self.out
+                    .push_str(&format!("alias {} {}", to.name, from.name));
+            }
+            NodeKind::Undef(v) => {
+                self.out.push_str("undef ");
+                for (i, name) in v.list.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.method_name(name)?;
+                }
+            }
+            NodeKind::Loop(v) => self.loop_(v)?,
+            NodeKind::Rescue(v) => self.rescue(v)?,
+            NodeKind::Case(v) => self.case(v)?,
+            NodeKind::Begin(v) => {
+                self.out.push_str("begin");
+                self.body(v)?;
+                self.newline();
+                self.out.push_str("end");
+            }
+            NodeKind::Nil => self.out.push_str("nil"),
+            NodeKind::Self_ => self.out.push_str("self"),
+            NodeKind::File(_) => self.out.push_str("__FILE__"),
+            // `__LINE__` would evaluate to the line it is printed on
+            NodeKind::Line(v) if *v != self.line() => self.out.push_str(&v.to_string()),
+            NodeKind::Line(_) => self.out.push_str("__LINE__"),
+            NodeKind::Encoding(_) => self.out.push_str("__ENCODING__"),
+            NodeKind::Redo => self.out.push_str("redo"),
+            NodeKind::Retry => self.out.push_str("retry"),
+            NodeKind::Return(v) => self.jump("return", v)?,
+            NodeKind::Break(v) => self.jump("break", v)?,
+            NodeKind::Next(v) => self.jump("next", v)?,
+            NodeKind::EndBlock(v) => {
+                self.out.push_str("END {");
+                self.body(v)?;
+                self.newline();
+                self.out.push('}');
+            }
+            NodeKind::EndOfProgram(_) => {
+                return Err(UnparseError::new(
+                    node,
+                    "`__END__` is only printed as part of a program",
+                ))
+            }
+            NodeKind::Error(v) => self.out.push_str(v),
+            NodeKind::Placeholder => match self.source {
+                Some(_) if !node.span.is_unset() => self.placeholder(&node.span),
+                _ => return Err(UnparseError::new(node, "placeholder nodes have no source")),
+            },
+        }
+        Ok(())
+    }
+
+    /// Prints a placeholder as the source it was parsed from
+    ///
+    /// The bodies of heredocs that were started before the placeholder are left out, and those of
+    /// the heredocs it starts on its last line follow the line it's printed on.
+    fn placeholder(&mut self, span: &Span) {
+        let (start, end) = (span.start.offset, span.end.offset);
+        let first = self
+            .tokens
+            .partition_point(|t| t.token.span.start.offset < start);
+        let mut skipped = false;
+        for t in self.tokens[first..]
+            .iter()
+            .take_while(|t| t.token.span.start.offset < end)
+        {
+            match (&t.token.kind, t.heredoc) {
+                (TokenKind::HeredocBody, Some(heredoc)) if heredoc < start => skipped = true,
+                // The line terminator that follows a body is skipped along with it
+                (TokenKind::Newline, _) if skipped => skipped = false,
+                // The heredocs started on the line being printed come first
+                (TokenKind::Newline, _) => {
+                    self.out.push_str(t.token.text);
+                    for body in std::mem::take(&mut self.heredocs) {
+                        self.out.push_str(body);
+                        self.out.push('\n');
+                    }
+                }
+                _ => {
+                    skipped = false;
+                    self.out.push_str(t.token.text);
+                }
+            }
+        }
+        self.out.truncate(self.out.trim_end().len());
+        // The bodies of heredocs that were started on the placeholder's last line follow it
+        let mut terminated = false;
+        for t in self.tokens[first..]
+            .iter()
+            .skip_while(|t| t.token.span.start.offset < end)
+        {
+            match (&t.token.kind, t.heredoc) {
+                (TokenKind::HeredocBody, Some(heredoc)) if (start..end).contains(&heredoc) => {
+                    self.heredocs.push(t.token.text)
+                }
+                (TokenKind::HeredocBody, _) => {}
+                (TokenKind::Newline, _) => terminated = true,
+                _ if terminated => break,
+                _ => {}
+            }
+        }
+    }
+
+    fn conditional(&mut self, v: &Conditional) -> Printed {
+        match v.kind {
+            ConditionalKind::If | ConditionalKind::Elsif | ConditionalKind::Unless => {
+                let keyword = match v.kind {
+                    ConditionalKind::Unless => "unless ",
+                    _ => "if ",
+                };
+                self.out.push_str(keyword);
+                self.expr(&v.cond, Precedence::Keyword)?;
+                self.body(&v.then)?;
+                let mut otherwise = &v.otherwise;
+                loop {
+                    match &otherwise.kind {
+                        NodeKind::None => break,
+                        NodeKind::Conditional(elsif)
+                            if elsif.kind == ConditionalKind::Elsif
+                                && v.kind != ConditionalKind::Unless =>
+                        {
+                            self.newline();
+                            self.out.push_str("elsif ");
+                            self.expr(&elsif.cond, Precedence::Keyword)?;
+                            self.body(&elsif.then)?;
+                            otherwise = &elsif.otherwise;
+                        }
+                        _ => {
+                            self.newline();
+                            self.out.push_str("else");
+                            self.body(otherwise)?;
+                            break;
+                        }
+                    }
+                }
+                self.newline();
+                self.out.push_str("end");
+            }
+            ConditionalKind::Ternary => {
+                self.expr(&v.cond, Precedence::Range)?;
+                self.out.push_str(" ? ");
+                self.expr(&v.then, Precedence::Ternary)?;
+                self.out.push_str(" : ");
+                self.expr(&v.otherwise, Precedence::Ternary)?;
+            }
+            ConditionalKind::ModifyingIf | ConditionalKind::ModifyingUnless => {
+                self.statement(&v.then)?;
+                self.out.push_str(match v.kind {
+                    ConditionalKind::ModifyingIf => " if ",
+                    _ => " unless ",
+                });
+                self.expr(&v.cond, Precedence::Keyword)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn logical(&mut self, first: &Node, second: &Node, level: Precedence) -> Printed {
+        let and = level == Precedence::And;
+        if symbolic(first, second, level) {
+            self.expr(first, level)?;
+            self.out.push_str(if and { " && " } else { " || " });
+            self.expr(second, level.tighter())
+        } else {
+            self.expr(first, Precedence::Keyword)?;
+            self.out.push_str(if and { " and " } else { " or " });
+            self.expr(second, Precedence::Not)
+        }
+    }
+
+    fn binary_op(&mut self, v: &BinaryOp) -> Printed {
+        let level = Precedence::of_binary_op(&v.op);
+        let (lhs, rhs) = match level {
+            Precedence::Power => (Precedence::Unary, Precedence::Power),
+            // Equality operators are not associative
+            Precedence::Equality => (Precedence::Relational, Precedence::Relational),
+            _ => (level, level.tighter()),
+        };
+        let op = match v.op {
+            BinaryOpKind::Compare => "<=>",
+            BinaryOpKind::Equal => "==",
+            BinaryOpKind::CaseEqual => "===",
+            BinaryOpKind::NotEqual => "!=",
+            BinaryOpKind::RegexMatch => "=~",
+            BinaryOpKind::NotRegexMatch => "!~",
+            BinaryOpKind::GreaterThan => ">",
+            BinaryOpKind::GreaterEqual => ">=",
+            BinaryOpKind::LessThan => "<",
+            BinaryOpKind::LessEqual => "<=",
+            BinaryOpKind::BitOr => "|",
+            BinaryOpKind::BitXor => "^",
+            BinaryOpKind::BitAnd => "&",
+            BinaryOpKind::ShiftLeft => "<<",
+            BinaryOpKind::ShiftRight => ">>",
+            BinaryOpKind::Add => "+",
+            BinaryOpKind::Subtract => "-",
+            BinaryOpKind::Multiply => "*",
+            BinaryOpKind::Divide => "/",
+            BinaryOpKind::Modulus => "%",
+            BinaryOpKind::Power => "**",
+        };
+        self.expr(&v.lhs, lhs)?;
+        self.out.push_str(&format!(" {} ", op));
+        self.expr(&v.rhs, rhs)
+    }
+
+    fn unary_op(&mut self, v: &UnaryOp) -> Printed {
+        let (op, level) = match v.op {
+            UnaryOpKind::BitNot => ('~', Precedence::Unary),
+            UnaryOpKind::Positive => ('+', Precedence::Unary),
+            UnaryOpKind::Negative => ('-', Precedence::Power),
+            UnaryOpKind::LogicalNot => ('!', Precedence::Unary),
+        };
+        self.out.push(op);
+        let start = self.out.len();
+        self.expr(&v.rhs, level)?;
+        // Keeps the operator from being lexed as part of a numeric literal (`- 1`) or of another
+        // operator (`! ~a`)
+        let next = self.out[start..].chars().next();
+        let separate = match op {
+            '+' | '-' => matches!(next, Some('0'..='9') | Some('+') | Some('-')),
+            '!' => matches!(next, Some('~') | Some('=')),
+            _ => false,
+        };
+        if separate {
+            self.out.insert(start, ' ');
+        }
+        Ok(())
+    }
+
+    fn jump(&mut self, keyword: &str, args: &[Node]) -> Printed {
+        self.out.push_str(keyword);
+        for (i, arg) in args.iter().enumerate() {
+            self.out.push_str(if i > 0 { ", " } else { " " });
+            match &arg.kind {
+                // A trailing hash is written without braces
+                NodeKind::Hash(v) if !v.is_empty() && i == args.len() - 1 => {
+                    self.associations(v)?
+                }
+                // A leading brace would be taken as a block
+                NodeKind::Hash(_) if i == 0 => {
+                    self.out.push('(');
+                    self.node(arg)?;
+                    self.out.push(')');
+                }
+                _ => self.expr(arg, Precedence::Ternary)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn loop_(&mut self, v: &Loop) -> Printed {
+        match v.kind {
+            LoopKind::For => {
+                self.out.push_str("for ");
+                match &v.bindings {
+                    Some(bindings) if !bindings.is_empty() => {
+                        self.list(bindings, Precedence::Primary)?
+                    }
+                    _ => {
+                        return Err(UnparseError::new(
+                            &Node::new(NodeKind::Loop(v.clone())),
+                            "`for` loops must bind at least one variable",
+                        ))
+                    }
+                }
+                self.out.push_str(" in ");
+                self.expr(&v.cond, Precedence::Keyword)?;
+                self.body(&v.body)?;
+                self.newline();
+                self.out.push_str("end");
+            }
+            LoopKind::While | LoopKind::Until => {
+                self.out.push_str(match v.kind {
+                    LoopKind::While => "while ",
+                    _ => "until ",
+                });
+                self.expr(&v.cond, Precedence::Keyword)?;
+                self.body(&v.body)?;
+                self.newline();
+                self.out.push_str("end");
+            }
+            LoopKind::ModifyingWhile | LoopKind::ModifyingUntil => {
+                // A modified `begin` expression would become a post-condition loop
+                if let NodeKind::Begin(_) = v.body.kind {
+                    self.out.push('(');
+                    self.node(&v.body)?;
+                    self.out.push(')');
+                } else {
+                    self.statement(&v.body)?;
+                }
+                self.out.push_str(match v.kind {
+                    LoopKind::ModifyingWhile => " while ",
+                    _ => " until ",
+                });
+                self.expr(&v.cond, Precedence::Keyword)?;
+            }
+            LoopKind::PostConditionWhile | LoopKind::PostConditionUntil => {
+                if let NodeKind::Begin(_) = v.body.kind {
+                    self.node(&v.body)?;
+                } else {
+                    self.out.push_str("begin");
+                    self.body(&v.body)?;
+                    self.newline();
+                    self.out.push_str("end");
+                }
+                self.out.push_str(match v.kind {
+                    LoopKind::PostConditionWhile => " while ",
+                    _ => " until ",
+                });
+                self.expr(&v.cond, Precedence::Keyword)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rescue(&mut self, v: &Rescue) -> Printed {
+        if is_rescue_modifier(v) {
+            self.statement(&v.body)?;
+            self.out.push_str(" rescue ");
+            return self.expr(&v.rescue[0].then, Precedence::SimpleStatement);
+        }
+        self.out.push_str("begin");
+        self.body(&v.body)?;
+        for clause in &v.rescue {
+            self.newline();
+            self.out.push_str("rescue");
+            if !clause.exceptions.is_empty() {
+                self.out.push(' ');
+                self.list(&clause.exceptions, Precedence::Ternary)?;
+            }
+            if clause.assigned_to.kind != NodeKind::None {
+                self.out.push_str(" => ");
+                self.expr(&clause.assigned_to, Precedence::Primary)?;
+            }
+            self.body(&clause.then)?;
+        }
+        if v.otherwise.kind != NodeKind::None {
+            self.newline();
+            self.out.push_str("else");
+            self.body(&v.otherwise)?;
+        }
+        self.newline();
+        self.out.push_str("end");
+        Ok(())
+    }
+
+    fn case(&mut self, v: &Case) -> Printed {
+        self.out.push_str("case");
+        if v.expr.kind != NodeKind::None {
+            self.out.push(' ');
+            self.expr(&v.expr, Precedence::Keyword)?;
+        }
+        for clause in &v.when {
+            self.newline();
+            self.out.push_str("when ");
+            self.list(&clause.when, Precedence::Ternary)?;
+            self.body(&clause.then)?;
+        }
+        if v.otherwise.kind != NodeKind::None {
+            self.newline();
+            self.out.push_str("else");
+            self.body(&v.otherwise)?;
+        }
+        self.newline();
+        self.out.push_str("end");
+        Ok(())
+    }
+
+    fn associations(&mut self, list: &[Association]) -> Printed {
+        for (i, association) in list.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            let pair = match association {
+                Association::DoubleSplat(v) => {
+                    self.out.push_str("**");
+                    self.expr(v, Precedence::Ternary)?;
+                    continue;
+                }
+                Association::Pair(pair) => pair,
+            };
+            let label = match &pair.key.kind {
+                NodeKind::Literal(Literal::Symbol(name)) => Some(name),
+                _ => None,
+            };
+            match (pair.syntax, label, &pair.key.kind) {
+                (PairSyntax::Shorthand, Some(name), _)
+                    if is_identifier(name)
+                        && matches!(&pair.value.kind, NodeKind::Identifier(v) if &v.name == name) =>
+                {
+                    self.out.push_str(name);
+                    self.out.push(':');
+                    continue;
+                }
+                (PairSyntax::Label, Some(name), _) | (PairSyntax::Shorthand, Some(name), _)
+                    if is_identifier(name) =>
+                {
+                    self.out.push_str(name);
+                    self.out.push_str(": ");
+                }
+                (PairSyntax::Rocket, ..) => {
+                    self.expr(&pair.key, Precedence::Ternary)?;
+                    self.out.push_str(" => ");
+                }
+                (_, Some(name), _) => {
+                    self.out.push('"');
+                    push_escaped(&mut self.out, name, '"', false);
+                    self.out.push_str("\": ");
+                }
+                (_, None, NodeKind::Interpolated(Interpolated::Symbol(parts))) => {
+                    self.interpolated(parts, '"', "\"")?;
+                    self.out.push_str(": ");
+                }
+                _ => {
+                    self.expr(&pair.key, Precedence::Ternary)?;
+                    self.out.push_str(" => ");
+                }
+            }
+            self.expr(&pair.value, Precedence::Ternary)?;
+        }
+        Ok(())
+    }
+
+    fn method_name(&mut self, name: &MethodName) -> Printed {
+        match name {
+            MethodName::Bare(v) => self.out.push_str(v),
+            MethodName::Symbol(v) => push_symbol(&mut self.out, v),
+            MethodName::DynamicSymbol(parts) => self.interpolated(parts, '"', ":\"")?,
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, node: &Node, v: &Literal) -> Printed {
+        match v {
+            Literal::Nil => self.out.push_str("nil"),
+            Literal::Boolean(v) => self.out.push_str(if *v { "true" } else { "false" }),
+            Literal::Integer(v) => self.out.push_str(&v.to_string()),
+            Literal::BigInteger(v) => self.out.push_str(v),
+            // Non-finite floats have no literal
+            Literal::Float(v) if v.is_nan() => self.out.push_str("Float::NAN"),
+            Literal::Float(v) if v.is_infinite() => {
+                if v.is_sign_negative() {
+                    self.out.push('-');
+                }
+                self.out.push_str("Float::INFINITY");
+            }
+            Literal::Float(v) => self.out.push_str(&format!("{:?}", v)),
+            Literal::String(v) => {
+                self.out.push('"');
+                push_escaped(&mut self.out, v, '"', false);
+                self.out.push('"');
+            }
+            Literal::Bytes(v) => {
+                self.out.push('"');
+                push_escaped_bytes(&mut self.out, v, '"', false);
+                self.out.push('"');
+            }
+            Literal::Character(v) => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (None, _) => {
+                        return Err(UnparseError::new(node, "character literals can't be empty"))
+                    }
+                    (Some(c), None) => {
+                        self.out.push('?');
+                        match c {
+                            ' ' => self.out.push_str("\\s"),
+                            '#' => self.out.push_str("\\#"),
+                            c => push_escaped_char(&mut self.out, c, None, '"', false),
+                        }
+                    }
+                    _ => {
+                        let codes: Vec<String> =
+                            v.chars().map(|c| format!("{:04X}", c as u32)).collect();
+                        self.out.push_str(&format!("?\\u{{{}}}", codes.join(" ")));
+                    }
+                }
+            }
+            Literal::CharacterBytes(v) if v.len() == 1 => {
+                self.out.push_str(&format!("?\\x{:02X}", v[0]))
+            }
+            Literal::CharacterBytes(_) => {
+                return Err(UnparseError::new(
+                    node,
+                    "character literals can only hold a single byte",
+                ))
+            }
+            Literal::Symbol(v) => push_symbol(&mut self.out, v),
+            Literal::Command(v) => {
+                self.out.push('`');
+                push_escaped(&mut self.out, v, '`', false);
+                self.out.push('`');
+            }
+            Literal::CommandBytes(v) => {
+                self.out.push('`');
+                push_escaped_bytes(&mut self.out, v, '`', false);
+                self.out.push('`');
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the parts of an interpolated literal between `open` and `delimiter`
+    fn interpolated(&mut self, parts: &[Node], delimiter: char, open: &str) -> Printed {
+        self.out.push_str(open);
+        // Text that directly follows `#@var` must not continue the variable's name
+        let mut after_variable = false;
+        for part in parts {
+            match &part.kind {
+                NodeKind::Segment(Segment::String(v)) => {
+                    push_escaped(&mut self.out, v, delimiter, after_variable)
+                }
+                NodeKind::Segment(Segment::Bytes(v)) => {
+                    push_escaped_bytes(&mut self.out, v, delimiter, after_variable)
+                }
+                NodeKind::Segment(Segment::Char(c)) => {
+                    push_escaped_char(&mut self.out, *c, None, delimiter, after_variable)
+                }
+                NodeKind::Segment(Segment::Expr(v)) => {
+                    self.out.push_str("#{");
+                    self.statement(v)?;
+                    self.out.push('}');
+                }
+                NodeKind::Identifier(v)
+                    if matches!(
+                        v.kind,
+                        IdentifierKind::GlobalVariable
+                            | IdentifierKind::NthReference
+                            | IdentifierKind::InstanceVariable
+                            | IdentifierKind::ClassVariable
+                    ) =>
+                {
+                    self.out.push('#');
+                    self.out.push_str(&v.name);
+                    after_variable = true;
+                    continue;
+                }
+                NodeKind::Block(v) => {
+                    self.out.push_str("#{");
+                    for (i, statement) in v.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push_str("; ");
+                        }
+                        self.statement(statement)?;
+                    }
+                    self.out.push('}');
+                }
+                _ => {
+                    self.out.push_str("#{");
+                    self.statement(part)?;
+                    self.out.push('}');
+                }
+            }
+            after_variable = false;
+        }
+        self.out.push(delimiter);
+        Ok(())
+    }
+}
+
+/// Whether a name can be written as a bare identifier (such as in a label)
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn push_symbol(out: &mut String, name: &str) {
+    let bare = name
+        .strip_suffix(|c| matches!(c, '?' | '!' | '='))
+        .unwrap_or(name);
+    if is_identifier(bare) {
+        out.push(':');
+        out.push_str(name);
+    } else {
+        out.push_str(":\"");
+        push_escaped(out, name, '"', false);
+        out.push('"');
+    }
+}
+
+/// Escapes a single character of a double-quoted (or backquoted) literal
+///
+/// When `escape_identifier` is set, a character that could continue an identifier is escaped too.
+fn push_escaped_char(
+    out: &mut String,
+    c: char,
+    next: Option<char>,
+    delimiter: char,
+    escape_identifier: bool,
+) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        '\x0C' => out.push_str("\\f"),
+        '\x0B' => out.push_str("\\v"),
+        '\x07' => out.push_str("\\a"),
+        '\x1B' => out.push_str("\\e"),
+        '\x08' => out.push_str("\\b"),
+        '#' if matches!(next, Some('{') | Some('$') | Some('@')) => out.push_str("\\#"),
+        c if c == delimiter => {
+            out.push('\\');
+            out.push(c);
+        }
+        c if c.is_ascii_control() => out.push_str(&format!("\\x{:02X}", c as u32)),
+        c if escape_identifier && (c.is_ascii_alphanumeric() || c == '_') => {
+            out.push_str(&format!("\\x{:02X}", c as u32))
+        }
+        c if escape_identifier && !c.is_ascii() => {
+            out.push_str(&format!("\\u{{{:04X}}}", c as u32))
+        }
+        c => out.push(c),
+    }
+}
+
+fn push_escaped(out: &mut String, s: &str, delimiter: char, escape_identifier: bool) {
+    let mut chars = s.chars().peekable();
+    let mut first = true;
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        push_escaped_char(out, c, next, delimiter, first && escape_identifier);
+        first = false;
+    }
+}
+
+/// Escapes bytes that may not be valid UTF-8, writing any non-ASCII byte as `\xNN`
+fn push_escaped_bytes(out: &mut String, bytes: &[u8], delimiter: char, escape_identifier: bool) {
+    for (i, &b) in bytes.iter().enumerate() {
+        if b.is_ascii() {
+            let next = bytes.get(i + 1).map(|&b| b as char);
+            push_escaped_char(out, b as char, next, delimiter, i == 0 && escape_identifier);
+        } else {
+            out.push_str(&format!("\\x{:02X}", b));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Input;
+
+    fn ident(name: &str) -> Node {
+        Node::ident(name, IdentifierKind::LocalVariable)
+    }
+
+    /// Asserts that a program prints as the given source, which parses back to the same program
+    ///
+    /// The source can't contain method calls, assignments or definitions, which parse to
+    /// placeholders.
+    fn assert_round_trip(source: &str, printed: &str) {
+        let program = crate::parse(Input::new(source)).unwrap();
+        let output = crate::unparse(&program).unwrap();
+        assert_eq!(output, printed);
+        let reparsed = crate::parse(Input::new(&output)).unwrap();
+        assert_eq!(reparsed.program, program.program);
+        assert_eq!(reparsed.begin_blocks, program.begin_blocks);
+    }
+
+    fn assert_source_round_trip(source: &str, printed: &str) {
+        let program = crate::parse(Input::new(source)).unwrap();
+        let output = crate::unparse_with_source(&program, source).unwrap();
+        assert_eq!(output, printed);
+        let reparsed = crate::parse(Input::new(&output)).unwrap();
+        assert_eq!(reparsed.program, program.program);
+        assert_eq!(reparsed.begin_blocks, program.begin_blocks);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_round_trip("", "");
+        assert_round_trip("a && b || c", "a && b || c\n");
+        assert_round_trip("a and not b", "a and not b\n");
+        assert_round_trip("not a == b", "not a == b\n");
+        assert_round_trip("- 1 + -2", "- 1 + -2\n");
+        assert_round_trip("(a; b) * c", "(a; b) * c\n");
+        assert_round_trip("x ? ?a : :b", "x ? ?a : :b\n");
+        assert_round_trip("a rescue b", "a rescue b\n");
+        assert_round_trip("until a\nend", "until a\nend\n");
+        assert_round_trip("END { a }", "END {\n  a\n}\n");
+        assert_round_trip("{\"a b\": 1, c:, **d}", "{\"a b\": 1, c:, **d}\n");
+        assert_round_trip("'a' \"b#{c}\"", "\"a\" \"b#{c}\"\n");
+        assert_round_trip("alias $a $b", "alias $a $b\n");
+        assert_round_trip("undef a, :b", "undef a, :b\n");
+        assert_round_trip("BEGIN { a }\nb", "BEGIN {\n  a\n}\nb\n");
+        assert_round_trip(
+            "#!ruby\n# frozen_string_literal: true\n",
+            "#!ruby\n# frozen_string_literal: true\n",
+        );
+        assert_round_trip("a\n__END__\ndata", "a\n__END__\ndata");
+        assert_round_trip(
+            "[-9223372036854775808, 0x7fffffffffffffffff]",
+            "[-9223372036854775808, 2361183241434822606847]\n",
+        );
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_source_round_trip("'a' + foo(1)", "\"a\" + foo(1)\n");
+        assert_source_round_trip("if x\n    foo 'a'\nend", "if x\n  foo 'a'\nend\n");
+        // Placeholders that span lines keep their layout
+        assert_source_round_trip("def f(a)\n    a\nend", "def f(a)\n    a\nend\n");
+        // Heredoc bodies follow the line that the heredoc is printed on
+        assert_source_round_trip(
+            "foo(<<A, 1); bar(2)\nbody\nA",
+            "foo(<<A, 1)\nbody\nA\nbar(2)\n",
+        );
+        assert_source_round_trip(
+            "foo(<<A) if x(<<B)\na\nA\nb\nB",
+            "foo(<<A) if x(<<B)\na\nA\nb\nB\n",
+        );
+        assert_source_round_trip(
+            "x = [<<A, foo(<<B)]\na\nA\nb\nB",
+            "x = [<<A, foo(<<B)]\na\nA\nb\nB\n",
+        );
+        assert_source_round_trip(
+            "if x\n  foo(<<A) + 1\nbody\nA\n  y\nend",
+            "if x\n  foo(<<A) + 1\nbody\nA\n  y\nend\n",
+        );
+        // Blank lines between statements aren't kept
+        let source = include_str!("../../test.rb");
+        assert_source_round_trip(source, &source.replace("\n\n", "\n"));
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let eq = Node::binary_op(ident("a"), BinaryOpKind::Equal, ident("b"));
+        let modifier = Node::conditional(
+            ConditionalKind::ModifyingIf,
+            ident("c"),
+            ident("b"),
+            Node::new(NodeKind::None),
+        );
+        assert_eq!(
+            node(&Node::unary_op(UnaryOpKind::LogicalNot, eq.clone())),
+            Ok("!(a == b)".to_owned())
+        );
+        assert_eq!(
+            node(&Node::logical_not(eq.clone())),
+            Ok("not a == b".to_owned())
+        );
+        assert_eq!(
+            node(&Node::binary_op(
+                ident("a"),
+                BinaryOpKind::Add,
+                modifier.clone()
+            )),
+            Ok("a + (b if c)".to_owned())
+        );
+        assert_eq!(
+            node(&Node::array(vec![modifier])),
+            Ok("[(b if c)]".to_owned())
+        );
+        assert_eq!(
+            node(&Node::binary_op(
+                ident("a"),
+                BinaryOpKind::Subtract,
+                Node::binary_op(ident("b"), BinaryOpKind::Subtract, ident("c"))
+            )),
+            Ok("a - (b - c)".to_owned())
+        );
+        assert_eq!(
+            node(&Node::binary_op(
+                eq.clone(),
+                BinaryOpKind::Equal,
+                ident("c")
+            )),
+            Ok("(a == b) == c".to_owned())
+        );
+        assert_eq!(
+            node(&Node::logical_and(
+                ident("a"),
+                Node::logical_not(ident("b"))
+            )),
+            Ok("a and not b".to_owned())
+        );
+        assert_eq!(
+            node(&Node::logical_and(
+                Node::logical_or(ident("a"), ident("b")),
+                ident("c")
+            )),
+            Ok("a || b and c".to_owned())
+        );
+        assert_eq!(
+            node(&Node::unary_op(UnaryOpKind::Negative, Node::int(1))),
+            Ok("- 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(
+            node(&Node::literal_string("\"\\#{a}#b\n\x7f")),
+            Ok("\"\\\"\\\\\\#{a}#b\\n\\x7F\"".to_owned())
+        );
+        assert_eq!(
+            node(&Node::new(NodeKind::Literal(Literal::Bytes(vec![
+                0xff, b'a'
+            ])))),
+            Ok("\"\\xFFa\"".to_owned())
+        );
+        assert_eq!(
+            node(&Node::new(NodeKind::Literal(Literal::CommandBytes(vec![
+                b'`', 0xfe
+            ])))),
+            Ok("`\\`\\xFE`".to_owned())
+        );
+        assert_eq!(node(&Node::literal_symbol("a?")), Ok(":a?".to_owned()));
+        assert_eq!(
+            node(&Node::literal_symbol("a b")),
+            Ok(":\"a b\"".to_owned())
+        );
+        assert_eq!(
+            node(&Node::new(NodeKind::Literal(Literal::Character(
+                " ".to_owned()
+            )))),
+            Ok("?\\s".to_owned())
+        );
+        // Text after an interpolated variable can't continue its name
+        assert_eq!(
+            node(&Node::new(NodeKind::Interpolated(Interpolated::String(
+                vec![
+                    Node::ident("@a", IdentifierKind::InstanceVariable),
+                    Node::new(NodeKind::Segment(Segment::String("b c".to_owned()))),
+                ]
+            )))),
+            Ok("\"#@a\\x62 c\"".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_values_without_literals() {
+        assert_eq!(
+            node(&Node::float(f64::INFINITY)),
+            Ok("Float::INFINITY".to_owned())
+        );
+        assert_eq!(
+            node(&Node::float(f64::NEG_INFINITY)),
+            Ok("-Float::INFINITY".to_owned())
+        );
+        assert_eq!(node(&Node::float(f64::NAN)), Ok("Float::NAN".to_owned()));
+        // `__LINE__` is printed as a number when it would evaluate to a different line
+        assert_round_trip("a\n__LINE__", "a\n__LINE__\n");
+        let program = crate::parse(Input::new("\n\n__LINE__ + __LINE__")).unwrap();
+        assert_eq!(crate::unparse(&program), Ok("3 + 3\n".to_owned()));
+        let program = crate::parse(Input::new("[\n  __LINE__\n]")).unwrap();
+        assert_eq!(crate::unparse(&program), Ok("[2]\n".to_owned()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(node(&Node::new(NodeKind::Placeholder)).is_err());
+        assert!(node(&Node::new(NodeKind::Comment("# a".to_owned()))).is_err());
+    }
+}
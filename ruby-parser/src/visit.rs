@@ -0,0 +1,966 @@
+//! Provides traits for traversing and rewriting the abstract syntax tree
+//!
+//! Each trait has a method for every type of node. The default methods recurse into the node's
+//! children by calling the matching free function (`walk_*`, `walk_*_mut` or `fold_*`), so an
+//! implementation only overrides the methods for the nodes it cares about, and calls the free
+//! function to keep descending. Children are visited in the same order as `Node::children()`.
+
+use crate::ast::*;
+use crate::lexer::Segment;
+
+/// Traverses a syntax tree by reference
+pub trait Visit<'ast> {
+    fn visit_program(&mut self, v: &'ast Program) {
+        walk_program(self, v)
+    }
+    fn visit_node(&mut self, v: &'ast Node) {
+        walk_node(self, v)
+    }
+    fn visit_conditional(&mut self, v: &'ast Conditional) {
+        walk_conditional(self, v)
+    }
+    fn visit_logical_and(&mut self, v: &'ast LogicalAnd) {
+        walk_logical_and(self, v)
+    }
+    fn visit_logical_or(&mut self, v: &'ast LogicalOr) {
+        walk_logical_or(self, v)
+    }
+    fn visit_logical_not(&mut self, v: &'ast LogicalNot) {
+        walk_logical_not(self, v)
+    }
+    fn visit_literal(&mut self, _v: &'ast Literal) {}
+    fn visit_identifier(&mut self, _v: &'ast Identifier) {}
+    fn visit_interpolated(&mut self, v: &'ast Interpolated) {
+        walk_interpolated(self, v)
+    }
+    fn visit_concatenated(&mut self, v: &'ast Concatenated) {
+        walk_concatenated(self, v)
+    }
+    fn visit_binary_op(&mut self, v: &'ast BinaryOp) {
+        walk_binary_op(self, v)
+    }
+    fn visit_unary_op(&mut self, v: &'ast UnaryOp) {
+        walk_unary_op(self, v)
+    }
+    fn visit_segment(&mut self, v: &'ast Segment) {
+        walk_segment(self, v)
+    }
+    fn visit_ranged(&mut self, v: &'ast Ranged) {
+        walk_ranged(self, v)
+    }
+    fn visit_association(&mut self, v: &'ast Association) {
+        walk_association(self, v)
+    }
+    fn visit_alias(&mut self, v: &'ast Alias) {
+        walk_alias(self, v)
+    }
+    fn visit_undef(&mut self, v: &'ast Undef) {
+        walk_undef(self, v)
+    }
+    fn visit_method_name(&mut self, v: &'ast MethodName) {
+        walk_method_name(self, v)
+    }
+    fn visit_loop(&mut self, v: &'ast Loop) {
+        walk_loop(self, v)
+    }
+    fn visit_rescue(&mut self, v: &'ast Rescue) {
+        walk_rescue(self, v)
+    }
+    fn visit_rescue_clause(&mut self, v: &'ast RescueClause) {
+        walk_rescue_clause(self, v)
+    }
+    fn visit_case(&mut self, v: &'ast Case) {
+        walk_case(self, v)
+    }
+    fn visit_when_clause(&mut self, v: &'ast WhenClause) {
+        walk_when_clause(self, v)
+    }
+}
+
+/// Visits the program's `BEGIN { }` blocks, then its body (comments are not visited)
+pub fn walk_program<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Program) {
+    for node in &v.begin_blocks {
+        visitor.visit_node(node);
+    }
+    visitor.visit_node(&v.program);
+}
+
+pub fn walk_node<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Node) {
+    match &v.kind {
+        NodeKind::Conditional(v) => visitor.visit_conditional(v),
+        NodeKind::LogicalAnd(v) => visitor.visit_logical_and(v),
+        NodeKind::LogicalOr(v) => visitor.visit_logical_or(v),
+        NodeKind::LogicalNot(v) => visitor.visit_logical_not(v),
+        NodeKind::Literal(v) => visitor.visit_literal(v),
+        NodeKind::Identifier(v) => visitor.visit_identifier(v),
+        NodeKind::Interpolated(v) => visitor.visit_interpolated(v),
+        NodeKind::Concatenated(v) => visitor.visit_concatenated(v),
+        NodeKind::BinaryOp(v) => visitor.visit_binary_op(v),
+        NodeKind::UnaryOp(v) => visitor.visit_unary_op(v),
+        NodeKind::Segment(v) => visitor.visit_segment(v),
+        NodeKind::Ranged(v) => visitor.visit_ranged(v),
+        NodeKind::Hash(v) => {
+            for association in v {
+                visitor.visit_association(association);
+            }
+        }
+        NodeKind::Alias(v) => visitor.visit_alias(v),
+        NodeKind::Undef(v) => visitor.visit_undef(v),
+        NodeKind::Loop(v) => visitor.visit_loop(v),
+        NodeKind::Rescue(v) => visitor.visit_rescue(v),
+        NodeKind::Case(v) => visitor.visit_case(v),
+        NodeKind::Block(v)
+        | NodeKind::Array(v)
+        | NodeKind::Return(v)
+        | NodeKind::Break(v)
+        | NodeKind::Next(v) => {
+            for node in v {
+                visitor.visit_node(node);
+            }
+        }
+        NodeKind::BlockArg(v)
+        | NodeKind::Defined(v)
+        | NodeKind::Splat(v)
+        | NodeKind::Begin(v)
+        | NodeKind::EndBlock(v) => visitor.visit_node(v),
+        NodeKind::None
+        | NodeKind::Comment(_)
+        | NodeKind::Nil
+        | NodeKind::Self_
+        | NodeKind::File(_)
+        | NodeKind::Line(_)
+        | NodeKind::Encoding(_)
+        | NodeKind::Redo
+        | NodeKind::Retry
+        | NodeKind::EndOfProgram(_)
+        | NodeKind::Error(_)
+        | NodeKind::Placeholder => {}
+    }
+}
+
+pub fn walk_conditional<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Conditional) {
+    visitor.visit_node(&v.cond);
+    visitor.visit_node(&v.then);
+    visitor.visit_node(&v.otherwise);
+}
+
+pub fn walk_logical_and<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast LogicalAnd) {
+    visitor.visit_node(&v.first);
+    visitor.visit_node(&v.second);
+}
+
+pub fn walk_logical_or<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast LogicalOr) {
+    visitor.visit_node(&v.first);
+    visitor.visit_node(&v.second);
+}
+
+pub fn walk_logical_not<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast LogicalNot) {
+    visitor.visit_node(&v.expr);
+}
+
+pub fn walk_interpolated<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Interpolated) {
+    match v {
+        Interpolated::Command(v) | Interpolated::String(v) | Interpolated::Symbol(v) => {
+            for node in v {
+                visitor.visit_node(node);
+            }
+        }
+    }
+}
+
+pub fn walk_concatenated<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Concatenated) {
+    visitor.visit_node(&v.value);
+    for node in &v.parts {
+        visitor.visit_node(node);
+    }
+}
+
+pub fn walk_binary_op<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast BinaryOp) {
+    visitor.visit_node(&v.lhs);
+    visitor.visit_node(&v.rhs);
+}
+
+pub fn walk_unary_op<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast UnaryOp) {
+    visitor.visit_node(&v.rhs);
+}
+
+pub fn walk_segment<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Segment) {
+    if let Segment::Expr(v) = v {
+        visitor.visit_node(v);
+    }
+}
+
+pub fn walk_ranged<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Ranged) {
+    visitor.visit_node(&v.from);
+    visitor.visit_node(&v.to);
+}
+
+pub fn walk_association<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Association) {
+    match v {
+        Association::Pair(pair) => {
+            visitor.visit_node(&pair.key);
+            visitor.visit_node(&pair.value);
+        }
+        Association::DoubleSplat(v) => visitor.visit_node(v),
+    }
+}
+
+pub fn walk_alias<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Alias) {
+    match v {
+        Alias::Method { to, from } => {
+            visitor.visit_method_name(to);
+            visitor.visit_method_name(from);
+        }
+        Alias::GlobalVariable { to, from } => {
+            visitor.visit_identifier(to);
+            visitor.visit_identifier(from);
+        }
+    }
+}
+
+pub fn walk_undef<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Undef) {
+    for name in &v.list {
+        visitor.visit_method_name(name);
+    }
+}
+
+pub fn walk_method_name<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast MethodName) {
+    if let MethodName::DynamicSymbol(v) = v {
+        for node in v {
+            visitor.visit_node(node);
+        }
+    }
+}
+
+pub fn walk_loop<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Loop) {
+    visitor.visit_node(&v.cond);
+    for node in v.bindings.iter().flatten() {
+        visitor.visit_node(node);
+    }
+    visitor.visit_node(&v.body);
+}
+
+pub fn walk_rescue<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Rescue) {
+    visitor.visit_node(&v.body);
+    for clause in &v.rescue {
+        visitor.visit_rescue_clause(clause);
+    }
+    visitor.visit_node(&v.otherwise);
+}
+
+pub fn walk_rescue_clause<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast RescueClause) {
+    for node in &v.exceptions {
+        visitor.visit_node(node);
+    }
+    visitor.visit_node(&v.assigned_to);
+    visitor.visit_node(&v.then);
+}
+
+pub fn walk_case<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast Case) {
+    visitor.visit_node(&v.expr);
+    for clause in &v.when {
+        visitor.visit_when_clause(clause);
+    }
+    visitor.visit_node(&v.otherwise);
+}
+
+pub fn walk_when_clause<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, v: &'ast WhenClause) {
+    for node in &v.when {
+        visitor.visit_node(node);
+    }
+    visitor.visit_node(&v.then);
+}
+
+/// Traverses a syntax tree by mutable reference, rewriting nodes in place
+pub trait VisitMut {
+    fn visit_program_mut(&mut self, v: &mut Program) {
+        walk_program_mut(self, v)
+    }
+    fn visit_node_mut(&mut self, v: &mut Node) {
+        walk_node_mut(self, v)
+    }
+    fn visit_conditional_mut(&mut self, v: &mut Conditional) {
+        walk_conditional_mut(self, v)
+    }
+    fn visit_logical_and_mut(&mut self, v: &mut LogicalAnd) {
+        walk_logical_and_mut(self, v)
+    }
+    fn visit_logical_or_mut(&mut self, v: &mut LogicalOr) {
+        walk_logical_or_mut(self, v)
+    }
+    fn visit_logical_not_mut(&mut self, v: &mut LogicalNot) {
+        walk_logical_not_mut(self, v)
+    }
+    fn visit_literal_mut(&mut self, _v: &mut Literal) {}
+    fn visit_identifier_mut(&mut self, _v: &mut Identifier) {}
+    fn visit_interpolated_mut(&mut self, v: &mut Interpolated) {
+        walk_interpolated_mut(self, v)
+    }
+    fn visit_concatenated_mut(&mut self, v: &mut Concatenated) {
+        walk_concatenated_mut(self, v)
+    }
+    fn visit_binary_op_mut(&mut self, v: &mut BinaryOp) {
+        walk_binary_op_mut(self, v)
+    }
+    fn visit_unary_op_mut(&mut self, v: &mut UnaryOp) {
+        walk_unary_op_mut(self, v)
+    }
+    fn visit_segment_mut(&mut self, v: &mut Segment) {
+        walk_segment_mut(self, v)
+    }
+    fn visit_ranged_mut(&mut self, v: &mut Ranged) {
+        walk_ranged_mut(self, v)
+    }
+    fn visit_association_mut(&mut self, v: &mut Association) {
+        walk_association_mut(self, v)
+    }
+    fn visit_alias_mut(&mut self, v: &mut Alias) {
+        walk_alias_mut(self, v)
+    }
+    fn visit_undef_mut(&mut self, v: &mut Undef) {
+        walk_undef_mut(self, v)
+    }
+    fn visit_method_name_mut(&mut self, v: &mut MethodName) {
+        walk_method_name_mut(self, v)
+    }
+    fn visit_loop_mut(&mut self, v: &mut Loop) {
+        walk_loop_mut(self, v)
+    }
+    fn visit_rescue_mut(&mut self, v: &mut Rescue) {
+        walk_rescue_mut(self, v)
+    }
+    fn visit_rescue_clause_mut(&mut self, v: &mut RescueClause) {
+        walk_rescue_clause_mut(self, v)
+    }
+    fn visit_case_mut(&mut self, v: &mut Case) {
+        walk_case_mut(self, v)
+    }
+    fn visit_when_clause_mut(&mut self, v: &mut WhenClause) {
+        walk_when_clause_mut(self, v)
+    }
+}
+
+pub fn walk_program_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Program) {
+    for node in &mut v.begin_blocks {
+        visitor.visit_node_mut(node);
+    }
+    visitor.visit_node_mut(&mut v.program);
+}
+
+pub fn walk_node_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Node) {
+    match &mut v.kind {
+        NodeKind::Conditional(v) => visitor.visit_conditional_mut(v),
+        NodeKind::LogicalAnd(v) => visitor.visit_logical_and_mut(v),
+        NodeKind::LogicalOr(v) => visitor.visit_logical_or_mut(v),
+        NodeKind::LogicalNot(v) => visitor.visit_logical_not_mut(v),
+        NodeKind::Literal(v) => visitor.visit_literal_mut(v),
+        NodeKind::Identifier(v) => visitor.visit_identifier_mut(v),
+        NodeKind::Interpolated(v) => visitor.visit_interpolated_mut(v),
+        NodeKind::Concatenated(v) => visitor.visit_concatenated_mut(v),
+        NodeKind::BinaryOp(v) => visitor.visit_binary_op_mut(v),
+        NodeKind::UnaryOp(v) => visitor.visit_unary_op_mut(v),
+        NodeKind::Segment(v) => visitor.visit_segment_mut(v),
+        NodeKind::Ranged(v) => visitor.visit_ranged_mut(v),
+        NodeKind::Hash(v) => {
+            for association in v {
+                visitor.visit_association_mut(association);
+            }
+        }
+        NodeKind::Alias(v) => visitor.visit_alias_mut(v),
+        NodeKind::Undef(v) => visitor.visit_undef_mut(v),
+        NodeKind::Loop(v) => visitor.visit_loop_mut(v),
+        NodeKind::Rescue(v) => visitor.visit_rescue_mut(v),
+        NodeKind::Case(v) => visitor.visit_case_mut(v),
+        NodeKind::Block(v)
+        | NodeKind::Array(v)
+        | NodeKind::Return(v)
+        | NodeKind::Break(v)
+        | NodeKind::Next(v) => {
+            for node in v {
+                visitor.visit_node_mut(node);
+            }
+        }
+        NodeKind::BlockArg(v)
+        | NodeKind::Defined(v)
+        | NodeKind::Splat(v)
+        | NodeKind::Begin(v)
+        | NodeKind::EndBlock(v) => visitor.visit_node_mut(v),
+        NodeKind::None
+        | NodeKind::Comment(_)
+        | NodeKind::Nil
+        | NodeKind::Self_
+        | NodeKind::File(_)
+        | NodeKind::Line(_)
+        | NodeKind::Encoding(_)
+        | NodeKind::Redo
+        | NodeKind::Retry
+        | NodeKind::EndOfProgram(_)
+        | NodeKind::Error(_)
+        | NodeKind::Placeholder => {}
+    }
+}
+
+pub fn walk_conditional_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Conditional) {
+    visitor.visit_node_mut(&mut v.cond);
+    visitor.visit_node_mut(&mut v.then);
+    visitor.visit_node_mut(&mut v.otherwise);
+}
+
+pub fn walk_logical_and_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut LogicalAnd) {
+    visitor.visit_node_mut(&mut v.first);
+    visitor.visit_node_mut(&mut v.second);
+}
+
+pub fn walk_logical_or_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut LogicalOr) {
+    visitor.visit_node_mut(&mut v.first);
+    visitor.visit_node_mut(&mut v.second);
+}
+
+pub fn walk_logical_not_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut LogicalNot) {
+    visitor.visit_node_mut(&mut v.expr);
+}
+
+pub fn walk_interpolated_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Interpolated) {
+    match v {
+        Interpolated::Command(v) | Interpolated::String(v) | Interpolated::Symbol(v) => {
+            for node in v {
+                visitor.visit_node_mut(node);
+            }
+        }
+    }
+}
+
+pub fn walk_concatenated_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Concatenated) {
+    visitor.visit_node_mut(&mut v.value);
+    for node in &mut v.parts {
+        visitor.visit_node_mut(node);
+    }
+}
+
+pub fn walk_binary_op_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut BinaryOp) {
+    visitor.visit_node_mut(&mut v.lhs);
+    visitor.visit_node_mut(&mut v.rhs);
+}
+
+pub fn walk_unary_op_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut UnaryOp) {
+    visitor.visit_node_mut(&mut v.rhs);
+}
+
+pub fn walk_segment_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Segment) {
+    if let Segment::Expr(v) = v {
+        visitor.visit_node_mut(v);
+    }
+}
+
+pub fn walk_ranged_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Ranged) {
+    visitor.visit_node_mut(&mut v.from);
+    visitor.visit_node_mut(&mut v.to);
+}
+
+pub fn walk_association_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Association) {
+    match v {
+        Association::Pair(pair) => {
+            visitor.visit_node_mut(&mut pair.key);
+            visitor.visit_node_mut(&mut pair.value);
+        }
+        Association::DoubleSplat(v) => visitor.visit_node_mut(v),
+    }
+}
+
+pub fn walk_alias_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Alias) {
+    match v {
+        Alias::Method { to, from } => {
+            visitor.visit_method_name_mut(to);
+            visitor.visit_method_name_mut(from);
+        }
+        Alias::GlobalVariable { to, from } => {
+            visitor.visit_identifier_mut(to);
+            visitor.visit_identifier_mut(from);
+        }
+    }
+}
+
+pub fn walk_undef_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Undef) {
+    for name in &mut v.list {
+        visitor.visit_method_name_mut(name);
+    }
+}
+
+pub fn walk_method_name_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut MethodName) {
+    if let MethodName::DynamicSymbol(v) = v {
+        for node in v {
+            visitor.visit_node_mut(node);
+        }
+    }
+}
+
+pub fn walk_loop_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Loop) {
+    visitor.visit_node_mut(&mut v.cond);
+    for node in v.bindings.iter_mut().flatten() {
+        visitor.visit_node_mut(node);
+    }
+    visitor.visit_node_mut(&mut v.body);
+}
+
+pub fn walk_rescue_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Rescue) {
+    visitor.visit_node_mut(&mut v.body);
+    for clause in &mut v.rescue {
+        visitor.visit_rescue_clause_mut(clause);
+    }
+    visitor.visit_node_mut(&mut v.otherwise);
+}
+
+pub fn walk_rescue_clause_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut RescueClause) {
+    for node in &mut v.exceptions {
+        visitor.visit_node_mut(node);
+    }
+    visitor.visit_node_mut(&mut v.assigned_to);
+    visitor.visit_node_mut(&mut v.then);
+}
+
+pub fn walk_case_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut Case) {
+    visitor.visit_node_mut(&mut v.expr);
+    for clause in &mut v.when {
+        visitor.visit_when_clause_mut(clause);
+    }
+    visitor.visit_node_mut(&mut v.otherwise);
+}
+
+pub fn walk_when_clause_mut<V: VisitMut + ?Sized>(visitor: &mut V, v: &mut WhenClause) {
+    for node in &mut v.when {
+        visitor.visit_node_mut(node);
+    }
+    visitor.visit_node_mut(&mut v.then);
+}
+
+/// Rebuilds a syntax tree by value
+///
+/// Folding a node preserves its span, so a folder that replaces a node's kind keeps the region of
+/// source that the original node was parsed from.
+pub trait Fold {
+    fn fold_program(&mut self, v: Program) -> Program {
+        fold_program(self, v)
+    }
+    fn fold_node(&mut self, v: Node) -> Node {
+        fold_node(self, v)
+    }
+    fn fold_conditional(&mut self, v: Conditional) -> Conditional {
+        fold_conditional(self, v)
+    }
+    fn fold_logical_and(&mut self, v: LogicalAnd) -> LogicalAnd {
+        fold_logical_and(self, v)
+    }
+    fn fold_logical_or(&mut self, v: LogicalOr) -> LogicalOr {
+        fold_logical_or(self, v)
+    }
+    fn fold_logical_not(&mut self, v: LogicalNot) -> LogicalNot {
+        fold_logical_not(self, v)
+    }
+    fn fold_literal(&mut self, v: Literal) -> Literal {
+        v
+    }
+    fn fold_identifier(&mut self, v: Identifier) -> Identifier {
+        v
+    }
+    fn fold_interpolated(&mut self, v: Interpolated) -> Interpolated {
+        fold_interpolated(self, v)
+    }
+    fn fold_concatenated(&mut self, v: Concatenated) -> Concatenated {
+        fold_concatenated(self, v)
+    }
+    fn fold_binary_op(&mut self, v: BinaryOp) -> BinaryOp {
+        fold_binary_op(self, v)
+    }
+    fn fold_unary_op(&mut self, v: UnaryOp) -> UnaryOp {
+        fold_unary_op(self, v)
+    }
+    fn fold_segment(&mut self, v: Segment) -> Segment {
+        fold_segment(self, v)
+    }
+    fn fold_ranged(&mut self, v: Ranged) -> Ranged {
+        fold_ranged(self, v)
+    }
+    fn fold_association(&mut self, v: Association) -> Association {
+        fold_association(self, v)
+    }
+    fn fold_alias(&mut self, v: Alias) -> Alias {
+        fold_alias(self, v)
+    }
+    fn fold_undef(&mut self, v: Undef) -> Undef {
+        fold_undef(self, v)
+    }
+    fn fold_method_name(&mut self, v: MethodName) -> MethodName {
+        fold_method_name(self, v)
+    }
+    fn fold_loop(&mut self, v: Loop) -> Loop {
+        fold_loop(self, v)
+    }
+    fn fold_rescue(&mut self, v: Rescue) -> Rescue {
+        fold_rescue(self, v)
+    }
+    fn fold_rescue_clause(&mut self, v: RescueClause) -> RescueClause {
+        fold_rescue_clause(self, v)
+    }
+    fn fold_case(&mut self, v: Case) -> Case {
+        fold_case(self, v)
+    }
+    fn fold_when_clause(&mut self, v: WhenClause) -> WhenClause {
+        fold_when_clause(self, v)
+    }
+}
+
+/// Folds each node of a list
+fn fold_nodes<F: Fold + ?Sized>(folder: &mut F, v: Vec<Node>) -> Vec<Node> {
+    v.into_iter().map(|node| folder.fold_node(node)).collect()
+}
+
+/// Folds a boxed node, reusing its allocation
+fn fold_box<F: Fold + ?Sized>(folder: &mut F, mut v: Box<Node>) -> Box<Node> {
+    let node = std::mem::replace(&mut *v, Node::empty());
+    *v = folder.fold_node(node);
+    v
+}
+
+pub fn fold_program<F: Fold + ?Sized>(folder: &mut F, v: Program) -> Program {
+    Program {
+        begin_blocks: fold_nodes(folder, v.begin_blocks),
+        program: folder.fold_node(v.program),
+        ..v
+    }
+}
+
+pub fn fold_node<F: Fold + ?Sized>(folder: &mut F, v: Node) -> Node {
+    let kind = match v.kind {
+        NodeKind::Conditional(v) => NodeKind::Conditional(folder.fold_conditional(v)),
+        NodeKind::LogicalAnd(v) => NodeKind::LogicalAnd(folder.fold_logical_and(v)),
+        NodeKind::LogicalOr(v) => NodeKind::LogicalOr(folder.fold_logical_or(v)),
+        NodeKind::LogicalNot(v) => NodeKind::LogicalNot(folder.fold_logical_not(v)),
+        NodeKind::Literal(v) => NodeKind::Literal(folder.fold_literal(v)),
+        NodeKind::Identifier(v) => NodeKind::Identifier(folder.fold_identifier(v)),
+        NodeKind::Interpolated(v) => NodeKind::Interpolated(folder.fold_interpolated(v)),
+        NodeKind::Concatenated(v) => NodeKind::Concatenated(folder.fold_concatenated(v)),
+        NodeKind::BinaryOp(v) => NodeKind::BinaryOp(folder.fold_binary_op(v)),
+        NodeKind::UnaryOp(v) => NodeKind::UnaryOp(folder.fold_unary_op(v)),
+        NodeKind::Segment(v) => NodeKind::Segment(folder.fold_segment(v)),
+        NodeKind::Ranged(v) => NodeKind::Ranged(folder.fold_ranged(v)),
+        NodeKind::Hash(v) => NodeKind::Hash(
+            v.into_iter()
+                .map(|association| folder.fold_association(association))
+                .collect(),
+        ),
+        NodeKind::Alias(v) => NodeKind::Alias(folder.fold_alias(v)),
+        NodeKind::Undef(v) => NodeKind::Undef(folder.fold_undef(v)),
+        NodeKind::Loop(v) => NodeKind::Loop(folder.fold_loop(v)),
+        NodeKind::Rescue(v) => NodeKind::Rescue(folder.fold_rescue(v)),
+        NodeKind::Case(v) => NodeKind::Case(folder.fold_case(v)),
+        NodeKind::Block(v) => NodeKind::Block(fold_nodes(folder, v)),
+        NodeKind::Array(v) => NodeKind::Array(fold_nodes(folder, v)),
+        NodeKind::Return(v) => NodeKind::Return(fold_nodes(folder, v)),
+        NodeKind::Break(v) => NodeKind::Break(fold_nodes(folder, v)),
+        NodeKind::Next(v) => NodeKind::Next(fold_nodes(folder, v)),
+        NodeKind::BlockArg(v) => NodeKind::BlockArg(fold_box(folder, v)),
+        NodeKind::Defined(v) => NodeKind::Defined(fold_box(folder, v)),
+        NodeKind::Splat(v) => NodeKind::Splat(fold_box(folder, v)),
+        NodeKind::Begin(v) => NodeKind::Begin(fold_box(folder, v)),
+        NodeKind::EndBlock(v) => NodeKind::EndBlock(fold_box(folder, v)),
+        kind @ NodeKind::None
+        | kind @ NodeKind::Comment(_)
+        | kind @ NodeKind::Nil
+        | kind @ NodeKind::Self_
+        | kind @ NodeKind::File(_)
+        | kind @ NodeKind::Line(_)
+        | kind @ NodeKind::Encoding(_)
+        | kind @ NodeKind::Redo
+        | kind @ NodeKind::Retry
+        | kind @ NodeKind::EndOfProgram(_)
+        | kind @ NodeKind::Error(_)
+        | kind @ NodeKind::Placeholder => kind,
+    };
+    Node::with_span(kind, v.span)
+}
+
+pub fn fold_conditional<F: Fold + ?Sized>(folder: &mut F, v: Conditional) -> Conditional {
+    Conditional {
+        kind: v.kind,
+        cond: fold_box(folder, v.cond),
+        then: fold_box(folder, v.then),
+        otherwise: fold_box(folder, v.otherwise),
+    }
+}
+
+pub fn fold_logical_and<F: Fold + ?Sized>(folder: &mut F, v: LogicalAnd) -> LogicalAnd {
+    LogicalAnd {
+        first: fold_box(folder, v.first),
+        second: fold_box(folder, v.second),
+    }
+}
+
+pub fn fold_logical_or<F: Fold + ?Sized>(folder: &mut F, v: LogicalOr) -> LogicalOr {
+    LogicalOr {
+        first: fold_box(folder, v.first),
+        second: fold_box(folder, v.second),
+    }
+}
+
+pub fn fold_logical_not<F: Fold + ?Sized>(folder: &mut F, v: LogicalNot) -> LogicalNot {
+    LogicalNot {
+        expr: fold_box(folder, v.expr),
+    }
+}
+
+pub fn fold_interpolated<F: Fold + ?Sized>(folder: &mut F, v: Interpolated) -> Interpolated {
+    match v {
+        Interpolated::Command(v) => Interpolated::Command(fold_nodes(folder, v)),
+        Interpolated::String(v) => Interpolated::String(fold_nodes(folder, v)),
+        Interpolated::Symbol(v) => Interpolated::Symbol(fold_nodes(folder, v)),
+    }
+}
+
+pub fn fold_concatenated<F: Fold + ?Sized>(folder: &mut F, v: Concatenated) -> Concatenated {
+    Concatenated {
+        value: fold_box(folder, v.value),
+        parts: fold_nodes(folder, v.parts),
+    }
+}
+
+pub fn fold_binary_op<F: Fold + ?Sized>(folder: &mut F, v: BinaryOp) -> BinaryOp {
+    BinaryOp {
+        op: v.op,
+        lhs: fold_box(folder, v.lhs),
+        rhs: fold_box(folder, v.rhs),
+    }
+}
+
+pub fn fold_unary_op<F: Fold + ?Sized>(folder: &mut F, v: UnaryOp) -> UnaryOp {
+    UnaryOp {
+        op: v.op,
+        rhs: fold_box(folder, v.rhs),
+    }
+}
+
+pub fn fold_segment<F: Fold + ?Sized>(folder: &mut F, v: Segment) -> Segment {
+    match v {
+        Segment::Expr(v) => Segment::Expr(fold_box(folder, v)),
+        v => v,
+    }
+}
+
+pub fn fold_ranged<F: Fold + ?Sized>(folder: &mut F, v: Ranged) -> Ranged {
+    Ranged {
+        from: fold_box(folder, v.from),
+        to: fold_box(folder, v.to),
+        exclusive: v.exclusive,
+    }
+}
+
+pub fn fold_association<F: Fold + ?Sized>(folder: &mut F, v: Association) -> Association {
+    match v {
+        Association::Pair(pair) => Association::Pair(Pair {
+            key: fold_box(folder, pair.key),
+            value: fold_box(folder, pair.value),
+            syntax: pair.syntax,
+        }),
+        Association::DoubleSplat(v) => Association::DoubleSplat(fold_box(folder, v)),
+    }
+}
+
+pub fn fold_alias<F: Fold + ?Sized>(folder: &mut F, v: Alias) -> Alias {
+    match v {
+        Alias::Method { to, from } => Alias::Method {
+            to: folder.fold_method_name(to),
+            from: folder.fold_method_name(from),
+        },
+        Alias::GlobalVariable { to, from } => Alias::GlobalVariable {
+            to: folder.fold_identifier(to),
+            from: folder.fold_identifier(from),
+        },
+    }
+}
+
+pub fn fold_undef<F: Fold + ?Sized>(folder: &mut F, v: Undef) -> Undef {
+    Undef {
+        list: v
+            .list
+            .into_iter()
+            .map(|name| folder.fold_method_name(name))
+            .collect(),
+    }
+}
+
+pub fn fold_method_name<F: Fold + ?Sized>(folder: &mut F, v: MethodName) -> MethodName {
+    match v {
+        MethodName::DynamicSymbol(v) => MethodName::DynamicSymbol(fold_nodes(folder, v)),
+        v => v,
+    }
+}
+
+pub fn fold_loop<F: Fold + ?Sized>(folder: &mut F, v: Loop) -> Loop {
+    Loop {
+        kind: v.kind,
+        cond: fold_box(folder, v.cond),
+        bindings: v.bindings.map(|bindings| fold_nodes(folder, bindings)),
+        body: fold_box(folder, v.body),
+    }
+}
+
+pub fn fold_rescue<F: Fold + ?Sized>(folder: &mut F, v: Rescue) -> Rescue {
+    Rescue {
+        body: fold_box(folder, v.body),
+        rescue: v
+            .rescue
+            .into_iter()
+            .map(|clause| folder.fold_rescue_clause(clause))
+            .collect(),
+        otherwise: fold_box(folder, v.otherwise),
+    }
+}
+
+pub fn fold_rescue_clause<F: Fold + ?Sized>(folder: &mut F, v: RescueClause) -> RescueClause {
+    RescueClause {
+        exceptions: fold_nodes(folder, v.exceptions),
+        assigned_to: fold_box(folder, v.assigned_to),
+        then: fold_box(folder, v.then),
+    }
+}
+
+pub fn fold_case<F: Fold + ?Sized>(folder: &mut F, v: Case) -> Case {
+    Case {
+        expr: fold_box(folder, v.expr),
+        when: v
+            .when
+            .into_iter()
+            .map(|clause| folder.fold_when_clause(clause))
+            .collect(),
+        otherwise: fold_box(folder, v.otherwise),
+    }
+}
+
+pub fn fold_when_clause<F: Fold + ?Sized>(folder: &mut F, v: WhenClause) -> WhenClause {
+    WhenClause {
+        when: fold_nodes(folder, v.when),
+        then: fold_box(folder, v.then),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects the names of the identifiers in a tree
+    #[derive(Default)]
+    struct Names<'ast>(Vec<&'ast str>);
+
+    impl<'ast> Visit<'ast> for Names<'ast> {
+        fn visit_identifier(&mut self, v: &'ast Identifier) {
+            self.0.push(&v.name);
+        }
+    }
+
+    /// Renames every local variable
+    struct Rename;
+
+    impl VisitMut for Rename {
+        fn visit_identifier_mut(&mut self, v: &mut Identifier) {
+            if v.kind == IdentifierKind::LocalVariable {
+                v.name = v.name.to_uppercase();
+            }
+        }
+    }
+
+    /// Replaces additions of integers with their sum
+    struct ConstantFold;
+
+    impl Fold for ConstantFold {
+        fn fold_node(&mut self, v: Node) -> Node {
+            let v = fold_node(self, v);
+            if let NodeKind::BinaryOp(BinaryOp {
+                op: BinaryOpKind::Add,
+                lhs,
+                rhs,
+            }) = &v.kind
+            {
+                if let (
+                    NodeKind::Literal(Literal::Integer(a)),
+                    NodeKind::Literal(Literal::Integer(b)),
+                ) = (&lhs.kind, &rhs.kind)
+                {
+                    return Node::with_span(NodeKind::Literal(Literal::Integer(a + b)), v.span);
+                }
+            }
+            v
+        }
+    }
+
+    fn block(nodes: Vec<Node>) -> Node {
+        Node::new(NodeKind::Block(nodes))
+    }
+
+    fn tree() -> Node {
+        block(vec![
+            Node::conditional(
+                ConditionalKind::If,
+                Node::ident("a", IdentifierKind::LocalVariable),
+                Node::binary_op(Node::int(1), BinaryOpKind::Add, Node::int(2)),
+                Node::array(vec![Node::ident("B", IdentifierKind::Constant)]),
+            ),
+            Node::binary_op(
+                Node::ident("c", IdentifierKind::LocalVariable),
+                BinaryOpKind::Add,
+                Node::binary_op(Node::int(3), BinaryOpKind::Add, Node::int(4)),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_children() {
+        let mut node = tree();
+        assert_eq!(node.children().count(), 2);
+        let first = node.children().next().unwrap();
+        assert_eq!(
+            first.children().next(),
+            Some(&Node::ident("a", IdentifierKind::LocalVariable))
+        );
+        for child in node.children_mut() {
+            *child = Node::new(NodeKind::Nil);
+        }
+        let nil = Node::new(NodeKind::Nil);
+        assert_eq!(node, block(vec![nil.clone(), nil]));
+    }
+
+    #[test]
+    fn test_visit() {
+        let node = tree();
+        let mut names = Names::default();
+        names.visit_node(&node);
+        assert_eq!(names.0, vec!["a", "B", "c"]);
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        let mut node = tree();
+        Rename.visit_node_mut(&mut node);
+        let mut names = Names::default();
+        names.visit_node(&node);
+        assert_eq!(names.0, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(
+            ConstantFold.fold_node(tree()),
+            block(vec![
+                Node::conditional(
+                    ConditionalKind::If,
+                    Node::ident("a", IdentifierKind::LocalVariable),
+                    Node::int(3),
+                    Node::array(vec![Node::ident("B", IdentifierKind::Constant)]),
+                ),
+                Node::binary_op(
+                    Node::ident("c", IdentifierKind::LocalVariable),
+                    BinaryOpKind::Add,
+                    Node::int(7),
+                ),
+            ])
+        );
+    }
+}